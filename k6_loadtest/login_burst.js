@@ -0,0 +1,57 @@
+// Login burst while probing /health.
+//
+// Password hashing is deliberately slow. If it runs on the async workers, a burst of logins
+// stalls every other request and the health threshold below fails. With hashing on the
+// bounded blocking pool, /health stays fast and excess logins are shed with 503.
+//
+//   k6 run -e BASE_URL=http://localhost:3000 k6_loadtest/login_burst.js
+import http from 'k6/http';
+import { check } from 'k6';
+
+const BASE_URL = __ENV.BASE_URL || 'http://localhost:3000';
+const EMAIL = __ENV.EMAIL || `k6-${Date.now()}@example.com`;
+const PASSWORD = __ENV.PASSWORD || 'k6-password';
+const JSON_HEADERS = { headers: { 'Content-Type': 'application/json' } };
+
+export let options = {
+  scenarios: {
+    logins: {
+      executor: 'constant-arrival-rate',
+      exec: 'login',
+      rate: 200,
+      timeUnit: '1s',
+      duration: '30s',
+      preAllocatedVUs: 200,
+      maxVUs: 400,
+    },
+    health: {
+      executor: 'constant-arrival-rate',
+      exec: 'health',
+      rate: 10,
+      timeUnit: '1s',
+      duration: '30s',
+      preAllocatedVUs: 10,
+    },
+  },
+  thresholds: {
+    'http_req_duration{scenario:health}': ['p(95)<100'],
+    'checks{scenario:health}': ['rate==1'],
+    'checks{scenario:logins}': ['rate>0.99'],
+  },
+};
+
+export function setup() {
+  const body = JSON.stringify({ username: 'k6-user', email: EMAIL, password: PASSWORD });
+  http.post(`${BASE_URL}/api/v1/auth/register`, body, JSON_HEADERS);
+}
+
+export function login() {
+  const body = JSON.stringify({ email: EMAIL, password: PASSWORD });
+  const res = http.post(`${BASE_URL}/api/v1/auth/login`, body, JSON_HEADERS);
+  check(res, { 'login succeeded or was shed': (r) => r.status === 200 || r.status === 503 });
+}
+
+export function health() {
+  const res = http.get(`${BASE_URL}/health`);
+  check(res, { 'status was 200': (r) => r.status === 200 });
+}
@@ -3,6 +3,41 @@ use figment::{
     Figment,
 };
 use serde::Deserialize;
+use std::collections::HashMap;
+use crate::models::permission::Permission;
+use crate::utils::password_policy::CharacterClass;
+
+/// An asymmetric JWT key. Give the PEM inline or as a file path.
+#[derive(Debug, Deserialize, Clone)]
+pub struct JwtKeyConfig {
+    pub kid: String,
+    pub algorithm: jsonwebtoken::Algorithm,
+    /// Only needed for the key named by `jwt_signing_kid`; retired keys keep just the public half.
+    #[serde(default)]
+    pub private_key_pem: Option<String>,
+    #[serde(default)]
+    pub private_key_file: Option<String>,
+    #[serde(default)]
+    pub public_key_pem: Option<String>,
+    #[serde(default)]
+    pub public_key_file: Option<String>,
+}
+
+/// An OpenID Connect provider for social login, e.g.
+/// `OIDC_PROVIDERS={google={issuer="https://accounts.google.com",client_id="..",client_secret="..",redirect_uri=".."}}`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct OidcProviderConfig {
+    /// Endpoints are discovered from `{issuer}/.well-known/openid-configuration`.
+    pub issuer: String,
+    pub client_id: String,
+    /// Omit for public clients, which then rely on PKCE alone.
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    /// Registered callback, usually a web client page that posts `code` and `state` back to us.
+    pub redirect_uri: String,
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+}
 
 #[allow(dead_code)]
 #[derive(Debug, Deserialize, Clone)]
@@ -14,11 +49,125 @@ pub struct AppConfig {
     pub redis_password: Option<String>,
     pub redis_db: i64,
     pub jwt_secret: String,
+    #[serde(default = "default_jwt_issuer")]
+    pub jwt_issuer: String,
+    #[serde(default = "default_jwt_audience")]
+    pub jwt_audience: String,
+    #[serde(default = "default_jwt_access_ttl_secs")]
+    pub jwt_access_ttl_secs: i64,
+    #[serde(default = "default_jwt_refresh_ttl_secs")]
+    pub jwt_refresh_ttl_secs: u64,
+    /// Verification keys. When empty, tokens are signed and verified with HS256 and `jwt_secret`.
+    #[serde(default)]
+    pub jwt_keys: Vec<JwtKeyConfig>,
+    /// `kid` of the entry in `jwt_keys` that signs new tokens.
+    #[serde(default)]
+    pub jwt_signing_kid: Option<String>,
     pub aws_region: String,
     pub aws_access_key_id: String,
     pub aws_secret_access_key: String,
     pub aws_bucket_name: String,
     pub firebase_credentials_file: String,
+    /// Base URL of the web client, used to build links sent by email.
+    #[serde(default = "default_frontend_url")]
+    pub frontend_url: String,
+    /// Public base URL of this API, used for links that hit an endpoint directly.
+    #[serde(default = "default_api_base_url")]
+    pub api_base_url: String,
+    #[serde(default = "default_password_reset_ttl_secs")]
+    pub password_reset_ttl_secs: u64,
+    /// Argon2id memory cost in KiB. Raising any of these rehashes passwords on next login.
+    #[serde(default = "default_password_hash_memory_kib")]
+    pub password_hash_memory_kib: u32,
+    #[serde(default = "default_password_hash_iterations")]
+    pub password_hash_iterations: u32,
+    #[serde(default = "default_password_hash_parallelism")]
+    pub password_hash_parallelism: u32,
+    #[serde(default = "default_password_min_length")]
+    pub password_min_length: usize,
+    /// Character classes every new password must contain, e.g. `["uppercase","digit"]`.
+    #[serde(default)]
+    pub password_required_classes: Vec<CharacterClass>,
+    /// Reject passwords that contain the username or email address.
+    #[serde(default = "default_true")]
+    pub password_reject_personal_info: bool,
+    /// SHA-1 hashes of breached passwords, one per line. No check when unset.
+    #[serde(default)]
+    pub password_breached_list_file: Option<String>,
+    /// Hashes computed at once on the blocking pool. Defaults to the number of cores.
+    #[serde(default = "crate::utils::blocking::default_concurrency")]
+    pub password_hash_max_concurrency: usize,
+    /// Hashing jobs allowed to wait for a worker; beyond this requests get a 503.
+    #[serde(default = "default_password_hash_max_queue")]
+    pub password_hash_max_queue: usize,
+    /// Reject logins until the user has confirmed their email address.
+    #[serde(default)]
+    pub require_email_verification: bool,
+    #[serde(default = "default_email_verification_ttl_secs")]
+    pub email_verification_ttl_secs: i64,
+    /// Minimum delay between two verification emails for the same address.
+    #[serde(default = "default_email_verification_resend_secs")]
+    pub email_verification_resend_secs: u64,
+    /// Failed logins per email before the account is locked.
+    #[serde(default = "default_login_max_attempts")]
+    pub login_max_attempts: i64,
+    /// Failed logins per client IP, across all emails, before the IP is locked.
+    #[serde(default = "default_login_ip_max_attempts")]
+    pub login_ip_max_attempts: i64,
+    /// How long a failure is remembered, counted from the most recent one.
+    #[serde(default = "default_login_failure_window_secs")]
+    pub login_failure_window_secs: u64,
+    /// First lockout duration. Every further failure doubles it, up to `login_lockout_max_secs`.
+    #[serde(default = "default_login_lockout_base_secs")]
+    pub login_lockout_base_secs: u64,
+    #[serde(default = "default_login_lockout_max_secs")]
+    pub login_lockout_max_secs: u64,
+    /// Read the client IP from the last X-Forwarded-For entry / X-Real-IP. Only enable behind
+    /// a trusted proxy that appends to X-Forwarded-For.
+    #[serde(default)]
+    pub trust_proxy_headers: bool,
+    /// Issuer label shown in authenticator apps.
+    #[serde(default = "default_mfa_issuer")]
+    pub mfa_issuer: String,
+    /// Base64 AES-256 key for TOTP secrets at rest. Derived from `jwt_secret` when unset, which
+    /// `MfaService::check_key` only accepts at startup while no user has a TOTP secret.
+    #[serde(default)]
+    pub mfa_encryption_key: Option<String>,
+    /// Lifetime of the token returned by login while the second factor is outstanding.
+    #[serde(default = "default_mfa_pending_ttl_secs")]
+    pub mfa_pending_ttl_secs: u64,
+    /// Enables `POST /auth/magic-link` and its callback. Off by default.
+    #[serde(default)]
+    pub magic_link_enabled: bool,
+    #[serde(default = "default_magic_link_ttl_secs")]
+    pub magic_link_ttl_secs: u64,
+    /// Minimum delay between two sign-in links for the same address.
+    #[serde(default = "default_magic_link_resend_secs")]
+    pub magic_link_resend_secs: u64,
+    /// Lifetime of tokens issued by `POST /admin/users/:id/impersonate`. They cannot be refreshed.
+    #[serde(default = "default_impersonation_ttl_secs")]
+    pub impersonation_ttl_secs: i64,
+    /// Page size of list endpoints when the request gives no `limit`.
+    #[serde(default = "default_pagination_default_limit")]
+    pub pagination_default_limit: u64,
+    /// Largest `limit` a list request may ask for.
+    #[serde(default = "default_pagination_max_limit")]
+    pub pagination_max_limit: u64,
+    /// Days a soft-deleted user is kept before an admin may purge it.
+    #[serde(default = "default_user_purge_retention_days")]
+    pub user_purge_retention_days: i64,
+    /// Social login providers, keyed by the name used in `/auth/oidc/:provider/...`.
+    #[serde(default)]
+    pub oidc_providers: HashMap<String, OidcProviderConfig>,
+    /// How long an OIDC authorization request may take before its state is forgotten.
+    #[serde(default = "default_oidc_state_ttl_secs")]
+    pub oidc_state_ttl_secs: u64,
+    /// How long a provider's discovery document and JWKS are reused before being fetched again.
+    #[serde(default = "default_oidc_cache_ttl_secs")]
+    pub oidc_cache_ttl_secs: u64,
+    /// Role name -> granted permissions, e.g. `ROLE_PERMISSIONS={user=["users:read"]}`.
+    #[serde(default = "default_role_permissions")]
+    pub role_permissions: HashMap<String, Vec<Permission>>,
     #[serde(default = "default_port")]
     pub port: u16,
     #[serde(default = "default_mode")]
@@ -33,7 +182,164 @@ fn default_mode() -> String {
     "production".to_string()
 }
 
+fn default_jwt_issuer() -> String {
+    "fldp-rust-backend".to_string()
+}
+
+fn default_jwt_audience() -> String {
+    "fldp-rust-backend-api".to_string()
+}
+
+fn default_jwt_access_ttl_secs() -> i64 {
+    15 * 60
+}
+
+fn default_jwt_refresh_ttl_secs() -> u64 {
+    30 * 24 * 60 * 60
+}
+
+fn default_frontend_url() -> String {
+    "http://localhost:5173".to_string()
+}
+
+fn default_api_base_url() -> String {
+    "http://localhost:3000".to_string()
+}
+
+fn default_password_reset_ttl_secs() -> u64 {
+    60 * 60
+}
+
+/// OWASP's Argon2id baseline: 19 MiB, 2 passes, 1 lane.
+fn default_password_hash_memory_kib() -> u32 {
+    19 * 1024
+}
+
+fn default_password_hash_iterations() -> u32 {
+    2
+}
+
+fn default_password_hash_parallelism() -> u32 {
+    1
+}
+
+fn default_password_min_length() -> usize {
+    8
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_password_hash_max_queue() -> usize {
+    crate::utils::blocking::DEFAULT_MAX_QUEUE
+}
+
+fn default_email_verification_ttl_secs() -> i64 {
+    24 * 60 * 60
+}
+
+fn default_email_verification_resend_secs() -> u64 {
+    60
+}
+
+fn default_login_max_attempts() -> i64 {
+    5
+}
+
+fn default_login_ip_max_attempts() -> i64 {
+    50
+}
+
+fn default_login_failure_window_secs() -> u64 {
+    60 * 60
+}
+
+fn default_login_lockout_base_secs() -> u64 {
+    30
+}
+
+fn default_login_lockout_max_secs() -> u64 {
+    60 * 60
+}
+
+fn default_mfa_issuer() -> String {
+    "FLDP Rust Backend".to_string()
+}
+
+fn default_mfa_pending_ttl_secs() -> u64 {
+    5 * 60
+}
+
+fn default_magic_link_ttl_secs() -> u64 {
+    10 * 60
+}
+
+fn default_magic_link_resend_secs() -> u64 {
+    60
+}
+
+fn default_impersonation_ttl_secs() -> i64 {
+    10 * 60
+}
+
+fn default_pagination_default_limit() -> u64 {
+    10
+}
+
+fn default_pagination_max_limit() -> u64 {
+    100
+}
+
+fn default_user_purge_retention_days() -> i64 {
+    30
+}
+
+fn default_oidc_scopes() -> Vec<String> {
+    vec!["openid".to_string(), "email".to_string(), "profile".to_string()]
+}
+
+fn default_oidc_state_ttl_secs() -> u64 {
+    10 * 60
+}
+
+fn default_oidc_cache_ttl_secs() -> u64 {
+    60 * 60
+}
+
+fn default_role_permissions() -> HashMap<String, Vec<Permission>> {
+    HashMap::from([
+        (
+            "admin".to_string(),
+            vec![Permission::UsersRead, Permission::UsersWrite, Permission::UsersAdmin],
+        ),
+        (
+            "user".to_string(),
+            vec![Permission::UsersRead, Permission::UsersWrite],
+        ),
+    ])
+}
+
 impl AppConfig {
+    /// Unknown roles get no permissions.
+    pub fn permissions_for(&self, role: &str) -> Vec<Permission> {
+        self.role_permissions.get(role).cloned().unwrap_or_default()
+    }
+
+    pub fn mfa_key(&self) -> anyhow::Result<[u8; 32]> {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+        use sha2::{Digest, Sha256};
+
+        match &self.mfa_encryption_key {
+            Some(encoded) => STANDARD
+                .decode(encoded)?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("MFA_ENCRYPTION_KEY must decode to 32 bytes")),
+            None => Ok(Sha256::digest(format!("mfa-encryption:{}", self.jwt_secret)).into()),
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
     pub fn new() -> Result<Self, figment::Error> {
         Figment::new()
             .merge(Toml::file("App.toml"))
@@ -45,11 +351,58 @@ impl AppConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use figment::providers::Serialized;
+
+    /// The settings without a default.
+    fn base() -> Figment {
+        Figment::new()
+            .merge(Serialized::default("mongodb_uri", "uri"))
+            .merge(Serialized::default("mongodb_name", "db"))
+            .merge(Serialized::default("redis_host", "localhost"))
+            .merge(Serialized::default("redis_port", 6379))
+            .merge(Serialized::default("redis_db", 0))
+            .merge(Serialized::default("jwt_secret", "secret"))
+            .merge(Serialized::default("aws_region", "us-east-1"))
+            .merge(Serialized::default("aws_access_key_id", "id"))
+            .merge(Serialized::default("aws_secret_access_key", "key"))
+            .merge(Serialized::default("aws_bucket_name", "bucket"))
+            .merge(Serialized::default("firebase_credentials_file", "file"))
+    }
 
     #[test]
     fn test_defaults() {
         assert_eq!(default_port(), 3000);
         assert_eq!(default_mode(), "production");
+        assert_eq!(default_jwt_issuer(), "fldp-rust-backend");
+        assert_eq!(default_jwt_audience(), "fldp-rust-backend-api");
+        assert_eq!(default_jwt_access_ttl_secs(), 900);
+        assert_eq!(default_jwt_refresh_ttl_secs(), 2592000);
+        assert_eq!(default_frontend_url(), "http://localhost:5173");
+        assert_eq!(default_api_base_url(), "http://localhost:3000");
+        assert_eq!(default_password_reset_ttl_secs(), 3600);
+        assert_eq!(default_password_hash_memory_kib(), 19456);
+        assert_eq!(default_password_hash_iterations(), 2);
+        assert_eq!(default_password_hash_parallelism(), 1);
+        assert_eq!(default_password_hash_max_queue(), 64);
+        assert_eq!(default_password_min_length(), 8);
+        assert_eq!(default_email_verification_ttl_secs(), 86400);
+        assert_eq!(default_email_verification_resend_secs(), 60);
+        assert_eq!(default_login_max_attempts(), 5);
+        assert_eq!(default_login_ip_max_attempts(), 50);
+        assert_eq!(default_login_failure_window_secs(), 3600);
+        assert_eq!(default_login_lockout_base_secs(), 30);
+        assert_eq!(default_login_lockout_max_secs(), 3600);
+        assert_eq!(default_mfa_issuer(), "FLDP Rust Backend");
+        assert_eq!(default_mfa_pending_ttl_secs(), 300);
+        assert_eq!(default_magic_link_ttl_secs(), 600);
+        assert_eq!(default_magic_link_resend_secs(), 60);
+        assert_eq!(default_impersonation_ttl_secs(), 600);
+        assert_eq!(default_pagination_default_limit(), 10);
+        assert_eq!(default_pagination_max_limit(), 100);
+        assert_eq!(default_user_purge_retention_days(), 30);
+        assert_eq!(default_oidc_scopes(), vec!["openid", "email", "profile"]);
+        assert_eq!(default_oidc_state_ttl_secs(), 600);
+        assert_eq!(default_oidc_cache_ttl_secs(), 3600);
     }
 
     #[test]
@@ -72,6 +425,9 @@ mod tests {
 
         assert_eq!(config.mongodb_uri, "uri");
         assert_eq!(config.port, 3000); // default
+        assert!(config.permissions_for("admin").contains(&Permission::UsersAdmin));
+        assert!(!config.permissions_for("user").contains(&Permission::UsersAdmin));
+        assert!(config.permissions_for("guest").is_empty());
     }
 
     #[test]
@@ -107,6 +463,71 @@ mod tests {
         assert_eq!(config.redis_password, Some("pass".to_string()));
     }
 
+    #[test]
+    fn test_config_role_permissions_override() {
+        let config: AppConfig = base()
+            .merge(Serialized::default("role_permissions", HashMap::from([
+                ("support", vec!["users:read"]),
+            ])))
+            .extract()
+            .unwrap();
+
+        assert_eq!(config.permissions_for("support"), vec![Permission::UsersRead]);
+        assert!(config.permissions_for("admin").is_empty());
+    }
+
+    #[test]
+    fn test_config_oidc_providers() {
+        let config: AppConfig = base()
+            .merge(Serialized::default("oidc_providers", HashMap::from([
+                ("google", HashMap::from([
+                    ("issuer", "https://accounts.google.com"),
+                    ("client_id", "client"),
+                    ("redirect_uri", "http://localhost:5173/oidc/callback"),
+                ])),
+            ])))
+            .extract()
+            .unwrap();
+
+        let google = &config.oidc_providers["google"];
+        assert_eq!(google.client_id, "client");
+        assert!(google.client_secret.is_none());
+        assert_eq!(google.scopes, default_oidc_scopes());
+    }
+
+    #[test]
+    fn test_config_password_policy() {
+        let config: AppConfig = base()
+            .merge(Serialized::default("password_required_classes", vec!["uppercase", "digit"]))
+            .extract()
+            .unwrap();
+
+        assert_eq!(
+            config.password_required_classes,
+            vec![CharacterClass::Uppercase, CharacterClass::Digit]
+        );
+        assert!(config.password_reject_personal_info);
+        assert!(config.password_breached_list_file.is_none());
+    }
+
+    #[test]
+    fn test_config_mfa_key() {
+        let derived: AppConfig = base().extract().unwrap();
+        assert_eq!(derived.mfa_key().unwrap(), derived.mfa_key().unwrap());
+
+        let explicit: AppConfig = base()
+            .merge(Serialized::default("mfa_encryption_key", "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE="))
+            .extract()
+            .unwrap();
+        assert_eq!(explicit.mfa_key().unwrap(), [1u8; 32]);
+
+        let short: AppConfig = base()
+            .merge(Serialized::default("mfa_encryption_key", "AQID"))
+            .extract()
+            .unwrap();
+        assert!(short.mfa_key().is_err());
+    }
+
     #[test]
     fn test_app_config_new() {
         let _ = AppConfig::new();
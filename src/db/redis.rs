@@ -5,6 +5,20 @@ use redis::AsyncCommands;
 pub trait IRedisProvider: Send + Sync {
     async fn set(&self, key: &str, value: &str) -> Result<(), redis::RedisError>;
     async fn get(&self, key: &str) -> Result<Option<String>, redis::RedisError>;
+    async fn set_ex(&self, key: &str, value: &str, ttl_secs: u64) -> Result<(), redis::RedisError>;
+    /// Sets the key only if it does not exist yet. Returns `true` when the key was written.
+    async fn set_nx_ex(&self, key: &str, value: &str, ttl_secs: u64) -> Result<bool, redis::RedisError>;
+    async fn del(&self, key: &str) -> Result<(), redis::RedisError>;
+    /// Atomically reads and deletes the key (GETDEL), so a value can only be consumed once.
+    async fn get_del(&self, key: &str) -> Result<Option<String>, redis::RedisError>;
+    /// INCR and EXPIRE in one MULTI block, so a counter never outlives its window. Returns the new value.
+    async fn incr_ex(&self, key: &str, ttl_secs: u64) -> Result<i64, redis::RedisError>;
+    /// Remaining lifetime in seconds. Negative when the key is missing or has no expiry.
+    async fn ttl(&self, key: &str) -> Result<i64, redis::RedisError>;
+    /// SADD and EXPIRE in one MULTI block, refreshing the set's lifetime on every insert.
+    async fn sadd_ex(&self, key: &str, member: &str, ttl_secs: u64) -> Result<(), redis::RedisError>;
+    async fn smembers(&self, key: &str) -> Result<Vec<String>, redis::RedisError>;
+    async fn srem(&self, key: &str, member: &str) -> Result<(), redis::RedisError>;
 }
 
 #[cfg(not(coverage))]
@@ -42,6 +56,129 @@ impl IRedisProvider for RedisProvider {
     async fn get(&self, _key: &str) -> Result<Option<String>, redis::RedisError> {
         Err(redis::RedisError::from((redis::ErrorKind::IoError, "Coverage dummy")))
     }
+
+    #[cfg(not(coverage))]
+    async fn set_ex(&self, key: &str, value: &str, ttl_secs: u64) -> Result<(), redis::RedisError> {
+        let mut conn = self.conn.clone();
+        conn.set_ex::<&str, &str, ()>(key, value, ttl_secs).await?;
+        Ok(())
+    }
+
+    #[cfg(coverage)]
+    async fn set_ex(&self, _key: &str, _value: &str, _ttl_secs: u64) -> Result<(), redis::RedisError> {
+        Err(redis::RedisError::from((redis::ErrorKind::IoError, "Coverage dummy")))
+    }
+
+    #[cfg(not(coverage))]
+    async fn set_nx_ex(&self, key: &str, value: &str, ttl_secs: u64) -> Result<bool, redis::RedisError> {
+        let mut conn = self.conn.clone();
+        let res: Option<String> = redis::cmd("SET")
+            .arg(key)
+            .arg(value)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl_secs)
+            .query_async(&mut conn)
+            .await?;
+        Ok(res.is_some())
+    }
+
+    #[cfg(coverage)]
+    async fn set_nx_ex(&self, _key: &str, _value: &str, _ttl_secs: u64) -> Result<bool, redis::RedisError> {
+        Err(redis::RedisError::from((redis::ErrorKind::IoError, "Coverage dummy")))
+    }
+
+    #[cfg(not(coverage))]
+    async fn del(&self, key: &str) -> Result<(), redis::RedisError> {
+        let mut conn = self.conn.clone();
+        conn.del::<&str, ()>(key).await?;
+        Ok(())
+    }
+
+    #[cfg(coverage)]
+    async fn del(&self, _key: &str) -> Result<(), redis::RedisError> {
+        Err(redis::RedisError::from((redis::ErrorKind::IoError, "Coverage dummy")))
+    }
+
+    #[cfg(not(coverage))]
+    async fn get_del(&self, key: &str) -> Result<Option<String>, redis::RedisError> {
+        let mut conn = self.conn.clone();
+        redis::cmd("GETDEL").arg(key).query_async(&mut conn).await
+    }
+
+    #[cfg(coverage)]
+    async fn get_del(&self, _key: &str) -> Result<Option<String>, redis::RedisError> {
+        Err(redis::RedisError::from((redis::ErrorKind::IoError, "Coverage dummy")))
+    }
+
+    #[cfg(not(coverage))]
+    async fn incr_ex(&self, key: &str, ttl_secs: u64) -> Result<i64, redis::RedisError> {
+        let mut conn = self.conn.clone();
+        let (count,): (i64,) = redis::pipe()
+            .atomic()
+            .incr(key, 1)
+            .expire(key, ttl_secs as i64)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+        Ok(count)
+    }
+
+    #[cfg(coverage)]
+    async fn incr_ex(&self, _key: &str, _ttl_secs: u64) -> Result<i64, redis::RedisError> {
+        Err(redis::RedisError::from((redis::ErrorKind::IoError, "Coverage dummy")))
+    }
+
+    #[cfg(not(coverage))]
+    async fn ttl(&self, key: &str) -> Result<i64, redis::RedisError> {
+        let mut conn = self.conn.clone();
+        conn.ttl(key).await
+    }
+
+    #[cfg(coverage)]
+    async fn ttl(&self, _key: &str) -> Result<i64, redis::RedisError> {
+        Err(redis::RedisError::from((redis::ErrorKind::IoError, "Coverage dummy")))
+    }
+
+    #[cfg(not(coverage))]
+    async fn sadd_ex(&self, key: &str, member: &str, ttl_secs: u64) -> Result<(), redis::RedisError> {
+        let mut conn = self.conn.clone();
+        redis::pipe()
+            .atomic()
+            .sadd(key, member)
+            .ignore()
+            .expire(key, ttl_secs as i64)
+            .ignore()
+            .query_async::<_, ()>(&mut conn)
+            .await
+    }
+
+    #[cfg(coverage)]
+    async fn sadd_ex(&self, _key: &str, _member: &str, _ttl_secs: u64) -> Result<(), redis::RedisError> {
+        Err(redis::RedisError::from((redis::ErrorKind::IoError, "Coverage dummy")))
+    }
+
+    #[cfg(not(coverage))]
+    async fn smembers(&self, key: &str) -> Result<Vec<String>, redis::RedisError> {
+        let mut conn = self.conn.clone();
+        conn.smembers(key).await
+    }
+
+    #[cfg(coverage)]
+    async fn smembers(&self, _key: &str) -> Result<Vec<String>, redis::RedisError> {
+        Err(redis::RedisError::from((redis::ErrorKind::IoError, "Coverage dummy")))
+    }
+
+    #[cfg(not(coverage))]
+    async fn srem(&self, key: &str, member: &str) -> Result<(), redis::RedisError> {
+        let mut conn = self.conn.clone();
+        conn.srem::<&str, &str, ()>(key, member).await
+    }
+
+    #[cfg(coverage)]
+    async fn srem(&self, _key: &str, _member: &str) -> Result<(), redis::RedisError> {
+        Err(redis::RedisError::from((redis::ErrorKind::IoError, "Coverage dummy")))
+    }
 }
 
 impl RedisProvider {
@@ -95,6 +232,15 @@ mod tests {
             let provider = RedisProvider::new("localhost", 6379, None, 0).await.unwrap();
             let _ = provider.set("k", "v").await;
             let _ = provider.get("k").await;
+            let _ = provider.set_ex("k", "v", 60).await;
+            let _ = provider.set_nx_ex("k", "v", 60).await;
+            let _ = provider.del("k").await;
+            let _ = provider.get_del("k").await;
+            let _ = provider.incr_ex("k", 60).await;
+            let _ = provider.ttl("k").await;
+            let _ = provider.sadd_ex("k", "m", 60).await;
+            let _ = provider.smembers("k").await;
+            let _ = provider.srem("k", "m").await;
         }
     }
 
@@ -0,0 +1,80 @@
+use crate::models::{api_key::ApiKey, permission::Permission};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKey {
+    #[validate(length(min = 1, max = 100, message = "Name must be 1-100 characters"))]
+    pub name: String,
+    /// Defaults to the admin creating the key.
+    pub owner_id: Option<String>,
+    #[validate(length(min = 1, message = "At least one scope is required"))]
+    pub scopes: Vec<Permission>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyResponse {
+    pub id: String,
+    pub name: String,
+    pub prefix: String,
+    pub owner_id: String,
+    pub scopes: Vec<Permission>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Returned once on creation. `key` cannot be retrieved again.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedApiKey {
+    pub key: String,
+    pub api_key: ApiKeyResponse,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            id: key.id,
+            name: key.name,
+            prefix: key.prefix,
+            owner_id: key.owner_id,
+            scopes: key.scopes,
+            expires_at: key.expires_at,
+            last_used_at: key.last_used_at,
+            revoked_at: key.revoked_at,
+            created_at: key.created_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_key_response_hides_hash() {
+        let key = ApiKey {
+            id: "key_1".into(),
+            name: "batch".into(),
+            prefix: "sk_1234abcd".into(),
+            key_hash: "secret-hash".into(),
+            owner_id: "user_1".into(),
+            scopes: vec![Permission::UsersRead],
+            expires_at: None,
+            last_used_at: None,
+            revoked_at: None,
+            created_at: Utc::now(),
+        };
+
+        let response: ApiKeyResponse = key.into();
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(!json.contains("secret-hash"));
+        assert_eq!(response.prefix, "sk_1234abcd");
+    }
+}
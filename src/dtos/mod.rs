@@ -1 +1,3 @@
 pub mod user;
+pub mod api_key;
+pub mod session;
@@ -0,0 +1,28 @@
+use crate::models::session::Session;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionResponse {
+    pub id: String,
+    pub ip: String,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    /// The session the request was made from.
+    pub current: bool,
+}
+
+impl SessionResponse {
+    pub fn new(session: Session, current_id: Option<&str>) -> Self {
+        Self {
+            current: current_id == Some(session.id.as_str()),
+            id: session.id,
+            ip: session.ip,
+            user_agent: session.user_agent,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+        }
+    }
+}
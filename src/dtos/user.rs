@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 use chrono::{DateTime, Utc};
+use crate::error::AppError;
+use crate::models::permission::Permission;
+use crate::models::user::{Role, UserFilter, UserSort};
 
 #[derive(Debug, Deserialize, Validate)]
 #[serde(rename_all = "camelCase")]
@@ -9,7 +12,7 @@ pub struct CreateUser {
     pub username: String,
     #[validate(email(message = "Invalid email format"))]
     pub email: String,
-    #[validate(length(min = 6, message = "Password must be at least 6 characters"))]
+    /// Checked against the password policy by the handler.
     pub password: String,
 }
 
@@ -22,6 +25,64 @@ pub struct UpdateUser {
     pub email: Option<String>,
 }
 
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePassword {
+    #[validate(length(min = 1))]
+    pub current_password: String,
+    /// Checked against the password policy by the handler.
+    pub new_password: String,
+}
+
+/// Filter and sort parameters of `GET /users`, next to the pagination ones.
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct ListUsersQuery {
+    pub role: Option<Role>,
+    pub created_from: Option<DateTime<Utc>>,
+    pub created_to: Option<DateTime<Utc>>,
+    #[validate(length(min = 1, max = 100))]
+    pub q: Option<String>,
+    /// `field:asc|desc`, see `UserSortField` for the allowed fields.
+    pub sort: Option<String>,
+}
+
+impl TryFrom<ListUsersQuery> for UserFilter {
+    type Error = AppError;
+
+    fn try_from(query: ListUsersQuery) -> Result<Self, Self::Error> {
+        query.validate().map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+        if let (Some(from), Some(to)) = (query.created_from, query.created_to) {
+            if from > to {
+                return Err(AppError::ValidationError("createdFrom must not be after createdTo".into()));
+            }
+        }
+        let sort = match query.sort.as_deref() {
+            Some(sort) => sort.parse().map_err(AppError::ValidationError)?,
+            None => UserSort::default(),
+        };
+
+        Ok(Self {
+            role: query.role,
+            created_from: query.created_from,
+            created_to: query.created_to,
+            search: query.q.map(|q| q.trim().to_string()).filter(|q| !q.is_empty()),
+            sort,
+        })
+    }
+}
+
+/// Identity asserted by an OpenID Connect provider after a successful sign-in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalProfile {
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub name: Option<String>,
+}
+
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct UserResponse {
@@ -29,10 +90,26 @@ pub struct UserResponse {
     pub username: String,
     pub email: String,
     pub role: String,
+    pub email_verified: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// The caller's own account as returned by `/users/me`, with fields that are not part of the
+/// public `UserResponse`.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileResponse {
+    #[serde(flatten)]
+    pub user: UserResponse,
+    pub mfa_enabled: bool,
+    /// What the current credentials allow, narrower than the role for API keys.
+    pub permissions: Vec<Permission>,
+    /// The admin acting as this user, when the request is impersonated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub impersonated_by: Option<String>,
+}
+
 impl From<crate::models::user::User> for UserResponse {
     fn from(user: crate::models::user::User) -> Self {
         Self {
@@ -40,6 +117,7 @@ impl From<crate::models::user::User> for UserResponse {
             username: user.username,
             email: user.email,
             role: user.role,
+            email_verified: user.email_verified,
             created_at: user.created_at,
             updated_at: user.updated_at,
         }
@@ -60,8 +138,12 @@ mod tests {
             email: "test@test.com".into(),
             password_hash: "hash".into(),
             role: "user".into(),
+            email_verified: false,
+            mfa: None,
+            identities: vec![],
             created_at: now,
             updated_at: now,
+            deleted_at: None,
         };
 
         let response: UserResponse = user.into();
@@ -69,4 +151,32 @@ mod tests {
         assert_eq!(response.username, "test");
         assert_eq!(response.email, "test@test.com");
     }
+
+    #[test]
+    fn test_list_users_query_into_filter() {
+        let query = ListUsersQuery {
+            role: Some(Role::Admin),
+            q: Some("  ali ".into()),
+            sort: Some("email:asc".into()),
+            ..Default::default()
+        };
+        let filter = UserFilter::try_from(query).unwrap();
+        assert_eq!(filter.role, Some(Role::Admin));
+        assert_eq!(filter.search.as_deref(), Some("ali"));
+        assert_eq!(filter.sort.field, crate::models::user::UserSortField::Email);
+
+        let query = ListUsersQuery { sort: Some("passwordHash:asc".into()), ..Default::default() };
+        assert!(matches!(UserFilter::try_from(query), Err(AppError::ValidationError(_))));
+
+        let query = ListUsersQuery {
+            created_from: Some(now_plus(1)),
+            created_to: Some(now_plus(0)),
+            ..Default::default()
+        };
+        assert!(matches!(UserFilter::try_from(query), Err(AppError::ValidationError(_))));
+    }
+
+    fn now_plus(days: i64) -> DateTime<Utc> {
+        Utc::now() + chrono::Duration::days(days)
+    }
 }
@@ -1,10 +1,12 @@
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
 use thiserror::Error;
+use validator::ValidationErrors;
+use crate::models::permission::Permission;
 
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -12,13 +14,19 @@ pub enum AppError {
     NotFound,
     #[error("Invalid Input: {0}")]
     ValidationError(String),
+    /// Reported per field as `{ field: [{ code, message }] }` so clients can point at the bad input.
+    #[error("Invalid Input: {0}")]
+    InvalidFields(ValidationErrors),
     #[error("Authentication Failed")]
     AuthError,
-    #[allow(dead_code)]
     #[error("Permission Denied")]
     PermissionDenied,
+    #[error("Missing Permission: {0}")]
+    MissingPermission(Permission),
     #[error("Database Error: {0}")]
     DatabaseError(#[from] mongodb::error::Error),
+    #[error("Cache Error: {0}")]
+    CacheError(#[from] redis::RedisError),
     #[error("Internal Server Error")]
     InternalServerError,
     #[error("Any Error: {0}")]
@@ -27,6 +35,12 @@ pub enum AppError {
     UserAlreadyExists,
     #[error("Invalid email or password")]
     InvalidCredentials,
+    #[error("Email address not verified")]
+    EmailNotVerified,
+    #[error("Too many requests, retry after {0}s")]
+    TooManyRequests(u64),
+    #[error("Service temporarily unavailable")]
+    ServiceUnavailable,
 }
 
 impl IntoResponse for AppError {
@@ -40,12 +54,45 @@ impl IntoResponse for AppError {
                 )
                     .into_response();
             }
+            AppError::InvalidFields(errors) => {
+                let details: serde_json::Map<_, _> = errors
+                    .field_errors()
+                    .into_iter()
+                    .map(|(field, errors)| {
+                        let errors: Vec<_> = errors
+                            .iter()
+                            .map(|e| json!({ "code": e.code, "message": e.message }))
+                            .collect();
+                        (field.to_string(), json!(errors))
+                    })
+                    .collect();
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "ok": false, "error": "Validation Error", "details": details })),
+                )
+                    .into_response();
+            }
             AppError::AuthError => (StatusCode::UNAUTHORIZED, "Authentication failed"),
             AppError::PermissionDenied => (StatusCode::FORBIDDEN, "Permission denied"),
+            AppError::MissingPermission(permission) => {
+                return (
+                    StatusCode::FORBIDDEN,
+                    Json(json!({
+                        "ok": false,
+                        "error": "Permission denied",
+                        "details": { "missingPermission": permission },
+                    })),
+                )
+                    .into_response();
+            }
             AppError::DatabaseError(e) => {
                 tracing::error!("Database Error: {:?}", e);
                 (StatusCode::INTERNAL_SERVER_ERROR, "Database error")
             }
+            AppError::CacheError(e) => {
+                tracing::error!("Cache Error: {:?}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Cache error")
+            }
             AppError::InternalServerError => {
                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
             }
@@ -55,6 +102,23 @@ impl IntoResponse for AppError {
             }
             AppError::UserAlreadyExists => (StatusCode::CONFLICT, "User already exists"),
             AppError::InvalidCredentials => (StatusCode::UNAUTHORIZED, "Invalid email or password"),
+            AppError::EmailNotVerified => (StatusCode::FORBIDDEN, "Email address not verified"),
+            AppError::TooManyRequests(retry_after) => {
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(header::RETRY_AFTER, retry_after.to_string())],
+                    Json(json!({ "ok": false, "error": "Too many requests" })),
+                )
+                    .into_response();
+            }
+            AppError::ServiceUnavailable => {
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    [(header::RETRY_AFTER, "1")],
+                    Json(json!({ "ok": false, "error": "Service temporarily unavailable" })),
+                )
+                    .into_response();
+            }
         };
 
         let body = Json(json!({
@@ -79,19 +143,41 @@ mod tests {
         let res = AppError::ValidationError("test".into()).into_response();
         assert_eq!(res.status(), StatusCode::BAD_REQUEST);
 
+        let mut errors = ValidationErrors::new();
+        errors.add("page", validator::ValidationError::new("range"));
+        let res = AppError::InvalidFields(errors).into_response();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
         let res = AppError::DatabaseError(mongodb::error::Error::custom("db error")).into_response();
         assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
 
+        let res = AppError::CacheError(redis::RedisError::from((redis::ErrorKind::IoError, "down"))).into_response();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
         let res = AppError::InternalServerError.into_response();
         assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
 
         let res = AppError::PermissionDenied.into_response();
         assert_eq!(res.status(), StatusCode::FORBIDDEN);
 
+        let res = AppError::MissingPermission(Permission::UsersWrite).into_response();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
         let res = AppError::InvalidCredentials.into_response();
         assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
 
         assert_eq!(AppError::UserAlreadyExists.into_response().status(), StatusCode::CONFLICT);
+
+        let res = AppError::EmailNotVerified.into_response();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = AppError::TooManyRequests(30).into_response();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers()[header::RETRY_AFTER], "30");
+
+        let res = AppError::ServiceUnavailable.into_response();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(res.headers()[header::RETRY_AFTER], "1");
         
         let res = AppError::AnyError(anyhow::anyhow!("error")).into_response();
         assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
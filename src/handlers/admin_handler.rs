@@ -0,0 +1,104 @@
+use crate::{
+    dtos::user::UserResponse,
+    error::AppError,
+    handlers::auth_handler::Claims,
+    middlewares::auth::AuthUser,
+    models::permission::Permission,
+    services::session_service::SessionService,
+    state::AppState,
+    utils::{jwt, response::json_ok},
+};
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+};
+use chrono::Utc;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImpersonationResponse {
+    pub token: String,
+    pub expires_in: i64,
+    pub user: UserResponse,
+}
+
+pub struct AdminHandler;
+
+impl AdminHandler {
+
+    pub async fn revoke_user_sessions(
+        State(state): State<AppState>,
+        Path(id): Path<String>,
+    ) -> Result<impl IntoResponse, AppError> {
+        // Make sure the target exists before writing the cutoff.
+        state.user_service.get_user(&id).await?;
+
+        SessionService::new(state.redis.clone(), &state.config)
+            .revoke_all(&id)
+            .await?;
+
+        Ok(json_ok("All sessions revoked"))
+    }
+
+    /// Undoes a soft delete. The user signs in again with their existing credentials.
+    pub async fn restore_user(
+        State(state): State<AppState>,
+        Path(id): Path<String>,
+    ) -> Result<impl IntoResponse, AppError> {
+        let user = state.user_service.restore_user(&id).await?;
+        tracing::info!("Restored user {}", id);
+        Ok(json_ok(user))
+    }
+
+    /// Permanently removes a soft-deleted user once the retention window has passed.
+    pub async fn purge_user(
+        State(state): State<AppState>,
+        admin: AuthUser,
+        Path(id): Path<String>,
+    ) -> Result<impl IntoResponse, AppError> {
+        let retention = chrono::Duration::days(state.config.user_purge_retention_days);
+        state.user_service.purge_user(&id, Utc::now() - retention).await?;
+
+        tracing::warn!("Admin {} purged user {}", admin.id, id);
+        Ok(json_ok("User purged"))
+    }
+
+    /// Issues a short-lived access token for the user with the admin recorded in `act`.
+    /// No refresh token or session is created, so access ends when the token expires.
+    pub async fn impersonate(
+        State(state): State<AppState>,
+        admin: AuthUser,
+        Path(id): Path<String>,
+    ) -> Result<impl IntoResponse, AppError> {
+        if admin.api_key_id.is_some() || admin.actor_id.is_some() {
+            return Err(AppError::PermissionDenied);
+        }
+        if admin.id == id {
+            return Err(AppError::ValidationError("You cannot impersonate yourself".into()));
+        }
+
+        let user = state.user_service.get_user(&id).await?;
+        // Acting as another admin would let one admin borrow another's identity. Any role
+        // granted users:admin through ROLE_PERMISSIONS counts as an admin here.
+        if state.config.permissions_for(&user.role).contains(&Permission::UsersAdmin) {
+            return Err(AppError::PermissionDenied);
+        }
+
+        let ttl = state.config.impersonation_ttl_secs;
+        let claims = Claims::new(&state.config, user.id.clone(), user.role.clone())
+            .with_actor(&admin.id)
+            .expires_in(ttl);
+        let token = jwt::encode_token(state.jwt_keys()?, &claims)?;
+
+        tracing::warn!(
+            "Admin {} started impersonating user {} (token {}, {}s)",
+            admin.id,
+            user.id,
+            claims.jti,
+            ttl
+        );
+
+        Ok(json_ok(ImpersonationResponse { token, expires_in: ttl, user }))
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{
+    dtos::api_key::CreateApiKey,
+    error::AppError,
+    middlewares::auth::AuthUser,
+    state::AppState,
+    utils::response::{json_created, json_ok},
+};
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
+use validator::Validate;
+
+pub struct ApiKeyHandler;
+
+impl ApiKeyHandler {
+
+    pub async fn create(
+        State(state): State<AppState>,
+        user: AuthUser,
+        Json(payload): Json<CreateApiKey>,
+    ) -> Result<impl IntoResponse, AppError> {
+        payload.validate().map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+        // A leaked key must not be able to mint further keys, nor an impersonation token
+        // outlive itself as a key for the impersonated user.
+        if user.api_key_id.is_some() {
+            return Err(AppError::PermissionDenied);
+        }
+        user.forbid_impersonation()?;
+
+        let owner_id = payload.owner_id.clone().unwrap_or_else(|| user.id.clone());
+        state.user_service.get_user(&owner_id).await.map_err(|e| match e {
+            AppError::NotFound => AppError::ValidationError("Owner not found".into()),
+            e => e,
+        })?;
+
+        let created = state.api_keys()?.create_key(&owner_id, payload).await?;
+        tracing::info!("API key {} created by {} for {}", created.api_key.prefix, user.id, owner_id);
+
+        Ok(json_created(created))
+    }
+
+    pub async fn list(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+        let keys = state.api_keys()?.list_keys().await?;
+        Ok(json_ok(keys))
+    }
+
+    pub async fn revoke(
+        State(state): State<AppState>,
+        Path(id): Path<String>,
+    ) -> Result<impl IntoResponse, AppError> {
+        state.api_keys()?.revoke_key(&id).await?;
+        Ok(json_ok("API key revoked"))
+    }
+}
@@ -1,14 +1,25 @@
 use axum::{
-    extract::State,
-    Json,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    Extension, Json,
 };
 use crate::{
     dtos::user::{CreateUser, UserResponse},
     error::AppError,
+    middlewares::client_ip::{ClientIp, UserAgent},
+    models::user::User,
+    services::{
+        login_throttle::LoginThrottle, magic_link_service::MagicLinkService, mfa_service::MfaService,
+        oidc_service::OidcService, session_service::SessionService, token_service::TokenService,
+    },
     state::AppState,
+    utils::crypto::{generate_token, sha256_hex, sign_token, verify_signed_token},
+    utils::response::json_ok,
 };
+use crate::config::AppConfig;
+use crate::utils::jwt;
 use chrono::{Utc, Duration};
-use jsonwebtoken::{encode, Header, EncodingKey};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
@@ -19,17 +30,172 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshRequest {
+    #[validate(length(min = 1))]
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaLoginRequest {
+    #[validate(length(min = 1))]
+    pub mfa_token: String,
+    /// Current TOTP code or an unused recovery code.
+    #[validate(length(min = 1))]
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ForgotPasswordRequest {
+    #[validate(email)]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetPasswordRequest {
+    #[validate(length(min = 1))]
+    pub token: String,
+    /// Checked against the password policy by the handler.
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailQuery {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResendVerificationRequest {
+    #[validate(email)]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct OidcCallbackRequest {
+    #[validate(length(min = 1))]
+    pub code: String,
+    #[validate(length(min = 1))]
+    pub state: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MagicLinkRequest {
+    #[validate(email)]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MagicLinkQuery {
+    pub token: String,
+}
+
+const PASSWORD_RESET: &str = "password_reset";
+const MAGIC_LINK_COOKIE: &str = "magic_link_nonce";
+const MFA_PENDING: &str = "mfa_pending";
+const EMAIL_VERIFICATION: &str = "email_verification";
+
 #[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AuthResponse {
     pub token: String,
+    pub refresh_token: String,
     pub user: UserResponse,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    sub: String,
-    role: String,
-    exp: usize,
+/// Returned by login instead of tokens when the account has 2FA enabled.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaChallenge {
+    pub mfa_required: bool,
+    pub mfa_token: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OidcAuthorization {
+    pub authorization_url: String,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Authenticated(AuthResponse),
+    MfaRequired(MfaChallenge),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub role: String,
+    pub jti: String,
+    pub iss: String,
+    pub aud: String,
+    pub iat: usize,
+    /// `iat` in milliseconds, so a token issued in the same second as a revocation cutoff
+    /// can still be told apart from it. Absent on tokens from older releases.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat_ms: Option<i64>,
+    pub nbf: usize,
+    pub exp: usize,
+    /// Session (refresh-token family) the token was issued for. Revoking the session
+    /// invalidates the token immediately.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    /// Set on impersonation tokens: the admin acting as `sub` (RFC 8693 `act`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Actor>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Actor {
+    pub sub: String,
+}
+
+impl Claims {
+    pub fn new(config: &AppConfig, sub: String, role: String) -> Self {
+        let now = Utc::now();
+        let exp = now
+            .checked_add_signed(Duration::seconds(config.jwt_access_ttl_secs))
+            .expect("valid timestamp");
+
+        Self {
+            sub,
+            role,
+            jti: uuid::Uuid::new_v4().to_string(),
+            iss: config.jwt_issuer.clone(),
+            aud: config.jwt_audience.clone(),
+            iat: now.timestamp() as usize,
+            iat_ms: Some(now.timestamp_millis()),
+            nbf: now.timestamp() as usize,
+            exp: exp.timestamp() as usize,
+            sid: None,
+            act: None,
+        }
+    }
+
+    pub fn with_session(mut self, session_id: impl Into<String>) -> Self {
+        self.sid = Some(session_id.into());
+        self
+    }
+
+    pub fn with_actor(mut self, actor_id: impl Into<String>) -> Self {
+        self.act = Some(Actor { sub: actor_id.into() });
+        self
+    }
+
+    /// Issue time in milliseconds, falling back to `iat` for tokens without `iat_ms`.
+    pub fn issued_at_ms(&self) -> i64 {
+        self.iat_ms.unwrap_or(self.iat as i64 * 1000)
+    }
+
+    /// Shortens or extends the lifetime set by [`Self::new`], counting from `iat`.
+    pub fn expires_in(mut self, ttl_secs: i64) -> Self {
+        self.exp = (self.iat as i64 + ttl_secs) as usize;
+        self
+    }
 }
 
 pub struct AuthHandler;
@@ -40,41 +206,450 @@ impl AuthHandler {
         State(state): State<AppState>,
         Json(payload): Json<CreateUser>,
     ) -> Result<Json<UserResponse>, AppError> {
-        payload.validate().map_err(|e| AppError::ValidationError(e.to_string()))?;
+        state.password_policy.validate(
+            &payload,
+            "password",
+            &payload.password,
+            &[&payload.username, &payload.email],
+        )?;
 
         let user = state.user_service.create_user(payload).await?;
+        send_verification_email(&state, &user).await;
         Ok(Json(user))
     }
 
     pub async fn login(
         State(state): State<AppState>,
+        ClientIp(ip): ClientIp,
+        UserAgent(user_agent): UserAgent,
         Json(payload): Json<LoginRequest>,
+    ) -> Result<Json<LoginResponse>, AppError> {
+        payload.validate().map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+        let throttle = LoginThrottle::new(state.redis.clone(), &state.config);
+        throttle.check(&payload.email, &ip).await?;
+
+        let user = match state.user_service.authenticate(&payload.email, &payload.password).await {
+            Err(AppError::InvalidCredentials) => {
+                throttle.record_failure(&payload.email, &ip).await?;
+                return Err(AppError::InvalidCredentials);
+            }
+            result => result?,
+        };
+
+        // With 2FA on, the counters are only cleared once `login_mfa` accepts the second factor.
+        let mfa = mfa_enabled(&user);
+        if !mfa {
+            throttle.reset(&payload.email, &ip).await?;
+        }
+        Ok(Json(start_session(&state, user.into(), mfa, &ip, user_agent.as_deref()).await?))
+    }
+
+    /// Returns the provider URL the web client should navigate to.
+    pub async fn oidc_authorize(
+        State(state): State<AppState>,
+        Path(provider): Path<String>,
+    ) -> Result<Json<OidcAuthorization>, AppError> {
+        let authorization_url = OidcService::new(state.redis.clone(), &state.config, &provider)?
+            .authorization_url()
+            .await?;
+
+        Ok(Json(OidcAuthorization { authorization_url }))
+    }
+
+    /// Takes the `code` and `state` the provider redirected back with and signs the user in,
+    /// linking or creating the account by verified email on first use.
+    pub async fn oidc_callback(
+        State(state): State<AppState>,
+        Path(provider): Path<String>,
+        ClientIp(ip): ClientIp,
+        UserAgent(user_agent): UserAgent,
+        Json(payload): Json<OidcCallbackRequest>,
+    ) -> Result<Json<LoginResponse>, AppError> {
+        payload.validate().map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+        let profile = OidcService::new(state.redis.clone(), &state.config, &provider)?
+            .complete(&payload.code, &payload.state)
+            .await?;
+        let user = state.user_service.find_or_create_external(profile).await?;
+
+        let mfa = mfa_enabled(&user);
+        Ok(Json(start_session(&state, user.into(), mfa, &ip, user_agent.as_deref()).await?))
+    }
+
+    /// Second login step. The pending token is single-use, so a wrong code means logging in again.
+    /// Wrong codes count against the same email and IP limits as wrong passwords.
+    pub async fn login_mfa(
+        State(state): State<AppState>,
+        ClientIp(ip): ClientIp,
+        UserAgent(user_agent): UserAgent,
+        Json(payload): Json<MfaLoginRequest>,
     ) -> Result<Json<AuthResponse>, AppError> {
         payload.validate().map_err(|e| AppError::ValidationError(e.to_string()))?;
 
-        let user = state.user_service.authenticate(&payload.email, &payload.password).await?;
+        let tokens = TokenService::new(state.redis.clone(), &state.config);
+        let user_id = tokens
+            .consume_one_time_token(MFA_PENDING, &payload.mfa_token)
+            .await?
+            .ok_or(AppError::AuthError)?;
 
-        // Generate JWT
-        let exp = Utc::now()
-            .checked_add_signed(Duration::hours(24))
-            .expect("valid timestamp")
-            .timestamp() as usize;
+        let user = state.user_service.get_user(&user_id).await?;
+        let throttle = LoginThrottle::new(state.redis.clone(), &state.config);
+        throttle.check(&user.email, &ip).await?;
 
-        let claims = Claims {
-            sub: user.id.clone().unwrap(),
-            role: user.role.clone(),
-            exp,
-        };
+        match MfaService::new(state.user_service.clone(), &state.config)?
+            .verify(&user_id, &payload.code)
+            .await
+        {
+            Err(AppError::ValidationError(_) | AppError::NotFound) => {
+                throttle.record_failure(&user.email, &ip).await?;
+                return Err(AppError::AuthError);
+            }
+            result => result?,
+        }
+        throttle.reset(&user.email, &ip).await?;
 
-        let token = encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(state.config.jwt_secret.as_ref()),
-        ).map_err(|_| AppError::AuthError)?;
+        let refresh = SessionService::new(state.redis.clone(), &state.config)
+            .start(&user_id, &ip, user_agent.as_deref())
+            .await?;
+        let claims = Claims::new(&state.config, user.id.clone(), user.role.clone())
+            .with_session(&refresh.family_id);
+        let token = jwt::encode_token(state.jwt_keys()?, &claims)?;
 
         Ok(Json(AuthResponse {
             token,
-            user: user.into(),
+            refresh_token: refresh.token,
+            user,
         }))
     }
+
+    pub async fn refresh(
+        State(state): State<AppState>,
+        ClientIp(ip): ClientIp,
+        Json(payload): Json<RefreshRequest>,
+    ) -> Result<Json<AuthResponse>, AppError> {
+        payload.validate().map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+        let refresh = TokenService::new(state.redis.clone(), &state.config)
+            .rotate_refresh_token(&payload.refresh_token)
+            .await?;
+
+        let user = state.user_service.get_user(&refresh.user_id).await.map_err(|e| match e {
+            AppError::NotFound => AppError::AuthError,
+            e => e,
+        })?;
+
+        SessionService::new(state.redis.clone(), &state.config)
+            .touch(&refresh.family_id, &ip)
+            .await?;
+
+        let claims = Claims::new(&state.config, user.id.clone(), user.role.clone())
+            .with_session(&refresh.family_id);
+        let token = jwt::encode_token(state.jwt_keys()?, &claims)?;
+
+        Ok(Json(AuthResponse {
+            token,
+            refresh_token: refresh.token,
+            user,
+        }))
+    }
+
+    /// Revokes the access token and ends its session, so the refresh token stops working too.
+    /// API keys carry no claims and are revoked through the admin endpoints instead.
+    pub async fn logout(
+        State(state): State<AppState>,
+        claims: Option<Extension<Claims>>,
+    ) -> Result<impl IntoResponse, AppError> {
+        let Extension(claims) = claims.ok_or(AppError::PermissionDenied)?;
+
+        TokenService::new(state.redis.clone(), &state.config)
+            .revoke_access_token(&claims)
+            .await?;
+
+        if let Some(sid) = &claims.sid {
+            match SessionService::new(state.redis.clone(), &state.config)
+                .revoke(&claims.sub, sid)
+                .await
+            {
+                Ok(()) | Err(AppError::NotFound) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(json_ok("Logged out successfully"))
+    }
+
+    /// Always answers the same way so the endpoint cannot be used to probe for accounts.
+    pub async fn forgot_password(
+        State(state): State<AppState>,
+        Json(payload): Json<ForgotPasswordRequest>,
+    ) -> Result<impl IntoResponse, AppError> {
+        payload.validate().map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+        // Sent in the background so the response time does not reveal whether the account exists.
+        tokio::spawn(async move {
+            if let Err(e) = send_password_reset(&state, &payload.email).await {
+                tracing::error!("Failed to process password reset request: {}", e);
+            }
+        });
+
+        Ok(json_ok("If the email is registered, a password reset link has been sent"))
+    }
+
+    pub async fn reset_password(
+        State(state): State<AppState>,
+        Json(payload): Json<ResetPasswordRequest>,
+    ) -> Result<impl IntoResponse, AppError> {
+        let invalid = || AppError::ValidationError("Invalid or expired reset token".into());
+
+        // Peek first so a password the policy rejects does not use up the link.
+        let tokens = TokenService::new(state.redis.clone(), &state.config);
+        let user_id = tokens
+            .peek_one_time_token(PASSWORD_RESET, &payload.token)
+            .await?
+            .ok_or_else(invalid)?;
+        let account = state.user_service.get_user(&user_id).await?;
+        state.password_policy.validate(
+            &payload,
+            "new_password",
+            &payload.new_password,
+            &[&account.username, &account.email],
+        )?;
+
+        tokens
+            .consume_one_time_token(PASSWORD_RESET, &payload.token)
+            .await?
+            .filter(|id| *id == user_id)
+            .ok_or_else(invalid)?;
+
+        state.user_service.set_password(&user_id, &payload.new_password).await?;
+        SessionService::new(state.redis.clone(), &state.config)
+            .revoke_all(&user_id)
+            .await?;
+
+        Ok(json_ok("Password has been reset"))
+    }
+
+    /// The link is bound to the address it was sent to, so changing the email invalidates it.
+    pub async fn verify_email(
+        State(state): State<AppState>,
+        Query(query): Query<VerifyEmailQuery>,
+    ) -> Result<impl IntoResponse, AppError> {
+        let invalid = || AppError::ValidationError("Invalid or expired verification link".into());
+
+        let payload = verify_signed_token(&state.config.jwt_secret, EMAIL_VERIFICATION, &query.token)
+            .ok_or_else(invalid)?;
+        let (user_id, email) = payload.split_once(':').ok_or_else(invalid)?;
+
+        let user = state.user_service.get_user(user_id).await.map_err(|e| match e {
+            AppError::NotFound => invalid(),
+            e => e,
+        })?;
+        if user.email != email {
+            return Err(invalid());
+        }
+
+        if !user.email_verified {
+            state.user_service.mark_email_verified(&user.id).await?;
+        }
+
+        Ok(json_ok("Email verified"))
+    }
+
+    /// Throttled per address whether or not it is registered, so the response does not reveal accounts.
+    pub async fn resend_verification(
+        State(state): State<AppState>,
+        Json(payload): Json<ResendVerificationRequest>,
+    ) -> Result<impl IntoResponse, AppError> {
+        payload.validate().map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+        let cooldown = state.config.email_verification_resend_secs;
+        let key = format!("email_verification_resend:{}", sha256_hex(&payload.email.to_lowercase()));
+        if !state.redis.set_nx_ex(&key, "1", cooldown).await? {
+            return Err(AppError::TooManyRequests(cooldown));
+        }
+
+        if let Some(user) = state.user_service.find_by_email(&payload.email).await? {
+            if !user.email_verified {
+                send_verification_email(&state, &user).await;
+            }
+        }
+
+        Ok(json_ok("If the email is registered and unverified, a verification link has been sent"))
+    }
+
+    /// Always answers the same way so the endpoint cannot be used to probe for accounts. The
+    /// nonce cookie is set either way; the link only works in a browser that holds it.
+    pub async fn request_magic_link(
+        State(state): State<AppState>,
+        Json(payload): Json<MagicLinkRequest>,
+    ) -> Result<impl IntoResponse, AppError> {
+        if !state.config.magic_link_enabled {
+            return Err(AppError::NotFound);
+        }
+        payload.validate().map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+        let cooldown = state.config.magic_link_resend_secs;
+        let key = format!("magic_link_resend:{}", sha256_hex(&payload.email.to_lowercase()));
+        if !state.redis.set_nx_ex(&key, "1", cooldown).await? {
+            return Err(AppError::TooManyRequests(cooldown));
+        }
+
+        let nonce = generate_token();
+        if let Some(user) = state.user_service.find_by_email(&payload.email).await? {
+            let token = MagicLinkService::new(state.redis.clone(), &state.config)
+                .issue(&user.id, &nonce)
+                .await?;
+
+            let link = format!("{}/api/v1/auth/magic-link/callback?token={}", state.config.api_base_url, token);
+            let body = format!(
+                "Use the link below to sign in. It expires in {} minutes and only works in the browser \
+                 where you requested it.\n\n{}",
+                state.config.magic_link_ttl_secs / 60,
+                link
+            );
+
+            if let Err(e) = state.email.send_email(&user.email, "Your sign-in link", &body).await {
+                tracing::error!("Failed to send sign-in link to user {}: {}", user.id, e);
+            }
+        }
+
+        let cookie = nonce_cookie(&state.config, &nonce, state.config.magic_link_ttl_secs);
+        Ok((
+            [(header::SET_COOKIE, cookie)],
+            json_ok("If the email is registered, a sign-in link has been sent"),
+        ))
+    }
+
+    /// Opening the link proves control of the address, so it also verifies the email.
+    pub async fn magic_link_callback(
+        State(state): State<AppState>,
+        ClientIp(ip): ClientIp,
+        UserAgent(user_agent): UserAgent,
+        headers: HeaderMap,
+        Query(query): Query<MagicLinkQuery>,
+    ) -> Result<impl IntoResponse, AppError> {
+        if !state.config.magic_link_enabled {
+            return Err(AppError::NotFound);
+        }
+
+        let user_id = MagicLinkService::new(state.redis.clone(), &state.config)
+            .redeem(&query.token, cookie(&headers, MAGIC_LINK_COOKIE))
+            .await?;
+
+        let mut user = state.user_service.get_user(&user_id).await?;
+        if !user.email_verified {
+            state.user_service.mark_email_verified(&user.id).await?;
+            user.email_verified = true;
+        }
+        let mfa = state.user_service.get_mfa(&user_id).await?.is_some_and(|mfa| mfa.enabled);
+
+        let response = start_session(&state, user, mfa, &ip, user_agent.as_deref()).await?;
+        Ok((
+            [(header::SET_COOKIE, nonce_cookie(&state.config, "", 0))],
+            Json(response),
+        ))
+    }
+
+    /// Public verification keys so other services can check our tokens without the secret.
+    pub async fn jwks(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+        let keys = state.jwt_keys()?.jwks();
+        Ok(([(header::CACHE_CONTROL, "public, max-age=300")], Json(keys.clone())))
+    }
+}
+
+/// Emails a password reset link if `email` belongs to an account, and does nothing otherwise.
+async fn send_password_reset(state: &AppState, email: &str) -> Result<(), AppError> {
+    let Some(user) = state.user_service.find_by_email(email).await? else {
+        return Ok(());
+    };
+
+    let token = TokenService::new(state.redis.clone(), &state.config)
+        .issue_one_time_token(PASSWORD_RESET, &user.id, state.config.password_reset_ttl_secs)
+        .await?;
+
+    let link = format!("{}/reset-password?token={}", state.config.frontend_url, token);
+    let body = format!(
+        "Use the link below to reset your password. It expires in {} minutes.\n\n{}",
+        state.config.password_reset_ttl_secs / 60,
+        link
+    );
+
+    if let Err(e) = state.email.send_email(&user.email, "Reset your password", &body).await {
+        tracing::error!("Failed to send password reset email to user {}: {}", user.id, e);
+    }
+    Ok(())
+}
+
+/// Last step of every first-factor sign-in: a 2FA challenge when enabled, tokens otherwise.
+async fn start_session(
+    state: &AppState,
+    user: UserResponse,
+    mfa_enabled: bool,
+    ip: &str,
+    user_agent: Option<&str>,
+) -> Result<LoginResponse, AppError> {
+    let tokens = TokenService::new(state.redis.clone(), &state.config);
+    let user_id = user.id.clone();
+
+    if mfa_enabled {
+        let mfa_token = tokens
+            .issue_one_time_token(MFA_PENDING, &user_id, state.config.mfa_pending_ttl_secs)
+            .await?;
+        return Ok(LoginResponse::MfaRequired(MfaChallenge {
+            mfa_required: true,
+            mfa_token,
+        }));
+    }
+
+    let refresh = SessionService::new(state.redis.clone(), &state.config)
+        .start(&user_id, ip, user_agent)
+        .await?;
+    let claims = Claims::new(&state.config, user_id, user.role.clone()).with_session(&refresh.family_id);
+    let token = jwt::encode_token(state.jwt_keys()?, &claims)?;
+
+    Ok(LoginResponse::Authenticated(AuthResponse {
+        token,
+        refresh_token: refresh.token,
+        user,
+    }))
+}
+
+/// Scoped to the magic-link endpoints. `Secure` whenever the API is served over HTTPS.
+fn nonce_cookie(config: &AppConfig, nonce: &str, max_age_secs: u64) -> String {
+    let secure = if config.api_base_url.starts_with("https://") { "; Secure" } else { "" };
+    format!(
+        "{}={}; Path=/api/v1/auth/magic-link; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        MAGIC_LINK_COOKIE, nonce, max_age_secs, secure
+    )
+}
+
+fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+fn mfa_enabled(user: &User) -> bool {
+    user.mfa.as_ref().is_some_and(|mfa| mfa.enabled)
+}
+
+async fn send_verification_email(state: &AppState, user: &UserResponse) {
+    let token = sign_token(
+        &state.config.jwt_secret,
+        EMAIL_VERIFICATION,
+        &format!("{}:{}", user.id, user.email),
+        state.config.email_verification_ttl_secs,
+    );
+    let link = format!("{}/api/v1/auth/verify-email?token={}", state.config.api_base_url, token);
+    let body = format!("Confirm your email address by opening the link below.\n\n{}", link);
+
+    if let Err(e) = state.email.send_email(&user.email, "Verify your email address", &body).await {
+        tracing::error!("Failed to send verification email to user {}: {}", user.id, e);
+    }
 }
@@ -0,0 +1,79 @@
+use crate::{
+    error::AppError,
+    middlewares::auth::AuthUser,
+    services::mfa_service::MfaService,
+    state::AppState,
+    utils::response::json_ok,
+};
+use axum::{extract::State, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MfaCodeRequest {
+    #[validate(length(min = 1))]
+    pub code: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+pub struct MfaHandler;
+
+impl MfaHandler {
+
+    pub async fn enroll(
+        State(state): State<AppState>,
+        user: AuthUser,
+    ) -> Result<impl IntoResponse, AppError> {
+        account_owner(&user)?;
+
+        let enrollment = MfaService::new(state.user_service.clone(), &state.config)?
+            .enroll(&user.id)
+            .await?;
+
+        Ok(json_ok(enrollment))
+    }
+
+    pub async fn confirm(
+        State(state): State<AppState>,
+        user: AuthUser,
+        Json(payload): Json<MfaCodeRequest>,
+    ) -> Result<impl IntoResponse, AppError> {
+        account_owner(&user)?;
+        payload.validate().map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+        let recovery_codes = MfaService::new(state.user_service.clone(), &state.config)?
+            .confirm(&user.id, &payload.code)
+            .await?;
+
+        Ok(json_ok(RecoveryCodesResponse { recovery_codes }))
+    }
+
+    pub async fn disable(
+        State(state): State<AppState>,
+        user: AuthUser,
+        Json(payload): Json<MfaCodeRequest>,
+    ) -> Result<impl IntoResponse, AppError> {
+        account_owner(&user)?;
+        payload.validate().map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+        MfaService::new(state.user_service.clone(), &state.config)?
+            .disable(&user.id, &payload.code)
+            .await?;
+
+        Ok(json_ok("Two-factor authentication disabled"))
+    }
+}
+
+/// Changing the second factor can lock the owner out, so only the person signed in
+/// may do it: not an API key acting for them, and not an admin impersonating them.
+fn account_owner(user: &AuthUser) -> Result<(), AppError> {
+    if user.api_key_id.is_some() {
+        return Err(AppError::PermissionDenied);
+    }
+    user.forbid_impersonation()
+}
@@ -3,4 +3,8 @@ pub mod ws;
 pub mod health;
 pub mod docs;
 pub mod auth_handler;
+pub mod admin_handler;
 
+pub mod mfa_handler;
+pub mod api_key_handler;
+pub mod session_handler;
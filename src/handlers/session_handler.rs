@@ -0,0 +1,76 @@
+use crate::{
+    dtos::session::SessionResponse,
+    error::AppError,
+    middlewares::auth::AuthUser,
+    services::session_service::SessionService,
+    state::AppState,
+    utils::response::json_ok,
+};
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+};
+use serde_json::json;
+
+pub struct SessionHandler;
+
+impl SessionHandler {
+
+    pub async fn list(
+        State(state): State<AppState>,
+        user: AuthUser,
+    ) -> Result<impl IntoResponse, AppError> {
+        interactive(&user)?;
+
+        let sessions: Vec<SessionResponse> = SessionService::new(state.redis.clone(), &state.config)
+            .list(&user.id)
+            .await?
+            .into_iter()
+            .map(|session| SessionResponse::new(session, user.session_id.as_deref()))
+            .collect();
+
+        Ok(json_ok(sessions))
+    }
+
+    /// Signs out one device. Revoking the current session works like logout.
+    pub async fn revoke(
+        State(state): State<AppState>,
+        user: AuthUser,
+        Path(id): Path<String>,
+    ) -> Result<impl IntoResponse, AppError> {
+        interactive(&user)?;
+        user.forbid_impersonation()?;
+
+        SessionService::new(state.redis.clone(), &state.config)
+            .revoke(&user.id, &id)
+            .await?;
+
+        Ok(json_ok("Session revoked"))
+    }
+
+    /// "Log out all other devices".
+    pub async fn revoke_others(
+        State(state): State<AppState>,
+        user: AuthUser,
+    ) -> Result<impl IntoResponse, AppError> {
+        interactive(&user)?;
+        user.forbid_impersonation()?;
+        let current = user.session_id.as_deref().ok_or_else(|| {
+            AppError::ValidationError("The current token is not tied to a session, sign in again".into())
+        })?;
+
+        let revoked = SessionService::new(state.redis.clone(), &state.config)
+            .revoke_others(&user.id, current)
+            .await?;
+
+        Ok(json_ok(json!({ "revoked": revoked })))
+    }
+}
+
+/// Sessions belong to people signing in, not to API keys acting for them.
+fn interactive(user: &AuthUser) -> Result<(), AppError> {
+    if user.api_key_id.is_some() {
+        return Err(AppError::PermissionDenied);
+    }
+    Ok(())
+}
@@ -1,12 +1,21 @@
 use crate::{
-    dtos::user::{CreateUser, UpdateUser},
+    dtos::user::{ChangePassword, CreateUser, ListUsersQuery, ProfileResponse, UpdateUser},
     error::AppError,
+    handlers::auth_handler::{AuthResponse, Claims},
+    middlewares::{
+        auth::AuthUser,
+        client_ip::{ClientIp, UserAgent},
+        ownership::{Owned, UserRecord},
+    },
+    services::{login_throttle::LoginThrottle, session_service::SessionService},
     state::AppState,
+    utils::jwt,
     utils::response::{json_created, json_ok,},
-    utils::pagination::PaginationParams,
+    utils::pagination::Pagination,
 };
 use axum::{
     extract::{Path, Query, State},
+    http::header,
     response::IntoResponse,
     Json,
 };
@@ -20,15 +29,20 @@ impl UserHandler {
         State(state): State<AppState>,
         Json(payload): Json<CreateUser>,
     ) -> Result<impl IntoResponse, AppError> {
-        payload.validate().map_err(|e| AppError::ValidationError(e.to_string()))?;
-        
+        state.password_policy.validate(
+            &payload,
+            "password",
+            &payload.password,
+            &[&payload.username, &payload.email],
+        )?;
+
         let user = state.user_service.create_user(payload).await?;
         Ok(json_created(user))
     }
 
     pub async fn get_user(
         State(state): State<AppState>,
-        Path(id): Path<String>,
+        Owned { id, .. }: Owned<UserRecord>,
     ) -> Result<impl IntoResponse, AppError> {
         let user = state.user_service.get_user(&id).await?;
         Ok(json_ok(user))
@@ -36,20 +50,174 @@ impl UserHandler {
 
     pub async fn list_users(
         State(state): State<AppState>,
-        Query(params): Query<PaginationParams>,
+        pagination: Pagination,
+        Query(query): Query<ListUsersQuery>,
     ) -> Result<impl IntoResponse, AppError> {
-        let result = state.user_service.list_users(params.page, params.limit).await?;
-        Ok(json_ok(result))
+        let filter = query.try_into()?;
+
+        if let Some(cursor) = pagination.cursor.clone() {
+            let result = state
+                .user_service
+                .list_users_after(filter, Some(cursor), Some(pagination.limit))
+                .await?;
+            let links = pagination.cursor_links(result.next_cursor.as_deref());
+            return Ok(([(header::LINK, links)], json_ok(result)).into_response());
+        }
+
+        let result = state
+            .user_service
+            .list_users(filter, Some(pagination.page), Some(pagination.limit))
+            .await?;
+        let links = pagination.links(result.total);
+        Ok(([(header::LINK, links)], json_ok(result)).into_response())
     }
 
     pub async fn update_user(
         State(state): State<AppState>,
-        Path(id): Path<String>,
+        user: AuthUser,
+        Owned { id, .. }: Owned<UserRecord>,
         Json(payload): Json<UpdateUser>,
     ) -> Result<impl IntoResponse, AppError> {
         payload.validate().map_err(|e| AppError::ValidationError(e.to_string()))?;
+        guard_email_change(&user, &payload)?;
 
         state.user_service.update_user(&id, payload).await?;
         Ok(json_ok("User updated successfully"))
     }
+
+    /// Soft-deletes the user and signs them out everywhere. Admins can restore or purge it later.
+    pub async fn delete_user(
+        State(state): State<AppState>,
+        Path(id): Path<String>,
+    ) -> Result<impl IntoResponse, AppError> {
+        delete_account(&state, &id).await?;
+        Ok(json_ok("User deleted successfully"))
+    }
+
+    pub async fn get_me(
+        State(state): State<AppState>,
+        user: AuthUser,
+    ) -> Result<impl IntoResponse, AppError> {
+        Ok(json_ok(profile(&state, &user).await?))
+    }
+
+    pub async fn update_me(
+        State(state): State<AppState>,
+        user: AuthUser,
+        Json(payload): Json<UpdateUser>,
+    ) -> Result<impl IntoResponse, AppError> {
+        payload.validate().map_err(|e| AppError::ValidationError(e.to_string()))?;
+        guard_email_change(&user, &payload)?;
+
+        state.user_service.update_user(&user.id, payload).await?;
+        Ok(json_ok(profile(&state, &user).await?))
+    }
+
+    /// Closes the caller's own account, with the same soft delete as `DELETE /users/:id`.
+    pub async fn delete_me(
+        State(state): State<AppState>,
+        user: AuthUser,
+    ) -> Result<impl IntoResponse, AppError> {
+        if user.api_key_id.is_some() {
+            return Err(AppError::PermissionDenied);
+        }
+        user.forbid_impersonation()?;
+
+        delete_account(&state, &user.id).await?;
+        Ok(json_ok("Account deleted successfully"))
+    }
+
+    /// Changes the caller's own password. The per-user token cutoff acts as the credential
+    /// version: moving it signs out every device, then this one gets a fresh session.
+    /// Wrong current passwords count against the login limits, so a stolen access token
+    /// cannot be used to guess the password.
+    pub async fn change_password(
+        State(state): State<AppState>,
+        user: AuthUser,
+        ClientIp(ip): ClientIp,
+        UserAgent(user_agent): UserAgent,
+        Json(payload): Json<ChangePassword>,
+    ) -> Result<impl IntoResponse, AppError> {
+        // An API key acting for the user must not be able to take over the account.
+        if user.api_key_id.is_some() {
+            return Err(AppError::PermissionDenied);
+        }
+        user.forbid_impersonation()?;
+
+        let current = state.user_service.get_user(&user.id).await?;
+        state.password_policy.validate(
+            &payload,
+            "new_password",
+            &payload.new_password,
+            &[&current.username, &current.email],
+        )?;
+
+        // Keyed by the account's email so this shares one budget with `/auth/login`.
+        let throttle = LoginThrottle::new(state.redis.clone(), &state.config);
+        throttle.check(&current.email, &ip).await?;
+
+        let account = match state.user_service.change_password(&user.id, payload).await {
+            Err(AppError::InvalidCredentials) => {
+                throttle.record_failure(&current.email, &ip).await?;
+                return Err(AppError::ValidationError("Current password is incorrect".into()));
+            }
+            result => result?,
+        };
+        throttle.reset(&current.email, &ip).await?;
+
+        let sessions = SessionService::new(state.redis.clone(), &state.config);
+        sessions.revoke_all(&account.id).await?;
+        let refresh = sessions.start(&account.id, &ip, user_agent.as_deref()).await?;
+
+        let claims = Claims::new(&state.config, account.id.clone(), account.role.clone())
+            .with_session(&refresh.family_id);
+        let token = jwt::encode_token(state.jwt_keys()?, &claims)?;
+
+        let body = format!(
+            "The password of your account was changed from {}. All other devices have been signed out.\n\n\
+             If this was not you, reset your password immediately.",
+            ip
+        );
+        if let Err(e) = state.email.send_email(&account.email, "Your password was changed", &body).await {
+            tracing::error!("Failed to send password change notice to user {}: {}", account.id, e);
+        }
+
+        Ok(json_ok(AuthResponse {
+            token,
+            refresh_token: refresh.token,
+            user: account,
+        }))
+    }
+}
+
+/// A new address can receive password reset links, so changing it is a takeover:
+/// API keys and impersonation tokens may not do it.
+fn guard_email_change(user: &AuthUser, payload: &UpdateUser) -> Result<(), AppError> {
+    if payload.email.is_none() {
+        return Ok(());
+    }
+    if user.api_key_id.is_some() {
+        return Err(AppError::PermissionDenied);
+    }
+    user.forbid_impersonation()
+}
+
+async fn profile(state: &AppState, user: &AuthUser) -> Result<ProfileResponse, AppError> {
+    let account = state.user_service.get_user(&user.id).await?;
+    let mfa = state.user_service.get_mfa(&user.id).await?;
+
+    Ok(ProfileResponse {
+        user: account,
+        mfa_enabled: mfa.is_some_and(|mfa| mfa.enabled),
+        permissions: user.permissions.clone(),
+        impersonated_by: user.actor_id.clone(),
+    })
+}
+
+async fn delete_account(state: &AppState, id: &str) -> Result<(), AppError> {
+    state.user_service.delete_user(id).await?;
+
+    SessionService::new(state.redis.clone(), &state.config)
+        .revoke_all(id)
+        .await
 }
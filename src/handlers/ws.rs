@@ -21,7 +21,8 @@ async fn handle_socket(mut socket: WebSocket) {
     while let Some(Ok(msg)) = socket.recv().await {
         match msg {
             Message::Text(text) => {
-                if socket.send(Message::Text(text)).await.is_err() {
+                let sent = socket.send(Message::Text(text)).await;
+                if sent.is_err() {
                     break;
                 }
             }
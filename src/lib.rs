@@ -19,13 +19,17 @@ use tower_http::{
     cors::CorsLayer,
     trace::TraceLayer,
 };
-use axum::http::{header::{CONTENT_TYPE, AUTHORIZATION}, Method, HeaderValue};
+use axum::http::{header::{CONTENT_TYPE, AUTHORIZATION, LINK}, Method, HeaderValue};
 
 pub fn create_app(state: state::AppState) -> Router {
     let cors = CorsLayer::new()
         .allow_origin("http://localhost:5173".parse::<HeaderValue>().unwrap())
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-        .allow_headers([CONTENT_TYPE, AUTHORIZATION]);
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
+        .allow_headers([CONTENT_TYPE, AUTHORIZATION])
+        // Pagination links of list endpoints.
+        .expose_headers([LINK])
+        // Lets the web client receive the magic-link nonce cookie.
+        .allow_credentials(true);
 
     routes::init_routes(state.clone())
         .layer(axum::middleware::from_fn(middlewares::logger::logger_middleware))
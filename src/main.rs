@@ -29,6 +29,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Load configuration
     let config = AppConfig::new()?;
+    let jwt_keys = utils::jwt::JwtKeys::new(&config)?;
+    config.mfa_key()?;
 
     // Connect to Database
     let db = Arc::new(db::mongo::MongoProvider::new(&config.mongodb_uri, &config.mongodb_name).await?);
@@ -43,12 +45,39 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Initialize Repositories
     let user_repo = Arc::new(repositories::user_repository::UserRepository::new(db.as_ref()));
+    user_repo.ensure_indexes().await?;
+    let api_key_repo = Arc::new(repositories::api_key_repository::ApiKeyRepository::new(db.as_ref()));
+    api_key_repo.ensure_indexes().await?;
 
     // Initialize Services
-    let user_service = Arc::new(services::user_service::UserService::new(user_repo));
+    let password_hasher = Arc::new(utils::password::PasswordHasher::new(&config)?);
+    let user_service = Arc::new(
+        services::user_service::UserService::new(user_repo)
+            .with_password_hasher(password_hasher)
+            .with_blocking_pool(utils::blocking::BlockingPool::new(
+                config.password_hash_max_concurrency,
+                config.password_hash_max_queue,
+            ))
+            .with_email_verification_required(config.require_email_verification)
+            .with_cursor_secret(config.jwt_secret.clone()),
+    );
+    services::mfa_service::MfaService::check_key(user_service.as_ref(), &config).await?;
+    let api_key_service = Arc::new(services::api_key_service::ApiKeyService::new(api_key_repo));
+
+    let mut password_policy = utils::password_policy::PasswordPolicy::new(&config);
+    if let Some(path) = &config.password_breached_list_file {
+        let breached = utils::password_policy::BreachedPasswords::load(path)?;
+        tracing::info!("Loaded {} breached password hashes", breached.len());
+        password_policy = password_policy.with_breached_list(Arc::new(breached));
+    }
 
     // Create AppState
-    let state = Arc::new(InnerState::new(db, config.clone(), redis, user_service));
+    let state = Arc::new(
+        InnerState::new(db, config.clone(), redis, user_service)
+            .with_password_policy(password_policy)
+            .with_jwt_keys(jwt_keys)
+            .with_api_key_service(api_key_service),
+    );
 
     // Build Router
     let app = create_app(state);
@@ -59,7 +88,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("Listening on {}", addr);
     
     let listener = TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
     Ok(())
 }
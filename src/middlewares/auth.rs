@@ -1,26 +1,181 @@
-use crate::{error::AppError, state::AppState};
+use crate::{
+    config::AppConfig,
+    error::AppError,
+    handlers::auth_handler::Claims,
+    models::api_key::ApiKey,
+    models::permission::Permission,
+    services::token_service::TokenService,
+    state::AppState,
+    utils::jwt,
+};
 use axum::{
-    extract::{Request, State},
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::request::Parts,
     middleware::Next,
     response::Response,
 };
+use tracing::Instrument;
+
+/// The authenticated caller, inserted into request extensions by `auth_middleware`
+/// alongside the verified `Claims`. Permissions are derived from the role claim
+/// through `AppConfig::role_permissions`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthUser {
+    pub id: String,
+    pub role: String,
+    pub permissions: Vec<Permission>,
+    /// Set when the caller authenticated with an API key instead of a user token.
+    pub api_key_id: Option<String>,
+    /// Session of the access token, when it was issued for one.
+    pub session_id: Option<String>,
+    /// The admin behind an impersonation token. `id` is then the impersonated user.
+    pub actor_id: Option<String>,
+}
+
+impl AuthUser {
+    pub fn new(id: impl Into<String>, role: impl Into<String>, permissions: Vec<Permission>) -> Self {
+        Self {
+            id: id.into(),
+            role: role.into(),
+            permissions,
+            api_key_id: None,
+            session_id: None,
+            actor_id: None,
+        }
+    }
+
+    /// The key acts as its owner, limited to the scopes granted to the key.
+    pub fn from_api_key(key: &ApiKey, owner_role: &str, config: &AppConfig) -> Self {
+        let permissions = config
+            .permissions_for(owner_role)
+            .into_iter()
+            .filter(|p| key.scopes.contains(p))
+            .collect();
+
+        Self {
+            api_key_id: Some(key.id.clone()),
+            ..Self::new(key.owner_id.clone(), owner_role, permissions)
+        }
+    }
+
+    pub fn from_claims(claims: &Claims, config: &AppConfig) -> Self {
+        Self {
+            session_id: claims.sid.clone(),
+            actor_id: claims.act.as_ref().map(|actor| actor.sub.clone()),
+            ..Self::new(
+                claims.sub.clone(),
+                claims.role.clone(),
+                config.permissions_for(&claims.role),
+            )
+        }
+    }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+
+    /// For account-takeover operations (password, MFA, sessions, deletion) that support staff
+    /// must not perform while impersonating.
+    pub fn forbid_impersonation(&self) -> Result<(), AppError> {
+        match &self.actor_id {
+            Some(actor) => {
+                tracing::warn!("Admin {} was denied a sensitive operation while impersonating user {}", actor, self.id);
+                Err(AppError::PermissionDenied)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Same check as the `require_permission` layer, for handlers that decide at runtime.
+    pub fn require_permission(&self, permission: Permission) -> Result<(), AppError> {
+        if self.has_permission(permission) {
+            Ok(())
+        } else {
+            Err(AppError::MissingPermission(permission))
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AuthUser {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<AuthUser>()
+            .cloned()
+            .ok_or(AppError::AuthError)
+    }
+}
 
 pub async fn auth_middleware(
-    State(_state): State<AppState>,
-    request: Request,
+    State(state): State<AppState>,
+    mut request: Request,
     next: Next,
 ) -> Result<Response, AppError> {
+    let api_key = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .map(|header| header.to_str().map_err(|_| AppError::AuthError))
+        .transpose()?;
+
+    if let Some(key) = api_key {
+        let user = authenticate_api_key(&state, key).await?;
+        request.extensions_mut().insert(user);
+        return Ok(next.run(request).await);
+    }
+
     let auth_header = request
         .headers()
         .get("Authorization")
         .and_then(|header| header.to_str().ok())
         .ok_or(AppError::AuthError)?;
 
-    if !auth_header.starts_with("Bearer ") {
-        return Err(AppError::AuthError);
-    }
-    
-    Ok(next.run(request).await)
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::AuthError)?;
+
+    let claims = jwt::decode_token(state.jwt_keys()?, token)?;
+    TokenService::new(state.redis.clone(), &state.config)
+        .ensure_not_revoked(&claims)
+        .await?;
+
+    // Everything logged while serving an impersonated request carries both identities.
+    let span = match &claims.act {
+        Some(actor) => {
+            tracing::info!(
+                "Impersonated request {} {} as user {} by admin {}",
+                request.method(),
+                request.uri().path(),
+                claims.sub,
+                actor.sub
+            );
+            tracing::info_span!("impersonation", actor = %actor.sub, user = %claims.sub)
+        }
+        None => tracing::Span::none(),
+    };
+
+    request.extensions_mut().insert(AuthUser::from_claims(&claims, &state.config));
+    request.extensions_mut().insert(claims);
+
+    Ok(next.run(request).instrument(span).await)
+}
+
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+async fn authenticate_api_key(state: &AppState, key: &str) -> Result<AuthUser, AppError> {
+    let api_keys = state.api_key_service.as_ref().ok_or(AppError::AuthError)?;
+    let api_key = api_keys.authenticate(key).await?;
+
+    // The owner's current role bounds the key, so demoting or removing the owner takes effect immediately.
+    let owner = state.user_service.get_user(&api_key.owner_id).await.map_err(|e| match e {
+        AppError::NotFound => AppError::AuthError,
+        e => e,
+    })?;
+
+    Ok(AuthUser::from_api_key(&api_key, &owner.role, &state.config))
 }
 
 #[cfg(test)]
@@ -33,9 +188,35 @@ mod tests {
         Router,
         middleware,
     };
+    use crate::dtos::user::UserResponse;
+    use crate::mock::db_mock::MockRedisProvider;
     use crate::mock::get_mock_state;
+    use crate::mock::services::{api_key_service_mock::MockApiKeyService, user_service_mock::MockUserService};
+    use crate::state::InnerState;
+    use std::sync::Arc;
     use tower::ServiceExt;
 
+    fn state_with_redis(mock_redis: MockRedisProvider) -> AppState {
+        let state = get_mock_state();
+        Arc::new(InnerState::new(
+            state.db.clone(),
+            state.config.clone(),
+            Arc::new(mock_redis),
+            state.user_service.clone(),
+        ))
+    }
+
+    fn not_revoked_redis() -> MockRedisProvider {
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_get().returning(|_| Ok(None));
+        mock_redis
+    }
+
+    fn bearer(state: &AppState, sub: &str, role: &str) -> String {
+        let claims = Claims::new(&state.config, sub.into(), role.into());
+        format!("Bearer {}", jwt::encode_token(state.jwt_keys().unwrap(), &claims).unwrap())
+    }
+
     #[tokio::test]
     async fn test_auth_middleware_missing_header() {
         let state = get_mock_state();
@@ -74,6 +255,28 @@ mod tests {
 
     #[tokio::test]
     async fn test_auth_middleware_success() {
+        let state = state_with_redis(not_revoked_redis());
+        let token = bearer(&state, "user_1", "user");
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(state, auth_middleware));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("Authorization", token)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_unsigned_token() {
         let state = get_mock_state();
         let app = Router::new()
             .route("/", get(|| async { "ok" }))
@@ -90,7 +293,72 @@ mod tests {
             .await
             .unwrap();
 
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_auth_user_extractor() {
+        let state = state_with_redis(not_revoked_redis());
+        let token = bearer(&state, "user_1", "admin");
+        let app = Router::new()
+            .route("/", get(|user: AuthUser| async move {
+                format!("{}:{}:{}", user.id, user.role, user.has_permission(Permission::UsersAdmin))
+            }))
+            .layer(middleware::from_fn_with_state(state, auth_middleware));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("Authorization", token)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
         assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"user_1:admin:true");
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_revoked_token() {
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_get()
+            .withf(|key| key.starts_with("revoked_jti:"))
+            .returning(|_| Ok(Some("1".into())));
+        let state = state_with_redis(mock_redis);
+        let token = bearer(&state, "user_1", "user");
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(state, auth_middleware));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("Authorization", token)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_auth_user_extractor_without_middleware() {
+        let app = Router::new()
+            .route("/", get(|_user: AuthUser| async { "ok" }));
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
 
     #[tokio::test]
@@ -113,4 +381,140 @@ mod tests {
 
         assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
+
+    fn api_key(scopes: Vec<Permission>) -> ApiKey {
+        ApiKey {
+            id: "key_1".into(),
+            name: "batch".into(),
+            prefix: "sk_12345678".into(),
+            key_hash: "hash".into(),
+            owner_id: "owner_1".into(),
+            scopes,
+            expires_at: None,
+            last_used_at: None,
+            revoked_at: None,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    fn state_with_api_keys(api_keys: MockApiKeyService, owner_role: &'static str) -> AppState {
+        let state = get_mock_state();
+        let mut users = MockUserService::new();
+        users.expect_get_user().returning(move |id| Ok(UserResponse {
+            id: id.to_string(),
+            username: "owner".into(),
+            email: "owner@test.com".into(),
+            role: owner_role.into(),
+            email_verified: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }));
+
+        Arc::new(
+            InnerState::new(state.db.clone(), state.config.clone(), state.redis.clone(), Arc::new(users))
+                .with_api_key_service(Arc::new(api_keys)),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_impersonation() {
+        let state = state_with_redis(not_revoked_redis());
+        let claims = Claims::new(&state.config, "user_1".into(), "user".into()).with_actor("admin_1");
+        let token = format!("Bearer {}", jwt::encode_token(state.jwt_keys().unwrap(), &claims).unwrap());
+        let app = Router::new()
+            .route("/", get(|user: AuthUser| async move {
+                format!("{}:{}", user.id, user.forbid_impersonation().is_err())
+            }))
+            .layer(middleware::from_fn_with_state(state, auth_middleware));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("Authorization", token)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"user_1:true");
+    }
+
+    #[test]
+    fn test_from_claims_without_actor() {
+        let config = get_mock_state().config.clone();
+        let user = AuthUser::from_claims(&Claims::new(&config, "user_1".into(), "user".into()), &config);
+        assert!(user.actor_id.is_none());
+        assert!(user.forbid_impersonation().is_ok());
+    }
+
+    #[test]
+    fn test_from_api_key_limits_to_scopes() {
+        let config = get_mock_state().config.clone();
+        let key = api_key(vec![Permission::UsersRead, Permission::UsersAdmin]);
+
+        let user = AuthUser::from_api_key(&key, "user", &config);
+        assert_eq!(user.id, "owner_1");
+        assert_eq!(user.permissions, vec![Permission::UsersRead]);
+        assert_eq!(user.api_key_id.as_deref(), Some("key_1"));
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_api_key() {
+        let mut api_keys = MockApiKeyService::new();
+        api_keys.expect_authenticate()
+            .with(mockall::predicate::eq("sk_valid"))
+            .returning(|_| Ok(api_key(vec![Permission::UsersRead])));
+
+        let state = state_with_api_keys(api_keys, "admin");
+        let app = Router::new()
+            .route("/", get(|user: AuthUser| async move {
+                format!("{}:{}:{:?}", user.id, user.role, user.permissions)
+            }))
+            .layer(middleware::from_fn_with_state(state, auth_middleware));
+
+        let response = app
+            .oneshot(Request::builder().uri("/").header(API_KEY_HEADER, "sk_valid").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"owner_1:admin:[UsersRead]");
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_api_key_rejected() {
+        let mut api_keys = MockApiKeyService::new();
+        api_keys.expect_authenticate().returning(|_| Err(AppError::AuthError));
+
+        let state = state_with_api_keys(api_keys, "user");
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(state, auth_middleware));
+
+        let response = app
+            .oneshot(Request::builder().uri("/").header(API_KEY_HEADER, "sk_revoked").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_api_key_not_configured() {
+        let state = get_mock_state();
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(state, auth_middleware));
+
+        let response = app
+            .oneshot(Request::builder().uri("/").header(API_KEY_HEADER, "sk_valid").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
 }
@@ -0,0 +1,128 @@
+use crate::state::AppState;
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts},
+};
+use std::{convert::Infallible, net::SocketAddr};
+
+/// Best-effort client address for rate limiting and auditing.
+///
+/// Proxy headers are only honoured when `AppConfig::trust_proxy_headers` is set, otherwise
+/// any client could pick its own address. Of `X-Forwarded-For` only the last entry is used:
+/// the proxy appends the address it saw, everything before it is whatever the client sent.
+/// Falls back to the socket peer, then `"unknown"`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientIp(pub String);
+
+#[async_trait]
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if state.config.trust_proxy_headers {
+            let forwarded = parts
+                .headers
+                .get("x-forwarded-for")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.split(',').next_back())
+                .or_else(|| parts.headers.get("x-real-ip").and_then(|v| v.to_str().ok()))
+                .map(str::trim)
+                .filter(|v| !v.is_empty());
+
+            if let Some(ip) = forwarded {
+                return Ok(ClientIp(ip.to_string()));
+            }
+        }
+
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string());
+
+        Ok(ClientIp(peer.unwrap_or_else(|| "unknown".to_string())))
+    }
+}
+
+const MAX_USER_AGENT_LEN: usize = 512;
+
+/// The `User-Agent` header, truncated, for labelling sessions. `None` when absent or not UTF-8.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserAgent(pub Option<String>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for UserAgent {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(UserAgent(
+            parts
+                .headers
+                .get(header::USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.chars().take(MAX_USER_AGENT_LEN).collect()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::get_mock_state;
+    use crate::state::InnerState;
+    use axum::http::Request;
+    use std::sync::Arc;
+
+    async fn extract(state: &AppState, request: Request<()>) -> String {
+        let (mut parts, _) = request.into_parts();
+        ClientIp::from_request_parts(&mut parts, state).await.unwrap().0
+    }
+
+    fn trusting_state() -> AppState {
+        let state = get_mock_state();
+        let mut config = state.config.clone();
+        config.trust_proxy_headers = true;
+        Arc::new(InnerState::new(state.db.clone(), config, state.redis.clone(), state.user_service.clone()))
+    }
+
+    #[tokio::test]
+    async fn test_ignores_proxy_headers_by_default() {
+        let request = Request::builder()
+            .header("x-forwarded-for", "1.2.3.4")
+            .extension(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 5000))))
+            .body(())
+            .unwrap();
+
+        assert_eq!(extract(&get_mock_state(), request).await, "10.0.0.1");
+    }
+
+    #[tokio::test]
+    async fn test_uses_proxy_appended_address_when_trusted() {
+        let request = Request::builder()
+            .header("x-forwarded-for", "1.2.3.4, 10.0.0.1")
+            .body(())
+            .unwrap();
+
+        assert_eq!(extract(&trusting_state(), request).await, "10.0.0.1");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_without_peer() {
+        let request = Request::builder().body(()).unwrap();
+        assert_eq!(extract(&trusting_state(), request).await, "unknown");
+    }
+
+    #[tokio::test]
+    async fn test_user_agent() {
+        let (mut parts, _) = Request::builder()
+            .header("user-agent", "a".repeat(600))
+            .body(())
+            .unwrap()
+            .into_parts();
+        let UserAgent(agent) = UserAgent::from_request_parts(&mut parts, &()).await.unwrap();
+        assert_eq!(agent.unwrap().len(), MAX_USER_AGENT_LEN);
+
+        let (mut parts, _) = Request::builder().body(()).unwrap().into_parts();
+        assert_eq!(UserAgent::from_request_parts(&mut parts, &()).await.unwrap(), UserAgent(None));
+    }
+}
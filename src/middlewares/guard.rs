@@ -0,0 +1,74 @@
+use crate::{error::AppError, middlewares::auth::AuthUser};
+use axum::{
+    extract::Request,
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+type Check = Arc<dyn Fn(&AuthUser) -> Result<(), AppError> + Send + Sync>;
+
+/// Route layer that runs `check` against the authenticated caller before the handler.
+/// Must be layered inside `auth_middleware`; requests without an `AuthUser` get 401.
+#[derive(Clone)]
+pub struct GuardLayer {
+    check: Check,
+}
+
+impl GuardLayer {
+    pub fn new<F>(check: F) -> Self
+    where
+        F: Fn(&AuthUser) -> Result<(), AppError> + Send + Sync + 'static,
+    {
+        Self { check: Arc::new(check) }
+    }
+}
+
+impl<S> Layer<S> for GuardLayer {
+    type Service = Guard<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Guard {
+            inner,
+            check: self.check.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Guard<S> {
+    inner: S,
+    check: Check,
+}
+
+impl<S> Service<Request> for Guard<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let verdict = request
+            .extensions()
+            .get::<AuthUser>()
+            .ok_or(AppError::AuthError)
+            .and_then(|user| (self.check)(user));
+
+        match verdict {
+            Ok(()) => Box::pin(self.inner.call(request)),
+            Err(e) => Box::pin(async move { Ok(e.into_response()) }),
+        }
+    }
+}
@@ -17,7 +17,7 @@ pub async fn logger_middleware(
 
     // Skip logging for these paths (e.g., file uploads, health checks)
     let skip_paths = ["/api/v1/users/update-image-profile", "/health"];
-    let should_skip = skip_paths.iter().any(|&p| path == p);
+    let should_skip = skip_paths.contains(&path);
     
     let response = next.run(request).await;
     
@@ -1,3 +1,7 @@
 pub mod auth;
+pub mod guard;
 pub mod role;
+pub mod permission;
+pub mod ownership;
+pub mod client_ip;
 pub mod logger;
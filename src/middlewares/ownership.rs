@@ -0,0 +1,127 @@
+use crate::{
+    error::AppError,
+    middlewares::auth::AuthUser,
+    models::permission::Permission,
+    state::AppState,
+};
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path},
+    http::request::Parts,
+};
+use std::marker::PhantomData;
+
+/// A resource type addressed by the `:id` path parameter that belongs to a single user.
+#[async_trait]
+pub trait OwnedResource: Send + Sync + 'static {
+    /// Id of the user who owns the resource `id`. Return `AppError::NotFound` for unknown ids.
+    async fn owner_of(state: &AppState, id: &str) -> Result<String, AppError>;
+}
+
+/// User records are owned by the user they describe.
+pub struct UserRecord;
+
+#[async_trait]
+impl OwnedResource for UserRecord {
+    async fn owner_of(_state: &AppState, id: &str) -> Result<String, AppError> {
+        Ok(id.to_string())
+    }
+}
+
+/// The ownership policy: callers may act on what they own, `users:admin` holders on anything.
+pub fn authorize(user: &AuthUser, owner_id: &str) -> Result<(), AppError> {
+    if user.id == owner_id || user.has_permission(Permission::UsersAdmin) {
+        Ok(())
+    } else {
+        Err(AppError::PermissionDenied)
+    }
+}
+
+/// The `:id` path parameter, extracted only when the caller passes [`authorize`] for the
+/// resource it names. Use it instead of `Path<String>` on owner-scoped routes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Owned<R> {
+    pub id: String,
+    resource: PhantomData<fn() -> R>,
+}
+
+impl<R: OwnedResource> Owned<R> {
+    /// Skips the policy; for callers that have already checked ownership.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            resource: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<R: OwnedResource> FromRequestParts<AppState> for Owned<R> {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+        let Path(id) = Path::<String>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| AppError::ValidationError(e.body_text()))?;
+
+        let owner = R::owner_of(state, &id).await?;
+        authorize(&user, &owner)?;
+        Ok(Self::new(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::get_mock_state;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    async fn get_as(user: Option<AuthUser>, uri: &str) -> StatusCode {
+        let app = Router::new()
+            .route("/users/:id", get(|owned: Owned<UserRecord>| async move { owned.id }))
+            .with_state(get_mock_state());
+
+        let mut request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        if let Some(user) = user {
+            request.extensions_mut().insert(user);
+        }
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_owner_allowed() {
+        let user = AuthUser::new("user_1", "user", vec![]);
+        assert_eq!(get_as(Some(user), "/users/user_1").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_other_user_denied() {
+        let user = AuthUser::new("user_1", "user", vec![]);
+        assert_eq!(get_as(Some(user), "/users/user_2").await, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_admin_allowed_on_anyone() {
+        let admin = AuthUser::new("admin_1", "admin", vec![Permission::UsersAdmin]);
+        assert_eq!(get_as(Some(admin), "/users/user_2").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_admin_api_key_without_admin_scope_denied() {
+        let mut key = AuthUser::new("admin_1", "admin", vec![Permission::UsersRead]);
+        key.api_key_id = Some("key_1".to_string());
+        assert_eq!(get_as(Some(key), "/users/user_2").await, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_unauthenticated() {
+        assert_eq!(get_as(None, "/users/user_1").await, StatusCode::UNAUTHORIZED);
+    }
+}
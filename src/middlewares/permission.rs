@@ -0,0 +1,79 @@
+use crate::{
+    middlewares::guard::GuardLayer,
+    models::permission::Permission,
+};
+
+/// Only lets the request through when the authenticated caller holds `permission`.
+/// Must be layered inside `auth_middleware`.
+pub fn require_permission(permission: Permission) -> GuardLayer {
+    GuardLayer::new(move |user| user.require_permission(permission))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::AppError, middlewares::auth::AuthUser};
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    fn request_with(permissions: Option<Vec<Permission>>) -> Request<Body> {
+        let mut request = Request::builder().uri("/").body(Body::empty()).unwrap();
+        if let Some(permissions) = permissions {
+            request.extensions_mut().insert(AuthUser::new("user_1", "user", permissions));
+        }
+        request
+    }
+
+    #[tokio::test]
+    async fn test_require_permission_allows() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }).route_layer(require_permission(Permission::UsersRead)));
+
+        let response = app
+            .oneshot(request_with(Some(vec![Permission::UsersRead])))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_require_permission_reports_missing_permission() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }).route_layer(require_permission(Permission::UsersWrite)));
+
+        let response = app
+            .oneshot(request_with(Some(vec![Permission::UsersRead])))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["details"]["missingPermission"], "users:write");
+    }
+
+    #[tokio::test]
+    async fn test_require_permission_unauthenticated() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }).route_layer(require_permission(Permission::UsersRead)));
+
+        let response = app.oneshot(request_with(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_auth_user_require_permission() {
+        let user = AuthUser::new("user_1", "user", vec![Permission::UsersRead]);
+        assert!(user.has_permission(Permission::UsersRead));
+        assert!(user.require_permission(Permission::UsersRead).is_ok());
+        assert!(matches!(
+            user.require_permission(Permission::UsersAdmin),
+            Err(AppError::MissingPermission(Permission::UsersAdmin))
+        ));
+    }
+}
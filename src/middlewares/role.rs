@@ -1,16 +1,46 @@
-use crate::error::AppError;
+use crate::{
+    error::AppError,
+    middlewares::{auth::AuthUser, guard::GuardLayer},
+    models::user::Role,
+};
 use axum::{
     extract::Request,
     middleware::Next,
     response::Response,
 };
+use std::sync::Arc;
+
+/// Only lets the request through when the authenticated caller has `role`.
+/// Must be layered inside `auth_middleware`.
+pub fn require_role(role: Role) -> GuardLayer {
+    require_any_role([role])
+}
+
+/// Only lets the request through when the authenticated caller has one of `roles`.
+pub fn require_any_role<I: IntoIterator<Item = Role>>(roles: I) -> GuardLayer {
+    let allowed: Arc<[Role]> = roles.into_iter().collect();
+    GuardLayer::new(move |user| check_role(user, &allowed))
+}
 
 pub async fn admin_guard(
-    _request: Request,
+    request: Request,
     next: Next,
 ) -> Result<Response, AppError> {
-    // Placeholder logic - any user is allowed for now
-    Ok(next.run(_request).await)
+    let user = request
+        .extensions()
+        .get::<AuthUser>()
+        .ok_or(AppError::AuthError)?;
+    check_role(user, &[Role::Admin])?;
+
+    Ok(next.run(request).await)
+}
+
+fn check_role(user: &AuthUser, allowed: &[Role]) -> Result<(), AppError> {
+    if allowed.iter().any(|role| user.role == role.as_str()) {
+        Ok(())
+    } else {
+        Err(AppError::PermissionDenied)
+    }
 }
 
 #[cfg(test)]
@@ -25,17 +55,68 @@ mod tests {
     };
     use tower::ServiceExt;
 
+    fn request_as(role: Option<&str>) -> Request<Body> {
+        let mut request = Request::builder().uri("/").body(Body::empty()).unwrap();
+        if let Some(role) = role {
+            request.extensions_mut().insert(AuthUser::new("user_1", role, vec![]));
+        }
+        request
+    }
+
+    #[tokio::test]
+    async fn test_admin_guard_allows_admin() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn(admin_guard));
+
+        let response = app.oneshot(request_as(Some("admin"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
     #[tokio::test]
-    async fn test_admin_guard_always_ok_for_now() {
+    async fn test_admin_guard_rejects_user() {
         let app = Router::new()
             .route("/", get(|| async { "ok" }))
             .layer(middleware::from_fn(admin_guard));
 
-        let response = app
-            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
-            .await
-            .unwrap();
+        let response = app.oneshot(request_as(Some("user"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
 
+    #[tokio::test]
+    async fn test_admin_guard_unauthenticated() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn(admin_guard));
+
+        let response = app.oneshot(request_as(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_require_role() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }).route_layer(require_role(Role::Admin)));
+
+        let response = app.clone().oneshot(request_as(Some("admin"))).await.unwrap();
         assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.clone().oneshot(request_as(Some("user"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app.oneshot(request_as(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_require_any_role() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }).route_layer(require_any_role([Role::Admin, Role::User])));
+
+        let response = app.clone().oneshot(request_as(Some("user"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.oneshot(request_as(Some("guest"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
     }
 }
@@ -17,5 +17,14 @@ mock! {
     impl IRedisProvider for RedisProvider {
         async fn set(&self, key: &str, value: &str) -> Result<(), redis::RedisError>;
         async fn get(&self, key: &str) -> Result<Option<String>, redis::RedisError>;
+        async fn set_ex(&self, key: &str, value: &str, ttl_secs: u64) -> Result<(), redis::RedisError>;
+        async fn set_nx_ex(&self, key: &str, value: &str, ttl_secs: u64) -> Result<bool, redis::RedisError>;
+        async fn del(&self, key: &str) -> Result<(), redis::RedisError>;
+        async fn get_del(&self, key: &str) -> Result<Option<String>, redis::RedisError>;
+        async fn incr_ex(&self, key: &str, ttl_secs: u64) -> Result<i64, redis::RedisError>;
+        async fn ttl(&self, key: &str) -> Result<i64, redis::RedisError>;
+        async fn sadd_ex(&self, key: &str, member: &str, ttl_secs: u64) -> Result<(), redis::RedisError>;
+        async fn smembers(&self, key: &str) -> Result<Vec<String>, redis::RedisError>;
+        async fn srem(&self, key: &str, member: &str) -> Result<(), redis::RedisError>;
     }
 }
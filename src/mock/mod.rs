@@ -1,5 +1,6 @@
 pub mod repositories;
 pub mod services;
+pub mod providers;
 pub mod db_mock;
 
 pub fn get_mock_state() -> crate::state::AppState {
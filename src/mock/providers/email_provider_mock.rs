@@ -0,0 +1,11 @@
+use crate::providers::email::IEmailProvider;
+use mockall::mock;
+use async_trait::async_trait;
+
+mock! {
+    pub EmailProvider {}
+    #[async_trait]
+    impl IEmailProvider for EmailProvider {
+        async fn send_email(&self, to: &str, subject: &str, body: &str) -> Result<(), String>;
+    }
+}
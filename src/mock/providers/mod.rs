@@ -0,0 +1 @@
+pub mod email_provider_mock;
@@ -0,0 +1,17 @@
+use crate::repositories::api_key_repository::IApiKeyRepository;
+use crate::models::api_key::ApiKey;
+use chrono::{DateTime, Utc};
+use mockall::mock;
+use async_trait::async_trait;
+
+mock! {
+    pub ApiKeyRepository {}
+    #[async_trait]
+    impl IApiKeyRepository for ApiKeyRepository {
+        async fn create(&self, key: &ApiKey) -> Result<(), mongodb::error::Error>;
+        async fn find_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, mongodb::error::Error>;
+        async fn find_all(&self) -> Result<Vec<ApiKey>, mongodb::error::Error>;
+        async fn revoke(&self, id: &str, at: DateTime<Utc>) -> Result<bool, mongodb::error::Error>;
+        async fn touch_last_used(&self, id: &str, at: DateTime<Utc>) -> Result<(), mongodb::error::Error>;
+    }
+}
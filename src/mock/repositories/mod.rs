@@ -1 +1,2 @@
 pub mod user_repository_mock;
+pub mod api_key_repository_mock;
@@ -1,5 +1,7 @@
 use crate::repositories::user_repository::IUserRepository;
-use crate::models::user::User;
+use crate::models::user::{User, UserFilter};
+use crate::utils::pagination::Cursor;
+use chrono::{DateTime, Utc};
 use mockall::mock;
 use async_trait::async_trait;
 
@@ -10,8 +12,17 @@ mock! {
         async fn create(&self, user: &User) -> Result<String, mongodb::error::Error>;
         async fn find_by_id(&self, id: &str) -> Result<Option<User>, mongodb::error::Error>;
         async fn find_by_email(&self, email: &str) -> Result<Option<User>, mongodb::error::Error>;
+        async fn find_by_identity(&self, provider: &str, subject: &str) -> Result<Option<User>, mongodb::error::Error>;
         async fn update(&self, id: &str, update_doc: mongodb::bson::Document) -> Result<(), mongodb::error::Error>;
-        async fn find_all(&self, skip: u64, limit: i64) -> Result<Vec<User>, mongodb::error::Error>;
-        async fn count(&self) -> Result<u64, mongodb::error::Error>;
+        async fn update_if(&self, id: &str, condition: mongodb::bson::Document, update_doc: mongodb::bson::Document) -> Result<bool, mongodb::error::Error>;
+        async fn find_all(&self, filter: &UserFilter, skip: u64, limit: i64) -> Result<Vec<User>, mongodb::error::Error>;
+        async fn count(&self, filter: &UserFilter) -> Result<u64, mongodb::error::Error>;
+        async fn find_page(&self, filter: &UserFilter, after: Option<Cursor>, limit: i64) -> Result<Vec<User>, mongodb::error::Error>;
+        async fn email_exists(&self, email: &str) -> Result<bool, mongodb::error::Error>;
+        async fn mfa_exists(&self) -> Result<bool, mongodb::error::Error>;
+        async fn find_deleted_by_id(&self, id: &str) -> Result<Option<User>, mongodb::error::Error>;
+        async fn soft_delete(&self, id: &str, at: DateTime<Utc>) -> Result<bool, mongodb::error::Error>;
+        async fn restore(&self, id: &str, at: DateTime<Utc>) -> Result<bool, mongodb::error::Error>;
+        async fn purge(&self, id: &str, deleted_before: DateTime<Utc>) -> Result<bool, mongodb::error::Error>;
     }
 }
@@ -0,0 +1,17 @@
+use crate::services::api_key_service::IApiKeyService;
+use crate::dtos::api_key::{ApiKeyResponse, CreateApiKey, CreatedApiKey};
+use crate::models::api_key::ApiKey;
+use crate::error::AppError;
+use mockall::mock;
+use async_trait::async_trait;
+
+mock! {
+    pub ApiKeyService {}
+    #[async_trait]
+    impl IApiKeyService for ApiKeyService {
+        async fn create_key(&self, owner_id: &str, input: CreateApiKey) -> Result<CreatedApiKey, AppError>;
+        async fn list_keys(&self) -> Result<Vec<ApiKeyResponse>, AppError>;
+        async fn revoke_key(&self, id: &str) -> Result<(), AppError>;
+        async fn authenticate(&self, key: &str) -> Result<ApiKey, AppError>;
+    }
+}
@@ -1 +1,2 @@
 pub mod user_service_mock;
+pub mod api_key_service_mock;
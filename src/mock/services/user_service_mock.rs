@@ -1,8 +1,9 @@
 use crate::services::user_service::IUserService;
-use crate::dtos::user::{CreateUser, UpdateUser, UserResponse};
-use crate::models::user::User;
+use crate::dtos::user::{ChangePassword, CreateUser, ExternalProfile, UpdateUser, UserResponse};
+use crate::models::user::{User, UserFilter, UserMfa};
 use crate::error::AppError;
-use crate::utils::pagination::PaginationResult;
+use crate::utils::pagination::{CursorPage, PaginationResult};
+use chrono::{DateTime, Utc};
 use mockall::mock;
 use async_trait::async_trait;
 
@@ -12,8 +13,21 @@ mock! {
     impl IUserService for UserService {
         async fn create_user(&self, input: CreateUser) -> Result<UserResponse, AppError>;
         async fn get_user(&self, id: &str) -> Result<UserResponse, AppError>;
-        async fn list_users(&self, page: Option<u64>, limit: Option<u64>) -> Result<PaginationResult<UserResponse>, AppError>;
+        async fn list_users(&self, filter: UserFilter, page: Option<u64>, limit: Option<u64>) -> Result<PaginationResult<UserResponse>, AppError>;
+        async fn list_users_after(&self, filter: UserFilter, cursor: Option<String>, limit: Option<u64>) -> Result<CursorPage<UserResponse>, AppError>;
         async fn update_user(&self, id: &str, input: UpdateUser) -> Result<(), AppError>;
         async fn authenticate(&self, email: &str, password: &str) -> Result<User, AppError>;
+        async fn find_by_email(&self, email: &str) -> Result<Option<UserResponse>, AppError>;
+        async fn set_password(&self, id: &str, password: &str) -> Result<(), AppError>;
+        async fn change_password(&self, id: &str, input: ChangePassword) -> Result<UserResponse, AppError>;
+        async fn mark_email_verified(&self, id: &str) -> Result<(), AppError>;
+        async fn get_mfa(&self, id: &str) -> Result<Option<UserMfa>, AppError>;
+        async fn set_mfa(&self, id: &str, mfa: Option<UserMfa>) -> Result<(), AppError>;
+        async fn replace_mfa(&self, id: &str, current: &UserMfa, mfa: Option<UserMfa>) -> Result<(), AppError>;
+        async fn any_mfa(&self) -> Result<bool, AppError>;
+        async fn find_or_create_external(&self, profile: ExternalProfile) -> Result<User, AppError>;
+        async fn delete_user(&self, id: &str) -> Result<(), AppError>;
+        async fn restore_user(&self, id: &str) -> Result<UserResponse, AppError>;
+        async fn purge_user(&self, id: &str, deleted_before: DateTime<Utc>) -> Result<(), AppError>;
     }
 }
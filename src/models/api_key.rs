@@ -0,0 +1,32 @@
+use crate::models::permission::Permission;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A long-lived credential for service-to-service calls. Only the SHA-256 of the key is stored;
+/// `prefix` is kept in clear so keys can be told apart in listings and logs.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKey {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub name: String,
+    pub prefix: String,
+    pub key_hash: String,
+    /// The user the key acts on behalf of.
+    pub owner_id: String,
+    pub scopes: Vec<Permission>,
+    #[serde(default, with = "bson::serde_helpers::chrono_datetime_as_bson_datetime_optional")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "bson::serde_helpers::chrono_datetime_as_bson_datetime_optional")]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "bson::serde_helpers::chrono_datetime_as_bson_datetime_optional")]
+    pub revoked_at: Option<DateTime<Utc>>,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiKey {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|exp| exp > now)
+    }
+}
@@ -1 +1,4 @@
 pub mod user;
+pub mod permission;
+pub mod api_key;
+pub mod session;
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    #[serde(rename = "users:read")]
+    UsersRead,
+    #[serde(rename = "users:write")]
+    UsersWrite,
+    #[serde(rename = "users:admin")]
+    UsersAdmin,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::UsersRead => "users:read",
+            Permission::UsersWrite => "users:write",
+            Permission::UsersAdmin => "users:admin",
+        }
+    }
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_serde_names() {
+        let json = serde_json::to_string(&Permission::UsersWrite).unwrap();
+        assert_eq!(json, "\"users:write\"");
+
+        let parsed: Permission = serde_json::from_str("\"users:admin\"").unwrap();
+        assert_eq!(parsed, Permission::UsersAdmin);
+        assert_eq!(Permission::UsersRead.to_string(), "users:read");
+    }
+}
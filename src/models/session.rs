@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A signed-in device. `id` is the refresh-token family, so the session lives exactly as
+/// long as its refresh tokens do. Stored in Redis next to the family.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub id: String,
+    pub user_id: String,
+    pub ip: String,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Updated on login and on every refresh, so it lags by at most one access-token lifetime.
+    pub last_seen_at: DateTime<Utc>,
+}
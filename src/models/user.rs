@@ -10,8 +10,155 @@ pub struct User {
     pub email: String,
     pub password_hash: String,
     pub role: String, // "admin", "user"
+    #[serde(default)]
+    pub email_verified: bool,
+    #[serde(default)]
+    pub mfa: Option<UserMfa>,
+    /// Accounts at external OpenID Connect providers that can sign in as this user.
+    #[serde(default)]
+    pub identities: Vec<ExternalIdentity>,
     #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
     pub created_at: DateTime<Utc>,
     #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
     pub updated_at: DateTime<Utc>,
+    /// Set by a soft delete. Such users are hidden from lookups until restored or purged.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bson::serde_helpers::chrono_datetime_as_bson_datetime_optional"
+    )]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// TOTP second factor. `enabled` stays false until the first code has been confirmed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UserMfa {
+    pub enabled: bool,
+    /// AES-256-GCM encrypted TOTP secret.
+    pub secret: String,
+    /// SHA-256 hashes of the unused recovery codes.
+    #[serde(default)]
+    pub recovery_codes: Vec<String>,
+    /// Last accepted TOTP time step, so a code cannot be replayed.
+    #[serde(default)]
+    pub last_used_step: Option<i64>,
+}
+
+/// A linked account, keyed by the provider name from config and the provider's `sub` claim.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalIdentity {
+    pub provider: String,
+    pub subject: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Admin,
+    User,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::User => "user",
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Fields `GET /users` can be sorted by. Each has an index, see `UserRepository::ensure_indexes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UserSortField {
+    #[default]
+    CreatedAt,
+    Username,
+    Email,
+}
+
+impl UserSortField {
+    pub const ALL: [UserSortField; 3] = [UserSortField::CreatedAt, UserSortField::Username, UserSortField::Email];
+
+    /// Name in the API and in the stored document.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserSortField::CreatedAt => "createdAt",
+            UserSortField::Username => "username",
+            UserSortField::Email => "email",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// `field:asc|desc`, newest users first by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UserSort {
+    pub field: UserSortField,
+    pub direction: SortDirection,
+}
+
+impl std::str::FromStr for UserSort {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (field, direction) = value.split_once(':').unwrap_or((value, "asc"));
+
+        let field = UserSortField::ALL
+            .into_iter()
+            .find(|f| f.as_str() == field)
+            .ok_or_else(|| {
+                let allowed: Vec<_> = UserSortField::ALL.iter().map(|f| f.as_str()).collect();
+                format!("Cannot sort by '{}', use one of: {}", field, allowed.join(", "))
+            })?;
+        let direction = match direction {
+            "asc" => SortDirection::Asc,
+            "desc" => SortDirection::Desc,
+            other => return Err(format!("Sort direction must be 'asc' or 'desc', not '{}'", other)),
+        };
+
+        Ok(Self { field, direction })
+    }
+}
+
+/// Criteria for listing users. Soft-deleted users are always excluded.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UserFilter {
+    pub role: Option<Role>,
+    pub created_from: Option<DateTime<Utc>>,
+    pub created_to: Option<DateTime<Utc>>,
+    /// Case-insensitive prefix of the username or email.
+    pub search: Option<String>,
+    pub sort: UserSort,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_user_sort() {
+        let sort: UserSort = "username:asc".parse().unwrap();
+        assert_eq!(sort, UserSort { field: UserSortField::Username, direction: SortDirection::Asc });
+
+        let sort: UserSort = "createdAt:desc".parse().unwrap();
+        assert_eq!(sort, UserSort::default());
+
+        assert_eq!("email".parse::<UserSort>().unwrap().direction, SortDirection::Asc);
+        assert!("passwordHash:asc".parse::<UserSort>().is_err());
+        assert!("email:up".parse::<UserSort>().is_err());
+    }
 }
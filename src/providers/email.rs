@@ -1,14 +1,20 @@
-#[allow(dead_code)]
-#[derive(Clone)]
+#[async_trait::async_trait]
+pub trait IEmailProvider: Send + Sync {
+    async fn send_email(&self, to: &str, subject: &str, body: &str) -> Result<(), String>;
+}
+
+#[derive(Clone, Default)]
 pub struct EmailProvider;
 
-#[allow(dead_code)]
 impl EmailProvider {
     pub fn new() -> Self {
         Self
     }
+}
 
-    pub async fn send_email(&self, to: &str, subject: &str, body: &str) -> Result<(), String> {
+#[async_trait::async_trait]
+impl IEmailProvider for EmailProvider {
+    async fn send_email(&self, to: &str, subject: &str, body: &str) -> Result<(), String> {
         // Placeholder for lettre or other email service
         println!("Sending email to: {}, Subject: {}, Body: {}", to, subject, body);
         Ok(())
@@ -0,0 +1,121 @@
+use crate::models::api_key::ApiKey;
+use chrono::{DateTime, Utc};
+use mongodb::{
+    bson::doc,
+    options::IndexOptions,
+    Collection, IndexModel,
+};
+use futures::stream::TryStreamExt;
+
+use async_trait::async_trait;
+#[async_trait]
+pub trait IApiKeyRepository: Send + Sync {
+    async fn create(&self, key: &ApiKey) -> Result<(), mongodb::error::Error>;
+    async fn find_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, mongodb::error::Error>;
+    async fn find_all(&self) -> Result<Vec<ApiKey>, mongodb::error::Error>;
+    /// Returns `false` when no unrevoked key has this id.
+    async fn revoke(&self, id: &str, at: DateTime<Utc>) -> Result<bool, mongodb::error::Error>;
+    async fn touch_last_used(&self, id: &str, at: DateTime<Utc>) -> Result<(), mongodb::error::Error>;
+}
+
+#[derive(Clone)]
+pub struct ApiKeyRepository {
+    collection: Collection<ApiKey>,
+}
+
+use crate::db::mongo::IMongoProvider;
+
+impl ApiKeyRepository {
+    pub fn new(db: &dyn IMongoProvider) -> Self {
+        Self {
+            collection: db.database().collection("api_keys"),
+        }
+    }
+
+    /// Creates the `keyHash` lookup index used by every `X-API-Key` request and the `ownerId`
+    /// index for listings. Safe to run on each start.
+    pub async fn ensure_indexes(&self) -> Result<(), mongodb::error::Error> {
+        let indexes = [
+            IndexModel::builder()
+                .keys(doc! { "keyHash": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            IndexModel::builder().keys(doc! { "ownerId": 1 }).build(),
+        ];
+        self.collection.create_indexes(indexes, None).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl IApiKeyRepository for ApiKeyRepository {
+    async fn create(&self, key: &ApiKey) -> Result<(), mongodb::error::Error> {
+        self.collection.insert_one(key, None).await?;
+        Ok(())
+    }
+
+    async fn find_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, mongodb::error::Error> {
+        self.collection.find_one(doc! { "keyHash": key_hash }, None).await
+    }
+
+    async fn find_all(&self) -> Result<Vec<ApiKey>, mongodb::error::Error> {
+        let find_options = mongodb::options::FindOptions::builder()
+            .sort(doc! { "createdAt": -1 })
+            .build();
+
+        let mut cursor = self.collection.find(None, find_options).await?;
+        let mut keys = Vec::new();
+        while let Some(key) = cursor.try_next().await? {
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+
+    async fn revoke(&self, id: &str, at: DateTime<Utc>) -> Result<bool, mongodb::error::Error> {
+        let result = self
+            .collection
+            .update_one(
+                doc! { "_id": id, "revokedAt": null },
+                doc! { "$set": { "revokedAt": bson::DateTime::from_chrono(at) } },
+                None,
+            )
+            .await?;
+        Ok(result.matched_count > 0)
+    }
+
+    async fn touch_last_used(&self, id: &str, at: DateTime<Utc>) -> Result<(), mongodb::error::Error> {
+        self.collection
+            .update_one(
+                doc! { "_id": id },
+                doc! { "$set": { "lastUsedAt": bson::DateTime::from_chrono(at) } },
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::db_mock::MockMongoProvider;
+    use mongodb::options::ClientOptions;
+    use mongodb::Client;
+
+    #[tokio::test]
+    async fn test_api_key_repository_methods_error() {
+        let mut mock_db = MockMongoProvider::new();
+        let client_options = ClientOptions::parse("mongodb://localhost:27017/?serverSelectionTimeoutMS=100")
+            .await
+            .unwrap();
+        let client = Client::with_options(client_options).unwrap();
+        mock_db.expect_database().return_const(client.database("test"));
+        let repo = ApiKeyRepository::new(&mock_db);
+
+        // No server is running, so every call fails, but each method is exercised.
+        assert!(repo.find_by_hash("hash").await.is_err());
+        assert!(repo.find_all().await.is_err());
+        assert!(repo.revoke("id", Utc::now()).await.is_err());
+        assert!(repo.touch_last_used("id", Utc::now()).await.is_err());
+    }
+}
@@ -1 +1,2 @@
 pub mod user_repository;
+pub mod api_key_repository;
@@ -1,7 +1,10 @@
-use crate::models::user::User;
+use crate::models::user::{SortDirection, User, UserFilter, UserSortField};
+use crate::utils::pagination::Cursor;
+use chrono::{DateTime, Utc};
 use mongodb::{
     bson::doc,
-    Collection,
+    options::IndexOptions,
+    Collection, IndexModel,
 };
 use futures::stream::TryStreamExt;
 
@@ -11,9 +14,24 @@ pub trait IUserRepository: Send + Sync {
     async fn create(&self, user: &User) -> Result<String, mongodb::error::Error>;
     async fn find_by_id(&self, id: &str) -> Result<Option<User>, mongodb::error::Error>;
     async fn find_by_email(&self, email: &str) -> Result<Option<User>, mongodb::error::Error>;
+    async fn find_by_identity(&self, provider: &str, subject: &str) -> Result<Option<User>, mongodb::error::Error>;
     async fn update(&self, id: &str, update_doc: mongodb::bson::Document) -> Result<(), mongodb::error::Error>;
-    async fn find_all(&self, skip: u64, limit: i64) -> Result<Vec<User>, mongodb::error::Error>;
-    async fn count(&self) -> Result<u64, mongodb::error::Error>;
+    /// Like `update`, but only while the user still matches `condition`. Returns whether it did.
+    async fn update_if(&self, id: &str, condition: mongodb::bson::Document, update_doc: mongodb::bson::Document) -> Result<bool, mongodb::error::Error>;
+    async fn find_all(&self, filter: &UserFilter, skip: u64, limit: i64) -> Result<Vec<User>, mongodb::error::Error>;
+    async fn count(&self, filter: &UserFilter) -> Result<u64, mongodb::error::Error>;
+    /// Keyset page in `(createdAt, _id)` order, starting after `after`. Ignores `filter.sort.field`.
+    async fn find_page(&self, filter: &UserFilter, after: Option<Cursor>, limit: i64) -> Result<Vec<User>, mongodb::error::Error>;
+    /// Also sees soft-deleted users, whose addresses stay reserved until they are purged.
+    async fn email_exists(&self, email: &str) -> Result<bool, mongodb::error::Error>;
+    /// Whether any account, deleted ones included, has a TOTP secret stored.
+    async fn mfa_exists(&self) -> Result<bool, mongodb::error::Error>;
+    async fn find_deleted_by_id(&self, id: &str) -> Result<Option<User>, mongodb::error::Error>;
+    async fn soft_delete(&self, id: &str, at: DateTime<Utc>) -> Result<bool, mongodb::error::Error>;
+    async fn restore(&self, id: &str, at: DateTime<Utc>) -> Result<bool, mongodb::error::Error>;
+    /// Permanently removes a user soft-deleted at or before `deleted_before`. The check is part
+    /// of the delete, so a restore and a new delete in between cannot slip through.
+    async fn purge(&self, id: &str, deleted_before: DateTime<Utc>) -> Result<bool, mongodb::error::Error>;
 }
 
 #[derive(Clone)]
@@ -29,6 +47,122 @@ impl UserRepository {
             collection: db.database().collection("users"),
         }
     }
+
+    /// Creates the indexes behind every `UserSortField`. Safe to run on each start.
+    pub async fn ensure_indexes(&self) -> Result<(), mongodb::error::Error> {
+        self.collection.create_indexes(sort_indexes(), None).await?;
+        Ok(())
+    }
+}
+
+/// `(createdAt, _id)` in the requested direction, served by the `createdAt` sort index.
+fn keyset_sort(filter: &UserFilter) -> mongodb::bson::Document {
+    list_sort(&UserFilter {
+        sort: crate::models::user::UserSort { field: UserSortField::CreatedAt, ..filter.sort },
+        ..filter.clone()
+    })
+}
+
+/// One `(field, _id)` index per sort field, matching `list_sort` including its tie-break.
+/// Lists only ever see active users, so soft-deleted ones are left out of the index.
+fn sort_indexes() -> Vec<IndexModel> {
+    UserSortField::ALL
+        .iter()
+        .map(|field| {
+            IndexModel::builder()
+                .keys(doc! { field.as_str(): 1, "_id": 1 })
+                .options(
+                    IndexOptions::builder()
+                        .partial_filter_expression(doc! { "deletedAt": null })
+                        .build(),
+                )
+                .build()
+        })
+        .collect()
+}
+
+/// Matches users that have not been soft-deleted. Every regular lookup goes through this.
+fn active(mut filter: mongodb::bson::Document) -> mongodb::bson::Document {
+    filter.insert("deletedAt", mongodb::bson::Bson::Null);
+    filter
+}
+
+fn deleted(mut filter: mongodb::bson::Document) -> mongodb::bson::Document {
+    filter.insert("deletedAt", doc! { "$ne": null });
+    filter
+}
+
+fn list_filter(filter: &UserFilter) -> mongodb::bson::Document {
+    let mut query = active(doc! {});
+
+    if let Some(role) = filter.role {
+        query.insert("role", role.as_str());
+    }
+
+    let mut created = doc! {};
+    if let Some(from) = filter.created_from {
+        created.insert("$gte", bson::DateTime::from_chrono(from));
+    }
+    if let Some(to) = filter.created_to {
+        created.insert("$lte", bson::DateTime::from_chrono(to));
+    }
+    if !created.is_empty() {
+        query.insert("createdAt", created);
+    }
+
+    if let Some(search) = &filter.search {
+        let prefix = format!("^{}", escape_regex(search));
+        query.insert(
+            "$or",
+            vec![
+                doc! { "username": { "$regex": &prefix, "$options": "i" } },
+                doc! { "email": { "$regex": &prefix, "$options": "i" } },
+            ],
+        );
+    }
+
+    query
+}
+
+/// `_id` breaks ties so pages stay stable when many users share the sort value.
+fn list_sort(filter: &UserFilter) -> mongodb::bson::Document {
+    let direction = match filter.sort.direction {
+        SortDirection::Asc => 1,
+        SortDirection::Desc => -1,
+    };
+    doc! { filter.sort.field.as_str(): direction, "_id": direction }
+}
+
+fn keyset_filter(filter: &UserFilter, after: Option<&Cursor>) -> mongodb::bson::Document {
+    let query = list_filter(filter);
+    let Some(after) = after else {
+        return query;
+    };
+
+    let op = match filter.sort.direction {
+        SortDirection::Asc => "$gt",
+        SortDirection::Desc => "$lt",
+    };
+    let created_at = bson::DateTime::from_chrono(after.created_at);
+    let position = doc! {
+        "$or": [
+            { "createdAt": { op: created_at } },
+            { "createdAt": created_at, "_id": { op: &after.id } },
+        ]
+    };
+    doc! { "$and": [query, position] }
+}
+
+/// Search text is matched literally, not as a pattern.
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
 }
 
 #[async_trait]
@@ -39,28 +173,61 @@ impl IUserRepository for UserRepository {
     }
 
     async fn find_by_id(&self, id: &str) -> Result<Option<User>, mongodb::error::Error> {
-        self.collection.find_one(doc! { "_id": id }, None).await
+        self.collection.find_one(active(doc! { "_id": id }), None).await
     }
 
     async fn find_by_email(&self, email: &str) -> Result<Option<User>, mongodb::error::Error> {
         self.collection
-            .find_one(doc! { "email": email }, None)
+            .find_one(active(doc! { "email": email }), None)
+            .await
+    }
+
+    async fn find_by_identity(&self, provider: &str, subject: &str) -> Result<Option<User>, mongodb::error::Error> {
+        self.collection
+            .find_one(
+                active(doc! { "identities": { "$elemMatch": { "provider": provider, "subject": subject } } }),
+                None,
+            )
             .await
     }
 
     async fn update(&self, id: &str, update_doc: mongodb::bson::Document) -> Result<(), mongodb::error::Error> {
-         self.collection.update_one(doc! { "_id": id }, doc! { "$set": update_doc }, None).await?;
+         self.collection.update_one(active(doc! { "_id": id }), doc! { "$set": update_doc }, None).await?;
          Ok(())
     }
+
+    async fn update_if(&self, id: &str, mut condition: mongodb::bson::Document, update_doc: mongodb::bson::Document) -> Result<bool, mongodb::error::Error> {
+        condition.insert("_id", id);
+        let result = self.collection.update_one(active(condition), doc! { "$set": update_doc }, None).await?;
+        Ok(result.matched_count > 0)
+    }
     
-    async fn find_all(&self, skip: u64, limit: i64) -> Result<Vec<User>, mongodb::error::Error> {
+    async fn find_all(&self, filter: &UserFilter, skip: u64, limit: i64) -> Result<Vec<User>, mongodb::error::Error> {
         let find_options = mongodb::options::FindOptions::builder()
             .skip(skip)
             .limit(limit)
-            .sort(doc! { "created_at": -1 })
+            .sort(list_sort(filter))
             .build();
             
-        let mut cursor = self.collection.find(None, find_options).await?;
+        let mut cursor = self.collection.find(list_filter(filter), find_options).await?;
+        let mut users = Vec::new();
+        while let Some(user) = cursor.try_next().await? {
+            users.push(user);
+        }
+        Ok(users)
+    }
+
+    async fn count(&self, filter: &UserFilter) -> Result<u64, mongodb::error::Error> {
+        self.collection.count_documents(list_filter(filter), None).await
+    }
+
+    async fn find_page(&self, filter: &UserFilter, after: Option<Cursor>, limit: i64) -> Result<Vec<User>, mongodb::error::Error> {
+        let find_options = mongodb::options::FindOptions::builder()
+            .limit(limit)
+            .sort(keyset_sort(filter))
+            .build();
+
+        let mut cursor = self.collection.find(keyset_filter(filter, after.as_ref()), find_options).await?;
         let mut users = Vec::new();
         while let Some(user) = cursor.try_next().await? {
             users.push(user);
@@ -68,8 +235,53 @@ impl IUserRepository for UserRepository {
         Ok(users)
     }
 
-    async fn count(&self) -> Result<u64, mongodb::error::Error> {
-        self.collection.count_documents(None, None).await
+    async fn email_exists(&self, email: &str) -> Result<bool, mongodb::error::Error> {
+        Ok(self.collection.count_documents(doc! { "email": email }, None).await? > 0)
+    }
+
+    async fn mfa_exists(&self) -> Result<bool, mongodb::error::Error> {
+        Ok(self.collection.find_one(doc! { "mfa": { "$ne": null } }, None).await?.is_some())
+    }
+
+    async fn find_deleted_by_id(&self, id: &str) -> Result<Option<User>, mongodb::error::Error> {
+        self.collection.find_one(deleted(doc! { "_id": id }), None).await
+    }
+
+    async fn soft_delete(&self, id: &str, at: DateTime<Utc>) -> Result<bool, mongodb::error::Error> {
+        let at = bson::DateTime::from_chrono(at);
+        let result = self
+            .collection
+            .update_one(
+                active(doc! { "_id": id }),
+                doc! { "$set": { "deletedAt": at, "updatedAt": at } },
+                None,
+            )
+            .await?;
+        Ok(result.matched_count > 0)
+    }
+
+    async fn restore(&self, id: &str, at: DateTime<Utc>) -> Result<bool, mongodb::error::Error> {
+        let result = self
+            .collection
+            .update_one(
+                deleted(doc! { "_id": id }),
+                doc! {
+                    "$unset": { "deletedAt": "" },
+                    "$set": { "updatedAt": bson::DateTime::from_chrono(at) },
+                },
+                None,
+            )
+            .await?;
+        Ok(result.matched_count > 0)
+    }
+
+    async fn purge(&self, id: &str, deleted_before: DateTime<Utc>) -> Result<bool, mongodb::error::Error> {
+        let filter = doc! {
+            "_id": id,
+            "deletedAt": { "$ne": null, "$lte": bson::DateTime::from_chrono(deleted_before) },
+        };
+        let result = self.collection.delete_one(filter, None).await?;
+        Ok(result.deleted_count > 0)
     }
 }
 
@@ -83,7 +295,7 @@ mod tests {
     #[tokio::test]
     async fn test_user_repository_methods_error() {
         let mut mock_db = MockMongoProvider::new();
-        let client_options = ClientOptions::parse("mongodb://localhost:27017").await.unwrap();
+        let client_options = ClientOptions::parse("mongodb://localhost:27017/?serverSelectionTimeoutMS=100").await.unwrap();
         let client = Client::with_options(client_options).unwrap();
         let dummy_db = client.database("test");
         mock_db.expect_database().return_const(dummy_db);
@@ -92,17 +304,98 @@ mod tests {
         // This will attempt to connect and fail, but it covers the method call line.
         let _ = repo.find_by_id("id").await;
         let _ = repo.find_by_email("email").await;
-        let _ = repo.count().await;
-        let _ = repo.find_all(0, 10).await;
+        let _ = repo.find_by_identity("google", "sub").await;
+        let _ = repo.count(&UserFilter::default()).await;
+        let _ = repo.find_all(&UserFilter::default(), 0, 10).await;
+        let _ = repo.find_page(&UserFilter::default(), None, 10).await;
         let _ = repo.update("id", mongodb::bson::doc! {}).await;
+        let _ = repo.update_if("id", mongodb::bson::doc! {}, mongodb::bson::doc! {}).await;
+        let _ = repo.email_exists("email").await;
+        let _ = repo.mfa_exists().await;
+        let _ = repo.find_deleted_by_id("id").await;
+        let _ = repo.soft_delete("id", chrono::Utc::now()).await;
+        let _ = repo.restore("id", chrono::Utc::now()).await;
+        let _ = repo.purge("id", chrono::Utc::now()).await;
         let _ = repo.create(&crate::models::user::User {
              id: None,
              username: "test".into(),
              email: "test@test.com".into(),
              password_hash: "hash".into(),
              role: "user".into(),
+             email_verified: false,
+             mfa: None,
+             identities: vec![],
              created_at: chrono::Utc::now(),
              updated_at: chrono::Utc::now(),
+             deleted_at: None,
         }).await;
     }
+
+    #[test]
+    fn test_list_filter() {
+        let filter = UserFilter {
+            role: Some(crate::models::user::Role::Admin),
+            created_from: Some(chrono::Utc::now()),
+            search: Some("a.b+".into()),
+            ..Default::default()
+        };
+        let query = list_filter(&filter);
+
+        assert_eq!(query.get("deletedAt"), Some(&mongodb::bson::Bson::Null));
+        assert_eq!(query.get_str("role").unwrap(), "admin");
+        let created = query.get_document("createdAt").unwrap();
+        assert!(created.contains_key("$gte") && !created.contains_key("$lte"));
+
+        let or = query.get_array("$or").unwrap();
+        let username = or[0].as_document().unwrap().get_document("username").unwrap();
+        assert_eq!(username.get_str("$regex").unwrap(), r"^a\.b\+");
+        assert_eq!(username.get_str("$options").unwrap(), "i");
+    }
+
+    #[test]
+    fn test_keyset_filter() {
+        let after = Cursor { created_at: chrono::Utc::now(), id: "user_9".into() };
+        let query = keyset_filter(&UserFilter::default(), Some(&after));
+
+        let and = query.get_array("$and").unwrap();
+        assert_eq!(and[0].as_document().unwrap(), &doc! { "deletedAt": null });
+        let position = and[1].as_document().unwrap().get_array("$or").unwrap();
+        let tie = position[1].as_document().unwrap();
+        assert_eq!(tie.get_document("_id").unwrap().get_str("$lt").unwrap(), "user_9");
+
+        assert_eq!(keyset_filter(&UserFilter::default(), None), doc! { "deletedAt": null });
+    }
+
+    #[test]
+    fn test_sort_indexes_match_list_sort() {
+        for (index, field) in sort_indexes().iter().zip(UserSortField::ALL) {
+            let keys: Vec<&String> = index.keys.keys().collect();
+            assert_eq!(keys, [field.as_str(), "_id"]);
+
+            let partial = index.options.as_ref().and_then(|o| o.partial_filter_expression.as_ref());
+            assert_eq!(partial, Some(&doc! { "deletedAt": null }));
+        }
+    }
+
+    #[test]
+    fn test_keyset_sort_uses_created_at_index() {
+        let filter = UserFilter {
+            sort: crate::models::user::UserSort { field: UserSortField::Email, direction: SortDirection::Asc },
+            ..Default::default()
+        };
+        assert_eq!(keyset_sort(&filter), doc! { "createdAt": 1, "_id": 1 });
+
+        let indexes = sort_indexes();
+        let index = indexes.iter().find(|i| i.keys.contains_key("createdAt")).unwrap();
+        let keys: Vec<&String> = index.keys.keys().collect();
+        let sort = keyset_sort(&filter);
+        assert_eq!(keys, sort.keys().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_list_filter_defaults() {
+        let filter = UserFilter::default();
+        assert_eq!(list_filter(&filter), doc! { "deletedAt": null });
+        assert_eq!(list_sort(&filter), doc! { "createdAt": -1, "_id": -1 });
+    }
 }
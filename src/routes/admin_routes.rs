@@ -0,0 +1,26 @@
+use crate::{
+    handlers::{admin_handler::AdminHandler, api_key_handler::ApiKeyHandler},
+    middlewares::permission::require_permission,
+    models::permission::Permission,
+    state::AppState,
+};
+use axum::{
+    routing::{delete, post},
+    Router,
+};
+
+pub fn admin_routes(state: AppState) -> Router<AppState> {
+    let auth = axum::middleware::from_fn_with_state(state.clone(), crate::middlewares::auth::auth_middleware);
+
+    Router::new()
+        .nest("/admin", Router::new()
+            .route("/users/:id", delete(AdminHandler::purge_user))
+            .route("/users/:id/restore", post(AdminHandler::restore_user))
+            .route("/users/:id/revoke-sessions", post(AdminHandler::revoke_user_sessions))
+            .route("/users/:id/impersonate", post(AdminHandler::impersonate))
+            .route("/api-keys", post(ApiKeyHandler::create).get(ApiKeyHandler::list))
+            .route("/api-keys/:id", delete(ApiKeyHandler::revoke))
+            .route_layer(require_permission(Permission::UsersAdmin))
+            .route_layer(auth)
+        )
+}
@@ -1,13 +1,41 @@
-use crate::{handlers::auth_handler::AuthHandler, state::AppState};
+use crate::{
+    handlers::{auth_handler::AuthHandler, mfa_handler::MfaHandler, session_handler::SessionHandler},
+    state::AppState,
+};
 use axum::{
-    routing::post,
+    routing::{delete, get, post},
     Router,
 };
 
-pub fn auth_routes(_state: AppState) -> Router<AppState> {
+pub fn auth_routes(state: AppState) -> Router<AppState> {
+    let auth = axum::middleware::from_fn_with_state(state.clone(), crate::middlewares::auth::auth_middleware);
+
     Router::new()
         .nest("/auth", Router::new()
             .route("/register", post(AuthHandler::register))
             .route("/login", post(AuthHandler::login))
+            .route("/login/mfa", post(AuthHandler::login_mfa))
+            .route("/refresh", post(AuthHandler::refresh))
+            .route("/oidc/:provider/authorize", get(AuthHandler::oidc_authorize))
+            .route("/oidc/:provider/callback", post(AuthHandler::oidc_callback))
+            .route("/verify-email", get(AuthHandler::verify_email))
+            .route("/verify-email/resend", post(AuthHandler::resend_verification))
+            .route("/forgot-password", post(AuthHandler::forgot_password))
+            .route("/reset-password", post(AuthHandler::reset_password))
+            .route("/magic-link", post(AuthHandler::request_magic_link))
+            .route("/magic-link/callback", get(AuthHandler::magic_link_callback))
+            .route("/logout", post(AuthHandler::logout).route_layer(auth.clone()))
+            .nest("/sessions", Router::new()
+                .route("/", get(SessionHandler::list))
+                .route("/revoke-others", post(SessionHandler::revoke_others))
+                .route("/:id", delete(SessionHandler::revoke))
+                .route_layer(auth.clone())
+            )
+            .nest("/mfa", Router::new()
+                .route("/enroll", post(MfaHandler::enroll))
+                .route("/confirm", post(MfaHandler::confirm))
+                .route("/disable", post(MfaHandler::disable))
+                .route_layer(auth)
+            )
         )
 }
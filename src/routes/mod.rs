@@ -3,12 +3,14 @@ use crate::state::AppState;
 
 pub mod user_routes;
 pub mod auth_routes;
+pub mod admin_routes;
 pub mod ws_routes;
 
 pub fn init_routes(state: AppState) -> Router<AppState> {
     let v1_routes = Router::new()
         .merge(auth_routes::auth_routes(state.clone()))
-        .merge(user_routes::user_routes(state.clone()));
+        .merge(user_routes::user_routes(state.clone()))
+        .merge(admin_routes::admin_routes(state.clone()));
 
     let mut app = Router::new()
         .merge(ws_routes::ws_routes(state.clone()))
@@ -20,7 +22,8 @@ pub fn init_routes(state: AppState) -> Router<AppState> {
                 "docs": "/docs" 
             })) 
         }))
-        .route("/health", axum::routing::get(crate::handlers::health::health_check));
+        .route("/health", axum::routing::get(crate::handlers::health::health_check))
+        .route("/.well-known/jwks.json", axum::routing::get(crate::handlers::auth_handler::AuthHandler::jwks));
 
     if state.config.app_mode == "development" {
         app = app
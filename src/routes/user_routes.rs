@@ -1,6 +1,11 @@
-use crate::{handlers::user_handler::UserHandler, state::AppState};
+use crate::{
+    handlers::user_handler::UserHandler,
+    middlewares::permission::require_permission,
+    models::permission::Permission,
+    state::AppState,
+};
 use axum::{
-    routing::{get, post},
+    routing::{delete, get, patch, post, put},
     Router,
 };
 
@@ -9,7 +14,21 @@ pub fn user_routes(state: AppState) -> Router<AppState> {
 
     Router::new()
         .nest("/users", Router::new()
-            .route("/", post(UserHandler::create_user).get(UserHandler::list_users).route_layer(auth.clone()))
-            .route("/:id", get(UserHandler::get_user).put(UserHandler::update_user).route_layer(auth))
+            // Listing every account and creating accounts outside of registration are admin work.
+            .route("/", get(UserHandler::list_users)
+                .merge(post(UserHandler::create_user))
+                .route_layer(require_permission(Permission::UsersAdmin))
+                .route_layer(auth.clone()))
+            .route("/me", get(UserHandler::get_me)
+                .route_layer(require_permission(Permission::UsersRead))
+                .merge(patch(UserHandler::update_me).route_layer(require_permission(Permission::UsersWrite)))
+                .merge(delete(UserHandler::delete_me))
+                .route_layer(auth.clone()))
+            .route("/me/password", post(UserHandler::change_password).route_layer(auth.clone()))
+            .route("/:id", get(UserHandler::get_user)
+                .route_layer(require_permission(Permission::UsersRead))
+                .merge(put(UserHandler::update_user).route_layer(require_permission(Permission::UsersWrite)))
+                .merge(delete(UserHandler::delete_user).route_layer(require_permission(Permission::UsersAdmin)))
+                .route_layer(auth))
         )
 }
@@ -0,0 +1,108 @@
+use crate::{
+    dtos::api_key::{ApiKeyResponse, CreateApiKey, CreatedApiKey},
+    error::AppError,
+    models::api_key::ApiKey,
+    repositories::api_key_repository::IApiKeyRepository,
+    utils::crypto::{generate_token, sha256_hex},
+};
+use std::sync::Arc;
+use chrono::{Duration, Utc};
+
+use async_trait::async_trait;
+use mockall::automock;
+
+const KEY_PREFIX: &str = "sk_";
+/// `sk_` plus the first 8 characters of the random part.
+const DISPLAY_PREFIX_LEN: usize = 11;
+/// `last_used_at` is only written when the stored value is older than this, to avoid a write per request.
+const LAST_USED_RESOLUTION_SECS: i64 = 60;
+
+#[automock]
+#[async_trait]
+pub trait IApiKeyService: Send + Sync {
+    async fn create_key(&self, owner_id: &str, input: CreateApiKey) -> Result<CreatedApiKey, AppError>;
+    async fn list_keys(&self) -> Result<Vec<ApiKeyResponse>, AppError>;
+    async fn revoke_key(&self, id: &str) -> Result<(), AppError>;
+    /// Resolves a presented key. Unknown, revoked and expired keys fail with `AuthError`.
+    async fn authenticate(&self, key: &str) -> Result<ApiKey, AppError>;
+}
+
+#[derive(Clone)]
+pub struct ApiKeyService {
+    repo: Arc<dyn IApiKeyRepository>,
+}
+
+impl ApiKeyService {
+    pub fn new(repo: Arc<dyn IApiKeyRepository>) -> Self {
+        Self { repo }
+    }
+}
+
+#[async_trait]
+impl IApiKeyService for ApiKeyService {
+    async fn create_key(&self, owner_id: &str, input: CreateApiKey) -> Result<CreatedApiKey, AppError> {
+        let now = Utc::now();
+        if input.expires_at.is_some_and(|exp| exp <= now) {
+            return Err(AppError::ValidationError("expiresAt must be in the future".into()));
+        }
+
+        let key = format!("{}{}", KEY_PREFIX, generate_token());
+        let api_key = ApiKey {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: input.name,
+            prefix: key[..DISPLAY_PREFIX_LEN].to_string(),
+            key_hash: sha256_hex(&key),
+            owner_id: owner_id.to_string(),
+            scopes: input.scopes,
+            expires_at: input.expires_at,
+            last_used_at: None,
+            revoked_at: None,
+            created_at: now,
+        };
+
+        self.repo.create(&api_key).await?;
+
+        Ok(CreatedApiKey {
+            key,
+            api_key: api_key.into(),
+        })
+    }
+
+    async fn list_keys(&self) -> Result<Vec<ApiKeyResponse>, AppError> {
+        let keys = self.repo.find_all().await?;
+        Ok(keys.into_iter().map(Into::into).collect())
+    }
+
+    async fn revoke_key(&self, id: &str) -> Result<(), AppError> {
+        if !self.repo.revoke(id, Utc::now()).await? {
+            return Err(AppError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn authenticate(&self, key: &str) -> Result<ApiKey, AppError> {
+        if !key.starts_with(KEY_PREFIX) {
+            return Err(AppError::AuthError);
+        }
+
+        let api_key = self
+            .repo
+            .find_by_hash(&sha256_hex(key))
+            .await?
+            .ok_or(AppError::AuthError)?;
+
+        let now = Utc::now();
+        if !api_key.is_active(now) {
+            return Err(AppError::AuthError);
+        }
+
+        let stale = api_key
+            .last_used_at
+            .is_none_or(|last| now - last > Duration::seconds(LAST_USED_RESOLUTION_SECS));
+        if stale {
+            self.repo.touch_last_used(&api_key.id, now).await?;
+        }
+
+        Ok(api_key)
+    }
+}
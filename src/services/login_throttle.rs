@@ -0,0 +1,184 @@
+use crate::{
+    config::AppConfig,
+    db::redis::IRedisProvider,
+    error::AppError,
+    utils::crypto::sha256_hex,
+};
+use std::sync::Arc;
+
+/// Failed-login counters in Redis, one per email and one per client IP.
+///
+/// Once a counter reaches its threshold the email or IP is locked. The lock lasts
+/// `base * 2^(failures - threshold)` seconds, capped at the configured maximum, so
+/// every further failure doubles the wait. Counters expire `window` seconds after
+/// the most recent failure.
+pub struct LoginThrottle {
+    redis: Arc<dyn IRedisProvider>,
+    max_attempts: i64,
+    ip_max_attempts: i64,
+    window_secs: u64,
+    lockout_base_secs: u64,
+    lockout_max_secs: u64,
+}
+
+impl LoginThrottle {
+    pub fn new(redis: Arc<dyn IRedisProvider>, config: &AppConfig) -> Self {
+        Self {
+            redis,
+            max_attempts: config.login_max_attempts,
+            ip_max_attempts: config.login_ip_max_attempts,
+            window_secs: config.login_failure_window_secs,
+            lockout_base_secs: config.login_lockout_base_secs,
+            lockout_max_secs: config.login_lockout_max_secs,
+        }
+    }
+
+    /// Fails with `TooManyRequests` while the email or the IP is locked.
+    pub async fn check(&self, email: &str, ip: &str) -> Result<(), AppError> {
+        let mut retry_after = 0;
+        for scope in [email_scope(email), ip_scope(ip)] {
+            retry_after = retry_after.max(self.redis.ttl(&lock_key(&scope)).await?);
+        }
+
+        if retry_after > 0 {
+            return Err(AppError::TooManyRequests(retry_after as u64));
+        }
+        Ok(())
+    }
+
+    pub async fn record_failure(&self, email: &str, ip: &str) -> Result<(), AppError> {
+        self.bump(&email_scope(email), self.max_attempts).await?;
+        self.bump(&ip_scope(ip), self.ip_max_attempts).await
+    }
+
+    /// Clears both counters after a successful login. Locks already in place are left to expire.
+    pub async fn reset(&self, email: &str, ip: &str) -> Result<(), AppError> {
+        self.redis.del(&failures_key(&email_scope(email))).await?;
+        self.redis.del(&failures_key(&ip_scope(ip))).await?;
+        Ok(())
+    }
+
+    async fn bump(&self, scope: &str, threshold: i64) -> Result<(), AppError> {
+        let failures = self.redis.incr_ex(&failures_key(scope), self.window_secs).await?;
+        if failures < threshold {
+            return Ok(());
+        }
+
+        let lockout = self.lockout_secs(failures - threshold);
+        tracing::warn!("Login locked for {} after {} failures ({}s)", scope, failures, lockout);
+        self.redis.set_ex(&lock_key(scope), "1", lockout).await?;
+        Ok(())
+    }
+
+    fn lockout_secs(&self, excess: i64) -> u64 {
+        let factor = 1u64 << excess.clamp(0, 32);
+        self.lockout_base_secs.saturating_mul(factor).min(self.lockout_max_secs)
+    }
+}
+
+fn email_scope(email: &str) -> String {
+    format!("email:{}", sha256_hex(&email.trim().to_lowercase()))
+}
+
+fn ip_scope(ip: &str) -> String {
+    format!("ip:{}", ip)
+}
+
+fn failures_key(scope: &str) -> String {
+    format!("login_failures:{}", scope)
+}
+
+fn lock_key(scope: &str) -> String {
+    format!("login_lock:{}", scope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::db_mock::MockRedisProvider;
+    use crate::mock::get_mock_state;
+    use mockall::predicate::*;
+
+    fn throttle(redis: MockRedisProvider) -> LoginThrottle {
+        LoginThrottle::new(Arc::new(redis), &get_mock_state().config)
+    }
+
+    #[test]
+    fn test_lockout_doubles_up_to_max() {
+        let throttle = throttle(MockRedisProvider::new());
+        assert_eq!(throttle.lockout_secs(0), 30);
+        assert_eq!(throttle.lockout_secs(1), 60);
+        assert_eq!(throttle.lockout_secs(3), 240);
+        assert_eq!(throttle.lockout_secs(10), 3600);
+        assert_eq!(throttle.lockout_secs(1000), 3600);
+    }
+
+    #[tokio::test]
+    async fn test_check_passes_without_locks() {
+        let mut redis = MockRedisProvider::new();
+        redis.expect_ttl().times(2).returning(|_| Ok(-2));
+
+        assert!(throttle(redis).check("a@b.com", "1.2.3.4").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_check_reports_longest_lock() {
+        let mut redis = MockRedisProvider::new();
+        redis.expect_ttl()
+            .with(eq(lock_key(&email_scope("a@b.com"))))
+            .returning(|_| Ok(20));
+        redis.expect_ttl()
+            .with(eq("login_lock:ip:1.2.3.4"))
+            .returning(|_| Ok(90));
+
+        let result = throttle(redis).check("A@b.com ", "1.2.3.4").await;
+        assert!(matches!(result, Err(AppError::TooManyRequests(90))));
+    }
+
+    #[tokio::test]
+    async fn test_record_failure_below_threshold() {
+        let mut redis = MockRedisProvider::new();
+        redis.expect_incr_ex()
+            .withf(|_, ttl| *ttl == 3600)
+            .times(2)
+            .returning(|_, _| Ok(1));
+        redis.expect_set_ex().never();
+
+        assert!(throttle(redis).record_failure("a@b.com", "1.2.3.4").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_record_failure_locks_email() {
+        let email_failures = failures_key(&email_scope("a@b.com"));
+        let email_lock = lock_key(&email_scope("a@b.com"));
+
+        let mut redis = MockRedisProvider::new();
+        redis.expect_incr_ex()
+            .with(eq(email_failures), always())
+            .returning(|_, _| Ok(6));
+        redis.expect_incr_ex()
+            .with(eq("login_failures:ip:1.2.3.4"), always())
+            .returning(|_, _| Ok(6));
+        redis.expect_set_ex()
+            .withf(move |key, _, ttl| key == email_lock && *ttl == 60)
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        assert!(throttle(redis).record_failure("a@b.com", "1.2.3.4").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_reset_clears_email_and_ip_counters() {
+        let mut redis = MockRedisProvider::new();
+        redis.expect_del()
+            .with(eq(failures_key(&email_scope("a@b.com"))))
+            .times(1)
+            .returning(|_| Ok(()));
+        redis.expect_del()
+            .with(eq("login_failures:ip:1.2.3.4"))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        assert!(throttle(redis).reset("a@b.com", "1.2.3.4").await.is_ok());
+    }
+}
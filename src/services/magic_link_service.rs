@@ -0,0 +1,129 @@
+use crate::{
+    config::AppConfig,
+    db::redis::IRedisProvider,
+    error::AppError,
+    services::token_service::TokenService,
+    utils::crypto::sha256_hex,
+};
+use std::sync::Arc;
+
+const MAGIC_LINK: &str = "magic_link";
+
+/// Single-use email sign-in links.
+///
+/// The link token is a one-time token in Redis (stored as its hash) whose subject is the user
+/// id plus the hash of a nonce kept in a cookie by the browser that asked for the link. The
+/// link is only redeemed when that nonce comes back, so a forwarded or intercepted email
+/// cannot be used from another browser, and mail scanners that open it do not use it up.
+pub struct MagicLinkService {
+    tokens: TokenService,
+    ttl_secs: u64,
+}
+
+impl MagicLinkService {
+    pub fn new(redis: Arc<dyn IRedisProvider>, config: &AppConfig) -> Self {
+        Self {
+            tokens: TokenService::new(redis, config),
+            ttl_secs: config.magic_link_ttl_secs,
+        }
+    }
+
+    /// Returns the token to put in the emailed link.
+    pub async fn issue(&self, user_id: &str, nonce: &str) -> Result<String, AppError> {
+        let subject = format!("{}:{}", user_id, sha256_hex(nonce));
+        self.tokens
+            .issue_one_time_token(MAGIC_LINK, &subject, self.ttl_secs)
+            .await
+    }
+
+    /// Uses up the link and returns its user id. Without the matching nonce the link is left
+    /// untouched so the right browser can still use it.
+    pub async fn redeem(&self, token: &str, nonce: Option<&str>) -> Result<String, AppError> {
+        let invalid = || AppError::ValidationError("Invalid or expired sign-in link".into());
+
+        let subject = self
+            .tokens
+            .peek_one_time_token(MAGIC_LINK, token)
+            .await?
+            .ok_or_else(invalid)?;
+        let (user_id, nonce_hash) = subject.split_once(':').ok_or_else(invalid)?;
+
+        if nonce.map(sha256_hex).as_deref() != Some(nonce_hash) {
+            return Err(AppError::ValidationError(
+                "Open the sign-in link in the browser you requested it from".into(),
+            ));
+        }
+
+        self.tokens
+            .consume_one_time_token(MAGIC_LINK, token)
+            .await?
+            .filter(|consumed| *consumed == subject)
+            .ok_or_else(invalid)?;
+
+        Ok(user_id.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::db_mock::MockRedisProvider;
+    use crate::mock::get_mock_state;
+
+    fn service(redis: MockRedisProvider) -> MagicLinkService {
+        MagicLinkService::new(Arc::new(redis), &get_mock_state().config)
+    }
+
+    fn subject(nonce: &str) -> String {
+        format!("user_1:{}", sha256_hex(nonce))
+    }
+
+    #[tokio::test]
+    async fn test_issue_stores_hashed_nonce() {
+        let mut redis = MockRedisProvider::new();
+        redis.expect_set_ex()
+            .withf(|key, value, ttl| {
+                key.starts_with("one_time_token:magic_link:") && value == subject("nonce_1") && *ttl == 600
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        assert!(!service(redis).issue("user_1", "nonce_1").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_redeem_with_matching_nonce() {
+        let mut redis = MockRedisProvider::new();
+        redis.expect_get().returning(|_| Ok(Some(subject("nonce_1"))));
+        redis.expect_get_del().times(1).returning(|_| Ok(Some(subject("nonce_1"))));
+
+        let user_id = service(redis).redeem("token", Some("nonce_1")).await.unwrap();
+        assert_eq!(user_id, "user_1");
+    }
+
+    #[tokio::test]
+    async fn test_redeem_from_other_browser_keeps_link() {
+        let mut redis = MockRedisProvider::new();
+        redis.expect_get().returning(|_| Ok(Some(subject("nonce_1"))));
+        redis.expect_get_del().never();
+
+        let magic_links = service(redis);
+        assert!(matches!(
+            magic_links.redeem("token", Some("other")).await,
+            Err(AppError::ValidationError(_))
+        ));
+        assert!(matches!(
+            magic_links.redeem("token", None).await,
+            Err(AppError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_redeem_used_link() {
+        let mut redis = MockRedisProvider::new();
+        redis.expect_get().returning(|_| Ok(None));
+
+        let result = service(redis).redeem("token", Some("nonce_1")).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+    }
+}
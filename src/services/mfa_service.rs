@@ -0,0 +1,386 @@
+use crate::{
+    config::AppConfig,
+    error::AppError,
+    models::user::UserMfa,
+    services::user_service::IUserService,
+    utils::crypto::{decrypt, encrypt, sha256_hex},
+};
+use aes_gcm::aead::{rand_core::RngCore, OsRng};
+use chrono::Utc;
+use serde::Serialize;
+use std::sync::Arc;
+use totp_rs::{Algorithm, TOTP};
+
+const TOTP_STEP_SECS: i64 = 30;
+const TOTP_DIGITS: usize = 6;
+const SECRET_LEN: usize = 20;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaEnrollment {
+    /// Base32 secret for manual entry.
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+/// TOTP (RFC 6238) second factor with one-time recovery codes.
+///
+/// The shared secret is stored AES-256-GCM encrypted on the user document and recovery
+/// codes only as SHA-256 hashes. Each accepted TOTP step is remembered so a code cannot
+/// be used twice.
+pub struct MfaService {
+    users: Arc<dyn IUserService>,
+    key: [u8; 32],
+    issuer: String,
+}
+
+impl MfaService {
+    pub fn new(users: Arc<dyn IUserService>, config: &AppConfig) -> Result<Self, AppError> {
+        Ok(Self {
+            users,
+            key: config.mfa_key()?,
+            issuer: config.mfa_issuer.clone(),
+        })
+    }
+
+    /// Startup check for the key fallback. While no TOTP secret is stored the key derived from
+    /// `jwt_secret` is only warned about; once one is, rotating the JWT secret would make it
+    /// unreadable, so `MFA_ENCRYPTION_KEY` becomes required.
+    pub async fn check_key(users: &dyn IUserService, config: &AppConfig) -> Result<(), AppError> {
+        config.mfa_key()?;
+        if config.mfa_encryption_key.is_some() {
+            return Ok(());
+        }
+
+        if users.any_mfa().await? {
+            return Err(AppError::AnyError(anyhow::anyhow!(
+                "MFA_ENCRYPTION_KEY must be set once users have two-factor authentication. \
+                 Set it to the key derived from the current JWT_SECRET (see .env.example) to keep existing secrets readable"
+            )));
+        }
+        tracing::warn!("MFA_ENCRYPTION_KEY is not set, TOTP secrets will be encrypted with a key derived from JWT_SECRET");
+        Ok(())
+    }
+
+    /// Starts (or restarts) enrollment with a fresh secret. 2FA stays off until [`Self::confirm`].
+    pub async fn enroll(&self, user_id: &str) -> Result<MfaEnrollment, AppError> {
+        if self.users.get_mfa(user_id).await?.is_some_and(|mfa| mfa.enabled) {
+            return Err(AppError::ValidationError(
+                "Two-factor authentication is already enabled".into(),
+            ));
+        }
+
+        let user = self.users.get_user(user_id).await?;
+
+        let mut secret = [0u8; SECRET_LEN];
+        OsRng.fill_bytes(&mut secret);
+        let totp = self.totp(secret.to_vec(), user.email)?;
+
+        self.users
+            .set_mfa(
+                user_id,
+                Some(UserMfa {
+                    enabled: false,
+                    secret: encrypt(&self.key, &secret)?,
+                    recovery_codes: Vec::new(),
+                    last_used_step: None,
+                }),
+            )
+            .await?;
+
+        Ok(MfaEnrollment {
+            secret: totp.get_secret_base32(),
+            otpauth_uri: totp.get_url(),
+        })
+    }
+
+    /// Enables 2FA once the user proves their authenticator works. Returns the plaintext
+    /// recovery codes, which are never shown again.
+    pub async fn confirm(&self, user_id: &str, code: &str) -> Result<Vec<String>, AppError> {
+        let current = self.users.get_mfa(user_id).await?.ok_or_else(|| {
+            AppError::ValidationError("Two-factor enrollment has not been started".into())
+        })?;
+        let mut mfa = current.clone();
+        if mfa.enabled {
+            return Err(AppError::ValidationError(
+                "Two-factor authentication is already enabled".into(),
+            ));
+        }
+
+        let step = self.match_totp(&mfa, code)?.ok_or_else(invalid_code)?;
+        let codes = generate_recovery_codes();
+
+        mfa.enabled = true;
+        mfa.last_used_step = Some(step);
+        mfa.recovery_codes = codes.iter().map(|c| sha256_hex(&normalize(c))).collect();
+        self.users.replace_mfa(user_id, &current, Some(mfa)).await?;
+
+        Ok(codes)
+    }
+
+    pub async fn disable(&self, user_id: &str, code: &str) -> Result<(), AppError> {
+        self.verify(user_id, code).await?;
+        self.users.set_mfa(user_id, None).await
+    }
+
+    /// Accepts a current TOTP code or consumes one recovery code. The write only succeeds if
+    /// no concurrent request used a code in the meantime.
+    pub async fn verify(&self, user_id: &str, code: &str) -> Result<(), AppError> {
+        let current = self
+            .users
+            .get_mfa(user_id)
+            .await?
+            .filter(|mfa| mfa.enabled)
+            .ok_or_else(|| {
+                AppError::ValidationError("Two-factor authentication is not enabled".into())
+            })?;
+        let mut mfa = current.clone();
+
+        if let Some(step) = self.match_totp(&mfa, code)? {
+            mfa.last_used_step = Some(step);
+        } else {
+            let hash = sha256_hex(&normalize(code));
+            let position = mfa
+                .recovery_codes
+                .iter()
+                .position(|h| *h == hash)
+                .ok_or_else(invalid_code)?;
+            mfa.recovery_codes.remove(position);
+            tracing::info!(
+                "Recovery code used for user {}, {} left",
+                user_id,
+                mfa.recovery_codes.len()
+            );
+        }
+
+        self.users.replace_mfa(user_id, &current, Some(mfa)).await
+    }
+
+    /// Returns the time step the code belongs to, allowing one step of clock drift either way.
+    fn match_totp(&self, mfa: &UserMfa, code: &str) -> Result<Option<i64>, AppError> {
+        let totp = self.totp(decrypt(&self.key, &mfa.secret)?, String::new())?;
+        let current = Utc::now().timestamp() / TOTP_STEP_SECS;
+
+        Ok((current - 1..=current + 1)
+            .filter(|step| mfa.last_used_step.is_none_or(|last| *step > last))
+            .find(|step| totp.check(code, (step * TOTP_STEP_SECS) as u64)))
+    }
+
+    fn totp(&self, secret: Vec<u8>, account_name: String) -> Result<TOTP, AppError> {
+        TOTP::new(
+            Algorithm::SHA1,
+            TOTP_DIGITS,
+            0,
+            TOTP_STEP_SECS as u64,
+            secret,
+            Some(self.issuer.clone()),
+            account_name,
+        )
+        .map_err(|e| AppError::AnyError(anyhow::anyhow!("invalid TOTP parameters: {}", e)))
+    }
+}
+
+fn invalid_code() -> AppError {
+    AppError::ValidationError("Invalid verification code".into())
+}
+
+/// `xxxxx-xxxxx` hex codes. Dashes and case are ignored when redeeming.
+fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; 5];
+            OsRng.fill_bytes(&mut bytes);
+            let code = hex::encode(bytes);
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+fn normalize(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dtos::user::UserResponse;
+    use crate::mock::get_mock_state;
+    use crate::mock::services::user_service_mock::MockUserService;
+    use mockall::predicate::*;
+
+    const SECRET: [u8; SECRET_LEN] = [42; SECRET_LEN];
+
+    fn code_at(offset_steps: i64) -> String {
+        let totp = TOTP::new(Algorithm::SHA1, 6, 0, 30, SECRET.to_vec(), None, String::new()).unwrap();
+        let time = Utc::now().timestamp() + offset_steps * TOTP_STEP_SECS;
+        totp.generate(time as u64)
+    }
+
+    fn mfa(enabled: bool, recovery_codes: Vec<String>) -> UserMfa {
+        let key = get_mock_state().config.mfa_key().unwrap();
+        UserMfa {
+            enabled,
+            secret: encrypt(&key, &SECRET).unwrap(),
+            recovery_codes,
+            last_used_step: None,
+        }
+    }
+
+    fn service(users: MockUserService) -> MfaService {
+        MfaService::new(Arc::new(users), &get_mock_state().config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_check_key_requires_explicit_key_once_secrets_exist() {
+        let mut config = get_mock_state().config.clone();
+        let mut users = MockUserService::new();
+        users.expect_any_mfa().times(1).returning(|| Ok(false));
+        assert!(MfaService::check_key(&users, &config).await.is_ok());
+
+        let mut users = MockUserService::new();
+        users.expect_any_mfa().returning(|| Ok(true));
+        assert!(MfaService::check_key(&users, &config).await.is_err());
+
+        config.mfa_encryption_key = Some("AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=".into());
+        assert!(MfaService::check_key(&users, &config).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_enroll_stores_encrypted_secret() {
+        let mut users = MockUserService::new();
+        users.expect_get_mfa().returning(|_| Ok(None));
+        users.expect_get_user().with(eq("user_1")).returning(|_| {
+            Ok(UserResponse {
+                id: "user_1".into(),
+                username: "test".into(),
+                email: "test@test.com".into(),
+                role: "user".into(),
+                email_verified: true,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+        });
+        users.expect_set_mfa()
+            .withf(|id, mfa| {
+                let mfa = mfa.as_ref().unwrap();
+                id == "user_1" && !mfa.enabled && mfa.secret.len() > SECRET_LEN
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let enrollment = service(users).enroll("user_1").await.unwrap();
+        assert!(enrollment.otpauth_uri.starts_with("otpauth://totp/"));
+        assert!(enrollment.otpauth_uri.contains(&enrollment.secret));
+    }
+
+    #[tokio::test]
+    async fn test_enroll_rejects_when_enabled() {
+        let mut users = MockUserService::new();
+        users.expect_get_mfa().returning(|_| Ok(Some(mfa(true, vec![]))));
+
+        let result = service(users).enroll("user_1").await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_confirm_enables_and_returns_recovery_codes() {
+        let mut users = MockUserService::new();
+        users.expect_get_mfa().returning(|_| Ok(Some(mfa(false, vec![]))));
+        users.expect_replace_mfa()
+            .withf(|_, current, mfa| {
+                let mfa = mfa.as_ref().unwrap();
+                !current.enabled
+                    && mfa.enabled
+                    && mfa.recovery_codes.len() == RECOVERY_CODE_COUNT
+                    && mfa.last_used_step.is_some()
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let codes = service(users).confirm("user_1", &code_at(0)).await.unwrap();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert_eq!(codes[0].len(), 11);
+    }
+
+    #[tokio::test]
+    async fn test_confirm_wrong_code() {
+        let mut users = MockUserService::new();
+        users.expect_get_mfa().returning(|_| Ok(Some(mfa(false, vec![]))));
+        users.expect_replace_mfa().never();
+
+        let result = service(users).confirm("user_1", &code_at(5)).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_replayed_code() {
+        let mut users = MockUserService::new();
+        users.expect_get_mfa().returning(|_| {
+            let mut mfa = mfa(true, vec![]);
+            mfa.last_used_step = Some(Utc::now().timestamp() / TOTP_STEP_SECS + 1);
+            Ok(Some(mfa))
+        });
+        users.expect_replace_mfa().never();
+
+        let result = service(users).verify("user_1", &code_at(0)).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_loses_race_for_the_same_code() {
+        let mut users = MockUserService::new();
+        users.expect_get_mfa().returning(|_| Ok(Some(mfa(true, vec![]))));
+        users.expect_replace_mfa()
+            .withf(|_, current, mfa| current.last_used_step.is_none() && mfa.as_ref().unwrap().last_used_step.is_some())
+            .times(1)
+            .returning(|_, _, _| Err(AppError::AuthError));
+
+        let result = service(users).verify("user_1", &code_at(0)).await;
+        assert!(matches!(result, Err(AppError::AuthError)));
+    }
+
+    #[tokio::test]
+    async fn test_verify_consumes_recovery_code() {
+        let stored = vec![sha256_hex("aaaaabbbbb"), sha256_hex("cccccddddd")];
+        let mut users = MockUserService::new();
+        users.expect_get_mfa().returning(move |_| Ok(Some(mfa(true, stored.clone()))));
+        users.expect_replace_mfa()
+            .withf(|_, current, mfa| {
+                current.recovery_codes.len() == 2
+                    && mfa.as_ref().unwrap().recovery_codes == vec![sha256_hex("cccccddddd")]
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        assert!(service(users).verify("user_1", "AAAAA-BBBBB").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_disable_requires_valid_code() {
+        let mut users = MockUserService::new();
+        users.expect_get_mfa().returning(|_| Ok(Some(mfa(true, vec![]))));
+        users.expect_replace_mfa()
+            .withf(|id, _, _| id == "user_1")
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        users.expect_set_mfa()
+            .with(eq("user_1"), eq(None))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        assert!(service(users).disable("user_1", &code_at(0)).await.is_ok());
+    }
+
+    #[test]
+    fn test_recovery_codes_unique() {
+        let codes = generate_recovery_codes();
+        let mut deduped = codes.clone();
+        deduped.sort();
+        deduped.dedup();
+        assert_eq!(deduped.len(), codes.len());
+    }
+}
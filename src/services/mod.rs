@@ -1 +1,8 @@
 pub mod user_service;
+pub mod token_service;
+pub mod mfa_service;
+pub mod login_throttle;
+pub mod api_key_service;
+pub mod oidc_service;
+pub mod session_service;
+pub mod magic_link_service;
@@ -0,0 +1,349 @@
+use crate::{
+    config::{AppConfig, OidcProviderConfig},
+    db::redis::IRedisProvider,
+    dtos::user::ExternalProfile,
+    error::AppError,
+    services::token_service::TokenService,
+    utils::crypto::generate_token,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    decode, decode_header,
+    jwk::{Jwk, JwkSet},
+    Algorithm, DecodingKey, Validation,
+};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError},
+    time::{Duration, Instant},
+};
+
+const OIDC_STATE: &str = "oidc_state";
+const HTTP_TIMEOUT_SECS: u64 = 10;
+
+/// The subset of the discovery document we need.
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+/// What was last fetched from a provider, keyed by issuer and shared by every `OidcService`.
+#[derive(Default)]
+struct ProviderCache {
+    metadata: Option<(Instant, Arc<ProviderMetadata>)>,
+    jwks: Option<(Instant, Arc<JwkSet>)>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// Stored under the `state` parameter until the provider redirects back.
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingAuthorization {
+    provider: String,
+    nonce: String,
+    code_verifier: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    #[serde(default)]
+    nonce: Option<String>,
+    #[serde(default)]
+    email: Option<String>,
+    #[serde(default, deserialize_with = "lenient_bool")]
+    email_verified: bool,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+/// OpenID Connect authorization code flow with PKCE against one configured provider.
+///
+/// `state` is a single-use token in Redis that carries the nonce and the PKCE verifier, so
+/// a callback is only accepted once and only for the request that started it. The ID token
+/// is verified against the provider's JWKS; endpoints come from its discovery document.
+/// Both are cached for `oidc_cache_ttl_secs`, and the JWKS is refetched early when a token
+/// is signed with a key it does not contain.
+pub struct OidcService {
+    tokens: TokenService,
+    name: String,
+    provider: OidcProviderConfig,
+    state_ttl_secs: u64,
+    cache_ttl: Duration,
+}
+
+impl OidcService {
+    /// Unknown provider names are reported as `NotFound`.
+    pub fn new(redis: Arc<dyn IRedisProvider>, config: &AppConfig, provider: &str) -> Result<Self, AppError> {
+        let provider_config = config
+            .oidc_providers
+            .get(provider)
+            .cloned()
+            .ok_or(AppError::NotFound)?;
+
+        Ok(Self {
+            tokens: TokenService::new(redis, config),
+            name: provider.to_string(),
+            provider: provider_config,
+            state_ttl_secs: config.oidc_state_ttl_secs,
+            cache_ttl: Duration::from_secs(config.oidc_cache_ttl_secs),
+        })
+    }
+
+    /// Starts a sign-in and returns the provider URL to send the browser to.
+    pub async fn authorization_url(&self) -> Result<String, AppError> {
+        let metadata = self.discover().await?;
+
+        let pending = PendingAuthorization {
+            provider: self.name.clone(),
+            nonce: generate_token(),
+            code_verifier: generate_token(),
+        };
+        let raw = serde_json::to_string(&pending).map_err(|e| AppError::AnyError(e.into()))?;
+        let state = self
+            .tokens
+            .issue_one_time_token(OIDC_STATE, &raw, self.state_ttl_secs)
+            .await?;
+
+        let mut url = reqwest::Url::parse(&metadata.authorization_endpoint).map_err(|e| {
+            AppError::AnyError(anyhow::anyhow!("invalid authorization endpoint: {}", e))
+        })?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.provider.client_id)
+            .append_pair("redirect_uri", &self.provider.redirect_uri)
+            .append_pair("scope", &self.provider.scopes.join(" "))
+            .append_pair("state", &state)
+            .append_pair("nonce", &pending.nonce)
+            .append_pair("code_challenge", &pkce_challenge(&pending.code_verifier))
+            .append_pair("code_challenge_method", "S256");
+
+        Ok(url.into())
+    }
+
+    /// Redeems the authorization code and returns the verified identity from the ID token.
+    pub async fn complete(&self, code: &str, state: &str) -> Result<ExternalProfile, AppError> {
+        let invalid_state = || AppError::ValidationError("Invalid or expired sign-in state".into());
+
+        let pending: PendingAuthorization = self
+            .tokens
+            .consume_one_time_token(OIDC_STATE, state)
+            .await?
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .ok_or_else(invalid_state)?;
+        if pending.provider != self.name {
+            return Err(invalid_state());
+        }
+
+        let metadata = self.discover().await?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.provider.redirect_uri.as_str()),
+            ("client_id", self.provider.client_id.as_str()),
+            ("code_verifier", pending.code_verifier.as_str()),
+        ];
+        if let Some(secret) = &self.provider.client_secret {
+            form.push(("client_secret", secret));
+        }
+
+        let response = http()
+            .post(&metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(upstream)?;
+        if !response.status().is_success() {
+            tracing::warn!("OIDC provider {} rejected the code exchange: {}", self.name, response.status());
+            return Err(AppError::AuthError);
+        }
+        let token: TokenResponse = response.json().await.map_err(upstream)?;
+
+        let claims = self.verify_id_token(&metadata, &token.id_token).await?;
+        if claims.nonce.as_deref() != Some(pending.nonce.as_str()) {
+            tracing::warn!("OIDC provider {} returned an ID token with a wrong nonce", self.name);
+            return Err(AppError::AuthError);
+        }
+
+        Ok(ExternalProfile {
+            provider: self.name.clone(),
+            subject: claims.sub,
+            email: claims.email,
+            email_verified: claims.email_verified,
+            name: claims.name,
+        })
+    }
+
+    /// Checks signature, `exp`, issuer and audience. Only asymmetric algorithms are accepted.
+    async fn verify_id_token(&self, metadata: &ProviderMetadata, id_token: &str) -> Result<IdTokenClaims, AppError> {
+        let header = decode_header(id_token).map_err(|_| AppError::AuthError)?;
+        if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+            return Err(AppError::AuthError);
+        }
+
+        let key = self.signing_key(metadata, header.kid.as_deref()).await?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&self.provider.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| {
+                tracing::debug!("ID token from {} rejected: {:?}", self.name, e);
+                AppError::AuthError
+            })
+    }
+
+    /// The provider's key for `kid`. The cached JWKS is refetched when it is stale or does not
+    /// know `kid`, which is how key rotation on the provider side shows up. ID tokens only come
+    /// from the token endpoint, so callers cannot force refetches with made-up `kid`s.
+    async fn signing_key(&self, metadata: &ProviderMetadata, kid: Option<&str>) -> Result<DecodingKey, AppError> {
+        let issuer = self.issuer();
+        let cached = provider_cache()
+            .get(issuer)
+            .and_then(|cache| cache.jwks.clone())
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < self.cache_ttl);
+        if let Some(jwk) = cached.as_ref().and_then(|(_, jwks)| find_jwk(jwks, kid)) {
+            return DecodingKey::from_jwk(jwk).map_err(|_| AppError::AuthError);
+        }
+
+        let jwks: Arc<JwkSet> = Arc::new(get_json(&metadata.jwks_uri).await?);
+        provider_cache().entry(issuer.to_string()).or_default().jwks = Some((Instant::now(), jwks.clone()));
+
+        let jwk = find_jwk(&jwks, kid).ok_or(AppError::AuthError)?;
+        DecodingKey::from_jwk(jwk).map_err(|_| AppError::AuthError)
+    }
+
+    async fn discover(&self) -> Result<Arc<ProviderMetadata>, AppError> {
+        let issuer = self.issuer();
+        let cached = provider_cache().get(issuer).and_then(|cache| cache.metadata.clone());
+        if let Some((fetched_at, metadata)) = cached {
+            if fetched_at.elapsed() < self.cache_ttl {
+                return Ok(metadata);
+            }
+        }
+
+        let metadata: ProviderMetadata =
+            get_json(&format!("{}/.well-known/openid-configuration", issuer)).await?;
+        if metadata.issuer.trim_end_matches('/') != issuer {
+            return Err(AppError::AnyError(anyhow::anyhow!(
+                "OIDC provider {} reports issuer {}",
+                self.name,
+                metadata.issuer
+            )));
+        }
+
+        let metadata = Arc::new(metadata);
+        provider_cache().entry(issuer.to_string()).or_default().metadata = Some((Instant::now(), metadata.clone()));
+        Ok(metadata)
+    }
+
+    fn issuer(&self) -> &str {
+        self.provider.issuer.trim_end_matches('/')
+    }
+}
+
+fn find_jwk<'a>(jwks: &'a JwkSet, kid: Option<&str>) -> Option<&'a Jwk> {
+    match kid {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }
+}
+
+fn provider_cache() -> MutexGuard<'static, HashMap<String, ProviderCache>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, ProviderCache>>> = OnceLock::new();
+    CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// RFC 7636 `S256` code challenge.
+fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// Shared so connections and TLS sessions are pooled across sign-ins.
+fn http() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(HTTP_TIMEOUT_SECS))
+            .build()
+            .expect("failed to build HTTP client")
+    })
+}
+
+async fn get_json<T: DeserializeOwned>(url: &str) -> Result<T, AppError> {
+    http()
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(upstream)?
+        .json()
+        .await
+        .map_err(upstream)
+}
+
+fn upstream(e: reqwest::Error) -> AppError {
+    AppError::AnyError(anyhow::anyhow!("OIDC provider request failed: {}", e))
+}
+
+/// Some providers (Apple, Cognito) send `email_verified` as the string `"true"`.
+fn lenient_bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BoolOrString {
+        Bool(bool),
+        String(String),
+    }
+
+    Ok(match BoolOrString::deserialize(deserializer)? {
+        BoolOrString::Bool(value) => value,
+        BoolOrString::String(value) => value == "true",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::db_mock::MockRedisProvider;
+    use crate::mock::get_mock_state;
+
+    #[test]
+    fn test_pkce_challenge_rfc7636_vector() {
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn test_unknown_provider() {
+        let result = OidcService::new(Arc::new(MockRedisProvider::new()), &get_mock_state().config, "nope");
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+
+    #[test]
+    fn test_email_verified_as_string() {
+        let claims: IdTokenClaims =
+            serde_json::from_str(r#"{"sub":"1","email_verified":"true"}"#).unwrap();
+        assert!(claims.email_verified);
+
+        let claims: IdTokenClaims = serde_json::from_str(r#"{"sub":"1"}"#).unwrap();
+        assert!(!claims.email_verified);
+    }
+}
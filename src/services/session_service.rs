@@ -0,0 +1,233 @@
+use crate::{
+    config::AppConfig,
+    db::redis::IRedisProvider,
+    error::AppError,
+    models::session::Session,
+    services::token_service::{IssuedRefreshToken, TokenService},
+};
+use chrono::Utc;
+use std::sync::Arc;
+
+/// Signed-in devices, one per refresh-token family.
+///
+/// Each session is a JSON record under `session:{id}` plus a member of the per-user set
+/// `user_sessions:{user_id}`; both share the refresh-token lifetime. Revoking a session
+/// deletes its family, which also rejects access tokens carrying its `sid`.
+pub struct SessionService {
+    redis: Arc<dyn IRedisProvider>,
+    tokens: TokenService,
+    ttl_secs: u64,
+}
+
+impl SessionService {
+    pub fn new(redis: Arc<dyn IRedisProvider>, config: &AppConfig) -> Self {
+        Self {
+            tokens: TokenService::new(redis.clone(), config),
+            redis,
+            ttl_secs: config.jwt_refresh_ttl_secs,
+        }
+    }
+
+    /// Issues the first refresh token of a new family and records the device it went to.
+    pub async fn start(
+        &self,
+        user_id: &str,
+        ip: &str,
+        user_agent: Option<&str>,
+    ) -> Result<IssuedRefreshToken, AppError> {
+        let refresh = self.tokens.issue_refresh_token(user_id).await?;
+
+        let now = Utc::now();
+        self.save(&Session {
+            id: refresh.family_id.clone(),
+            user_id: user_id.to_string(),
+            ip: ip.to_string(),
+            user_agent: user_agent.map(Into::into),
+            created_at: now,
+            last_seen_at: now,
+        })
+        .await?;
+
+        Ok(refresh)
+    }
+
+    /// Called on refresh. Families started before sessions were recorded are left alone.
+    pub async fn touch(&self, session_id: &str, ip: &str) -> Result<(), AppError> {
+        if let Some(mut session) = self.find(session_id).await? {
+            session.ip = ip.to_string();
+            session.last_seen_at = Utc::now();
+            self.save(&session).await?;
+        }
+        Ok(())
+    }
+
+    /// Live sessions, most recently used first. Entries whose family is gone are pruned.
+    pub async fn list(&self, user_id: &str) -> Result<Vec<Session>, AppError> {
+        let mut sessions = Vec::new();
+        for id in self.redis.smembers(&user_sessions_key(user_id)).await? {
+            match self.find(&id).await? {
+                Some(session) if self.tokens.family_exists(&id).await? => sessions.push(session),
+                _ => self.forget(user_id, &id).await?,
+            }
+        }
+
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.last_seen_at));
+        Ok(sessions)
+    }
+
+    /// `NotFound` unless the session belongs to the user.
+    pub async fn revoke(&self, user_id: &str, session_id: &str) -> Result<(), AppError> {
+        match self.find(session_id).await? {
+            Some(session) if session.user_id == user_id => self.forget(user_id, session_id).await,
+            _ => Err(AppError::NotFound),
+        }
+    }
+
+    /// Signs out every device except `keep`. Returns how many sessions were ended.
+    pub async fn revoke_others(&self, user_id: &str, keep: &str) -> Result<usize, AppError> {
+        let mut revoked = 0;
+        for id in self.redis.smembers(&user_sessions_key(user_id)).await? {
+            if id != keep {
+                self.forget(user_id, &id).await?;
+                revoked += 1;
+            }
+        }
+        Ok(revoked)
+    }
+
+    /// Ends every session and invalidates all tokens issued so far, including ones that
+    /// predate session tracking.
+    pub async fn revoke_all(&self, user_id: &str) -> Result<(), AppError> {
+        for id in self.redis.smembers(&user_sessions_key(user_id)).await? {
+            self.forget(user_id, &id).await?;
+        }
+        self.tokens.revoke_all_for_user(user_id).await
+    }
+
+    async fn find(&self, session_id: &str) -> Result<Option<Session>, AppError> {
+        Ok(self
+            .redis
+            .get(&session_key(session_id))
+            .await?
+            .and_then(|raw| serde_json::from_str(&raw).ok()))
+    }
+
+    async fn save(&self, session: &Session) -> Result<(), AppError> {
+        let value = serde_json::to_string(session).map_err(|e| AppError::AnyError(e.into()))?;
+        self.redis
+            .set_ex(&session_key(&session.id), &value, self.ttl_secs)
+            .await?;
+        self.redis
+            .sadd_ex(&user_sessions_key(&session.user_id), &session.id, self.ttl_secs)
+            .await?;
+        Ok(())
+    }
+
+    async fn forget(&self, user_id: &str, session_id: &str) -> Result<(), AppError> {
+        self.tokens.revoke_family(session_id).await?;
+        self.redis.del(&session_key(session_id)).await?;
+        self.redis.srem(&user_sessions_key(user_id), session_id).await?;
+        Ok(())
+    }
+}
+
+fn session_key(session_id: &str) -> String {
+    format!("session:{}", session_id)
+}
+
+fn user_sessions_key(user_id: &str) -> String {
+    format!("user_sessions:{}", user_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::db_mock::MockRedisProvider;
+    use crate::mock::get_mock_state;
+    use mockall::predicate::*;
+
+    fn service(redis: MockRedisProvider) -> SessionService {
+        SessionService::new(Arc::new(redis), &get_mock_state().config)
+    }
+
+    fn stored(id: &str, user_id: &str, minutes_ago: i64) -> String {
+        let seen = Utc::now() - chrono::Duration::minutes(minutes_ago);
+        serde_json::to_string(&Session {
+            id: id.into(),
+            user_id: user_id.into(),
+            ip: "1.2.3.4".into(),
+            user_agent: Some("curl/8".into()),
+            created_at: seen,
+            last_seen_at: seen,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_start_records_session() {
+        let mut redis = MockRedisProvider::new();
+        redis.expect_set_ex()
+            .withf(|key, _, _| key.starts_with("refresh_"))
+            .times(2)
+            .returning(|_, _, _| Ok(()));
+        redis.expect_set_ex()
+            .withf(|key, value, ttl| {
+                key.starts_with("session:") && value.contains("\"userAgent\":\"curl/8\"") && *ttl == 2592000
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        redis.expect_sadd_ex()
+            .withf(|key, _, _| key == "user_sessions:user_1")
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let refresh = service(redis).start("user_1", "1.2.3.4", Some("curl/8")).await.unwrap();
+        assert_eq!(refresh.user_id, "user_1");
+    }
+
+    #[tokio::test]
+    async fn test_list_sorts_and_prunes() {
+        let mut redis = MockRedisProvider::new();
+        redis.expect_smembers()
+            .with(eq("user_sessions:user_1"))
+            .returning(|_| Ok(vec!["old".into(), "new".into(), "gone".into()]));
+        redis.expect_get().with(eq("session:old")).returning(|_| Ok(Some(stored("old", "user_1", 60))));
+        redis.expect_get().with(eq("session:new")).returning(|_| Ok(Some(stored("new", "user_1", 1))));
+        redis.expect_get().with(eq("session:gone")).returning(|_| Ok(None));
+        redis.expect_get()
+            .withf(|key| key.starts_with("refresh_family:"))
+            .returning(|_| Ok(Some("user_1".into())));
+        redis.expect_del().times(2).returning(|_| Ok(()));
+        redis.expect_srem()
+            .with(eq("user_sessions:user_1"), eq("gone"))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let sessions = service(redis).list("user_1").await.unwrap();
+        let ids: Vec<_> = sessions.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["new", "old"]);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_other_users_session() {
+        let mut redis = MockRedisProvider::new();
+        redis.expect_get().returning(|_| Ok(Some(stored("s1", "someone_else", 1))));
+        redis.expect_del().never();
+
+        let result = service(redis).revoke("user_1", "s1").await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_revoke_others_keeps_current() {
+        let mut redis = MockRedisProvider::new();
+        redis.expect_smembers().returning(|_| Ok(vec!["current".into(), "a".into(), "b".into()]));
+        redis.expect_del()
+            .withf(|key| key.ends_with("current"))
+            .never();
+        redis.expect_del().times(4).returning(|_| Ok(()));
+        redis.expect_srem().times(2).returning(|_, _| Ok(()));
+
+        assert_eq!(service(redis).revoke_others("user_1", "current").await.unwrap(), 2);
+    }
+}
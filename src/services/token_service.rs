@@ -0,0 +1,546 @@
+use crate::{
+    config::AppConfig,
+    db::redis::IRedisProvider,
+    error::AppError,
+    handlers::auth_handler::Claims,
+    utils::crypto::{generate_token, sha256_hex},
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RefreshTokenRecord {
+    user_id: String,
+    family_id: String,
+    /// When the family was started, i.e. the original login time, in milliseconds.
+    issued_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct IssuedRefreshToken {
+    pub token: String,
+    pub user_id: String,
+    pub family_id: String,
+}
+
+/// Opaque refresh tokens grouped into families stored in Redis.
+///
+/// Every login starts a new family. Each refresh token is single-use: redeeming it
+/// issues the next token of the same family, and presenting an already-rotated
+/// token again revokes the whole family.
+///
+/// Access tokens are revoked through a `jti` denylist, and all tokens of a user can be
+/// invalidated at once through a per-user "valid after" timestamp in milliseconds.
+pub struct TokenService {
+    redis: Arc<dyn IRedisProvider>,
+    access_ttl_secs: u64,
+    refresh_ttl_secs: u64,
+}
+
+impl TokenService {
+    pub fn new(redis: Arc<dyn IRedisProvider>, config: &AppConfig) -> Self {
+        Self {
+            redis,
+            access_ttl_secs: config.jwt_access_ttl_secs.max(0) as u64,
+            refresh_ttl_secs: config.jwt_refresh_ttl_secs,
+        }
+    }
+
+    pub async fn issue_refresh_token(&self, user_id: &str) -> Result<IssuedRefreshToken, AppError> {
+        let family_id = uuid::Uuid::new_v4().to_string();
+        self.store(user_id, &family_id, Utc::now().timestamp_millis()).await
+    }
+
+    pub async fn rotate_refresh_token(&self, token: &str) -> Result<IssuedRefreshToken, AppError> {
+        let hash = sha256_hex(token);
+
+        let raw = self
+            .redis
+            .get(&token_key(&hash))
+            .await?
+            .ok_or(AppError::AuthError)?;
+        let record: RefreshTokenRecord =
+            serde_json::from_str(&raw).map_err(|_| AppError::AuthError)?;
+
+        if self.redis.get(&family_key(&record.family_id)).await?.is_none() {
+            return Err(AppError::AuthError);
+        }
+
+        if self.issued_before_cutoff(&record.user_id, record.issued_at).await? {
+            self.revoke_family(&record.family_id).await?;
+            return Err(AppError::AuthError);
+        }
+
+        let first_use = self
+            .redis
+            .set_nx_ex(&used_key(&hash), "1", self.refresh_ttl_secs)
+            .await?;
+        if !first_use {
+            tracing::warn!(
+                "Refresh token reuse detected for user {}, revoking family {}",
+                record.user_id,
+                record.family_id
+            );
+            self.revoke_family(&record.family_id).await?;
+            return Err(AppError::AuthError);
+        }
+
+        self.store(&record.user_id, &record.family_id, record.issued_at).await
+    }
+
+    pub async fn revoke_family(&self, family_id: &str) -> Result<(), AppError> {
+        self.redis.del(&family_key(family_id)).await?;
+        Ok(())
+    }
+
+    /// Denylists a single access token until it would have expired anyway.
+    pub async fn revoke_access_token(&self, claims: &Claims) -> Result<(), AppError> {
+        let remaining = claims.exp as i64 - Utc::now().timestamp();
+        if remaining <= 0 {
+            return Ok(());
+        }
+
+        self.redis
+            .set_ex(&revoked_key(&claims.jti), "1", remaining as u64)
+            .await?;
+        Ok(())
+    }
+
+    /// Invalidates every access and refresh token issued to the user before now.
+    pub async fn revoke_all_for_user(&self, user_id: &str) -> Result<(), AppError> {
+        let ttl = self.access_ttl_secs.max(self.refresh_ttl_secs);
+        self.redis
+            .set_ex(&valid_after_key(user_id), &Utc::now().timestamp_millis().to_string(), ttl)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn ensure_not_revoked(&self, claims: &Claims) -> Result<(), AppError> {
+        if self.redis.get(&revoked_key(&claims.jti)).await?.is_some() {
+            return Err(AppError::AuthError);
+        }
+
+        if self.issued_before_cutoff(&claims.sub, claims.issued_at_ms()).await? {
+            return Err(AppError::AuthError);
+        }
+
+        if let Some(sid) = &claims.sid {
+            if !self.family_exists(sid).await? {
+                return Err(AppError::AuthError);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A family exists until it expires, is revoked, or a reused refresh token kills it.
+    pub async fn family_exists(&self, family_id: &str) -> Result<bool, AppError> {
+        Ok(self.redis.get(&family_key(family_id)).await?.is_some())
+    }
+
+    /// Issues an opaque single-use token for `purpose` that resolves to `subject`.
+    /// Only the SHA-256 of the token is kept in Redis.
+    pub async fn issue_one_time_token(
+        &self,
+        purpose: &str,
+        subject: &str,
+        ttl_secs: u64,
+    ) -> Result<String, AppError> {
+        let token = generate_token();
+        self.redis
+            .set_ex(&one_time_key(purpose, &sha256_hex(&token)), subject, ttl_secs)
+            .await?;
+        Ok(token)
+    }
+
+    /// Reads the subject of a one-time token without using it up.
+    pub async fn peek_one_time_token(
+        &self,
+        purpose: &str,
+        token: &str,
+    ) -> Result<Option<String>, AppError> {
+        Ok(self.redis.get(&one_time_key(purpose, &sha256_hex(token))).await?)
+    }
+
+    /// Redeems a token issued by [`Self::issue_one_time_token`]. Returns `None` when the token
+    /// is unknown, expired or was already used.
+    pub async fn consume_one_time_token(
+        &self,
+        purpose: &str,
+        token: &str,
+    ) -> Result<Option<String>, AppError> {
+        Ok(self.redis.get_del(&one_time_key(purpose, &sha256_hex(token))).await?)
+    }
+
+    /// Both `issued_at` and the stored cutoff are in milliseconds.
+    async fn issued_before_cutoff(&self, user_id: &str, issued_at: i64) -> Result<bool, AppError> {
+        let cutoff = self
+            .redis
+            .get(&valid_after_key(user_id))
+            .await?
+            .and_then(|v| v.parse::<i64>().ok());
+
+        Ok(matches!(cutoff, Some(cutoff) if issued_at < cutoff))
+    }
+
+    async fn store(
+        &self,
+        user_id: &str,
+        family_id: &str,
+        issued_at: i64,
+    ) -> Result<IssuedRefreshToken, AppError> {
+        let token = generate_token();
+        let record = RefreshTokenRecord {
+            user_id: user_id.to_string(),
+            family_id: family_id.to_string(),
+            issued_at,
+        };
+        let value = serde_json::to_string(&record).map_err(|e| AppError::AnyError(e.into()))?;
+
+        self.redis
+            .set_ex(&token_key(&sha256_hex(&token)), &value, self.refresh_ttl_secs)
+            .await?;
+        self.redis
+            .set_ex(&family_key(family_id), user_id, self.refresh_ttl_secs)
+            .await?;
+
+        Ok(IssuedRefreshToken {
+            token,
+            user_id: record.user_id,
+            family_id: record.family_id,
+        })
+    }
+}
+
+fn token_key(hash: &str) -> String {
+    format!("refresh_token:{}", hash)
+}
+
+fn used_key(hash: &str) -> String {
+    format!("refresh_token_used:{}", hash)
+}
+
+fn family_key(family_id: &str) -> String {
+    format!("refresh_family:{}", family_id)
+}
+
+fn revoked_key(jti: &str) -> String {
+    format!("revoked_jti:{}", jti)
+}
+
+fn valid_after_key(user_id: &str) -> String {
+    format!("tokens_valid_after:{}", user_id)
+}
+
+fn one_time_key(purpose: &str, hash: &str) -> String {
+    format!("one_time_token:{}:{}", purpose, hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::db_mock::MockRedisProvider;
+    use crate::mock::get_mock_state;
+    use mockall::predicate::*;
+
+    fn record(user_id: &str, family_id: &str) -> String {
+        serde_json::to_string(&RefreshTokenRecord {
+            user_id: user_id.into(),
+            family_id: family_id.into(),
+            issued_at: Utc::now().timestamp_millis() - 60_000,
+        })
+        .unwrap()
+    }
+
+    fn claims() -> Claims {
+        Claims::new(&get_mock_state().config, "user_1".into(), "user".into())
+    }
+
+    #[tokio::test]
+    async fn test_issue_refresh_token_starts_family() {
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_set_ex()
+            .withf(|key, _, ttl| key.starts_with("refresh_token:") && *ttl == 2592000)
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        mock_redis.expect_set_ex()
+            .withf(|key, value, _| key.starts_with("refresh_family:") && value == "user_1")
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        let issued = service.issue_refresh_token("user_1").await.unwrap();
+
+        assert_eq!(issued.user_id, "user_1");
+        assert_eq!(issued.token.len(), 64);
+    }
+
+    #[tokio::test]
+    async fn test_rotate_refresh_token_success() {
+        let mut mock_redis = MockRedisProvider::new();
+        let hash = sha256_hex("old_token");
+        mock_redis.expect_get()
+            .with(eq(format!("refresh_token:{}", hash)))
+            .returning(|_| Ok(Some(record("user_1", "fam_1"))));
+        mock_redis.expect_get()
+            .with(eq("refresh_family:fam_1"))
+            .returning(|_| Ok(Some("user_1".into())));
+        mock_redis.expect_get()
+            .with(eq("tokens_valid_after:user_1"))
+            .returning(|_| Ok(None));
+        mock_redis.expect_set_nx_ex()
+            .with(eq(format!("refresh_token_used:{}", hash)), always(), always())
+            .times(1)
+            .returning(|_, _, _| Ok(true));
+        mock_redis.expect_set_ex()
+            .times(2)
+            .returning(|_, _, _| Ok(()));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        let issued = service.rotate_refresh_token("old_token").await.unwrap();
+
+        assert_eq!(issued.user_id, "user_1");
+        assert_eq!(issued.family_id, "fam_1");
+        assert_ne!(issued.token, "old_token");
+    }
+
+    #[tokio::test]
+    async fn test_rotate_refresh_token_reuse_revokes_family() {
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_get()
+            .withf(|key| key.starts_with("refresh_token:"))
+            .returning(|_| Ok(Some(record("user_1", "fam_1"))));
+        mock_redis.expect_get()
+            .with(eq("refresh_family:fam_1"))
+            .returning(|_| Ok(Some("user_1".into())));
+        mock_redis.expect_get()
+            .with(eq("tokens_valid_after:user_1"))
+            .returning(|_| Ok(None));
+        mock_redis.expect_set_nx_ex()
+            .returning(|_, _, _| Ok(false));
+        mock_redis.expect_del()
+            .with(eq("refresh_family:fam_1"))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        let res = service.rotate_refresh_token("old_token").await;
+
+        assert!(matches!(res, Err(AppError::AuthError)));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_refresh_token_revoked_family() {
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_get()
+            .withf(|key| key.starts_with("refresh_token:"))
+            .returning(|_| Ok(Some(record("user_1", "fam_1"))));
+        mock_redis.expect_get()
+            .with(eq("refresh_family:fam_1"))
+            .returning(|_| Ok(None));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        let res = service.rotate_refresh_token("old_token").await;
+
+        assert!(matches!(res, Err(AppError::AuthError)));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_refresh_token_unknown() {
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_get().returning(|_| Ok(None));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        let res = service.rotate_refresh_token("unknown").await;
+
+        assert!(matches!(res, Err(AppError::AuthError)));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_refresh_token_after_revoke_all() {
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_get()
+            .withf(|key| key.starts_with("refresh_token:"))
+            .returning(|_| Ok(Some(record("user_1", "fam_1"))));
+        mock_redis.expect_get()
+            .with(eq("refresh_family:fam_1"))
+            .returning(|_| Ok(Some("user_1".into())));
+        mock_redis.expect_get()
+            .with(eq("tokens_valid_after:user_1"))
+            .returning(|_| Ok(Some(Utc::now().timestamp_millis().to_string())));
+        mock_redis.expect_del()
+            .with(eq("refresh_family:fam_1"))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        let res = service.rotate_refresh_token("old_token").await;
+
+        assert!(matches!(res, Err(AppError::AuthError)));
+    }
+
+    #[tokio::test]
+    async fn test_revoke_access_token_uses_remaining_lifetime() {
+        let claims = claims();
+        let jti = claims.jti.clone();
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_set_ex()
+            .withf(move |key, _, ttl| key == format!("revoked_jti:{}", jti) && *ttl > 0 && *ttl <= 900)
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        assert!(service.revoke_access_token(&claims).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_access_token_already_expired() {
+        let mut claims = claims();
+        claims.exp = claims.iat - 10;
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(MockRedisProvider::new()), &config);
+        assert!(service.revoke_access_token(&claims).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_all_for_user() {
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_set_ex()
+            .withf(|key, _, ttl| key == "tokens_valid_after:user_1" && *ttl == 2592000)
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        assert!(service.revoke_all_for_user("user_1").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_ensure_not_revoked() {
+        let claims = claims();
+        let jti = claims.jti.clone();
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_get()
+            .with(eq(format!("revoked_jti:{}", jti)))
+            .returning(|_| Ok(None));
+        mock_redis.expect_get()
+            .with(eq("tokens_valid_after:user_1"))
+            .returning(|_| Ok(None));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        assert!(service.ensure_not_revoked(&claims).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_ensure_not_revoked_ended_session() {
+        let claims = claims().with_session("fam");
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_get()
+            .with(eq("refresh_family:fam"))
+            .returning(|_| Ok(None));
+        mock_redis.expect_get().returning(|_| Ok(None));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        assert!(matches!(service.ensure_not_revoked(&claims).await, Err(AppError::AuthError)));
+    }
+
+    #[tokio::test]
+    async fn test_ensure_not_revoked_denylisted() {
+        let claims = claims();
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_get()
+            .withf(|key| key.starts_with("revoked_jti:"))
+            .returning(|_| Ok(Some("1".into())));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        assert!(matches!(service.ensure_not_revoked(&claims).await, Err(AppError::AuthError)));
+    }
+
+    #[tokio::test]
+    async fn test_ensure_not_revoked_issued_before_cutoff() {
+        let claims = claims();
+        let cutoff = (claims.issued_at_ms() + 1).to_string();
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_get()
+            .withf(|key| key.starts_with("revoked_jti:"))
+            .returning(|_| Ok(None));
+        mock_redis.expect_get()
+            .with(eq("tokens_valid_after:user_1"))
+            .returning(move |_| Ok(Some(cutoff.clone())));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        assert!(matches!(service.ensure_not_revoked(&claims).await, Err(AppError::AuthError)));
+    }
+
+    #[tokio::test]
+    async fn test_ensure_not_revoked_cutoff_in_same_second() {
+        let mut claims = claims();
+        claims.iat_ms = Some(claims.iat as i64 * 1000);
+        let cutoff = (claims.iat as i64 * 1000 + 500).to_string();
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_get()
+            .withf(|key| key.starts_with("revoked_jti:"))
+            .returning(|_| Ok(None));
+        mock_redis.expect_get()
+            .with(eq("tokens_valid_after:user_1"))
+            .returning(move |_| Ok(Some(cutoff.clone())));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        assert!(matches!(service.ensure_not_revoked(&claims).await, Err(AppError::AuthError)));
+    }
+
+    #[tokio::test]
+    async fn test_issue_one_time_token_stores_hash() {
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_set_ex()
+            .withf(|key, value, ttl| {
+                key.starts_with("one_time_token:password_reset:") && value == "user_1" && *ttl == 3600
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        let token = service.issue_one_time_token("password_reset", "user_1", 3600).await.unwrap();
+        assert_eq!(token.len(), 64);
+    }
+
+    #[tokio::test]
+    async fn test_consume_one_time_token() {
+        let mut mock_redis = MockRedisProvider::new();
+        let key = format!("one_time_token:password_reset:{}", sha256_hex("token"));
+        mock_redis.expect_get_del()
+            .with(eq(key))
+            .times(1)
+            .returning(|_| Ok(Some("user_1".into())));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        let subject = service.consume_one_time_token("password_reset", "token").await.unwrap();
+        assert_eq!(subject.as_deref(), Some("user_1"));
+    }
+
+    #[tokio::test]
+    async fn test_consume_one_time_token_unknown() {
+        let mut mock_redis = MockRedisProvider::new();
+        mock_redis.expect_get_del().returning(|_| Ok(None));
+
+        let config = get_mock_state().config.clone();
+        let service = TokenService::new(Arc::new(mock_redis), &config);
+        assert!(service.consume_one_time_token("password_reset", "token").await.unwrap().is_none());
+    }
+}
@@ -1,14 +1,18 @@
 use crate::{
-    dtos::user::{CreateUser, UpdateUser, UserResponse},
+    dtos::user::{ChangePassword, CreateUser, ExternalProfile, UpdateUser, UserResponse},
     error::AppError,
-    models::user::User,
+    models::user::{ExternalIdentity, Role, User, UserFilter, UserMfa, UserSortField},
     repositories::user_repository::IUserRepository,
-    utils::pagination::PaginationResult,
+    utils::{
+        blocking::BlockingPool,
+        crypto::generate_token,
+        pagination::{offset, Cursor, CursorPage, PaginationResult},
+        password::{IPasswordHasher, PasswordHasher},
+    },
 };
 use std::sync::Arc;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use mongodb::bson::doc;
-use bcrypt::{hash, verify, DEFAULT_COST};
 
 use async_trait::async_trait;
 use mockall::automock;
@@ -18,31 +22,81 @@ use mockall::automock;
 pub trait IUserService: Send + Sync {
     async fn create_user(&self, input: CreateUser) -> Result<UserResponse, AppError>;
     async fn get_user(&self, id: &str) -> Result<UserResponse, AppError>;
-    async fn list_users(&self, page: Option<u64>, limit: Option<u64>) -> Result<PaginationResult<UserResponse>, AppError>;
+    async fn list_users(&self, filter: UserFilter, page: Option<u64>, limit: Option<u64>) -> Result<PaginationResult<UserResponse>, AppError>;
+    async fn list_users_after(&self, filter: UserFilter, cursor: Option<String>, limit: Option<u64>) -> Result<CursorPage<UserResponse>, AppError>;
     async fn update_user(&self, id: &str, input: UpdateUser) -> Result<(), AppError>;
     async fn authenticate(&self, email: &str, password: &str) -> Result<User, AppError>;
+    async fn find_by_email(&self, email: &str) -> Result<Option<UserResponse>, AppError>;
+    async fn set_password(&self, id: &str, password: &str) -> Result<(), AppError>;
+    async fn change_password(&self, id: &str, input: ChangePassword) -> Result<UserResponse, AppError>;
+    async fn mark_email_verified(&self, id: &str) -> Result<(), AppError>;
+    async fn get_mfa(&self, id: &str) -> Result<Option<UserMfa>, AppError>;
+    async fn set_mfa(&self, id: &str, mfa: Option<UserMfa>) -> Result<(), AppError>;
+    /// Replaces the stored MFA state only if it still equals `current`, so two requests cannot
+    /// both spend the same TOTP step or recovery code. `AuthError` when another request won.
+    async fn replace_mfa(&self, id: &str, current: &UserMfa, mfa: Option<UserMfa>) -> Result<(), AppError>;
+    /// Whether any account has a TOTP secret stored, enrolled or still confirming.
+    async fn any_mfa(&self) -> Result<bool, AppError>;
+    async fn find_or_create_external(&self, profile: ExternalProfile) -> Result<User, AppError>;
+    async fn delete_user(&self, id: &str) -> Result<(), AppError>;
+    async fn restore_user(&self, id: &str) -> Result<UserResponse, AppError>;
+    async fn purge_user(&self, id: &str, deleted_before: DateTime<Utc>) -> Result<(), AppError>;
 }
 
 #[derive(Clone)]
 pub struct UserService {
     repo: Arc<dyn IUserRepository>,
+    require_email_verification: bool,
+    hasher: Arc<dyn IPasswordHasher>,
+    pool: BlockingPool,
+    cursor_secret: String,
 }
 
 impl UserService {
+    /// Hashes with Argon2id at its default cost; see [`Self::with_password_hasher`].
     pub fn new(repo: Arc<dyn IUserRepository>) -> Self {
-        Self { repo }
+        Self {
+            repo,
+            require_email_verification: false,
+            hasher: Arc::new(PasswordHasher::default()),
+            pool: BlockingPool::default(),
+            cursor_secret: generate_token(),
+        }
+    }
+
+    /// Key that signs pagination cursors. The default is random, so cursors only work on the
+    /// instance that issued them.
+    pub fn with_cursor_secret(mut self, secret: impl Into<String>) -> Self {
+        self.cursor_secret = secret.into();
+        self
+    }
+
+    pub fn with_password_hasher(mut self, hasher: Arc<dyn IPasswordHasher>) -> Self {
+        self.hasher = hasher;
+        self
+    }
+
+    /// Pool that hashing and verification run on. Share one pool per process so the limit holds.
+    pub fn with_blocking_pool(mut self, pool: BlockingPool) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    /// When enabled, `authenticate` rejects users who have not verified their email.
+    pub fn with_email_verification_required(mut self, required: bool) -> Self {
+        self.require_email_verification = required;
+        self
     }
 }
 
 #[async_trait]
 impl IUserService for UserService {
     async fn create_user(&self, input: CreateUser) -> Result<UserResponse, AppError> {
-        if self.repo.find_by_email(&input.email).await?.is_some() {
+        if self.repo.email_exists(&input.email).await? {
             return Err(AppError::ValidationError("Email already exists".into()));
         }
 
-        let password_hash = hash(input.password, DEFAULT_COST)
-            .map_err(|_| AppError::AuthError)?;
+        let password_hash = self.hash_password(&input.password).await?;
 
         let user_id = uuid::Uuid::new_v4().to_string();
 
@@ -51,9 +105,13 @@ impl IUserService for UserService {
             username: input.username,
             email: input.email,
             password_hash,
-            role: "user".to_string(),
+            role: Role::User.to_string(),
+            email_verified: false,
+            mfa: None,
+            identities: vec![],
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            deleted_at: None,
         };
 
         self.repo.create(&user).await?;
@@ -68,33 +126,79 @@ impl IUserService for UserService {
 
     async fn list_users(
         &self,
+        filter: UserFilter,
         page: Option<u64>,
         limit: Option<u64>,
     ) -> Result<PaginationResult<UserResponse>, AppError> {
-        let page = page.unwrap_or(1);
-        let limit = limit.unwrap_or(10);
-        let skip = (page - 1) * limit;
+        // Handlers validate through the `Pagination` extractor; clamp anyway so other callers
+        // cannot underflow the offset.
+        let page = page.unwrap_or(1).max(1);
+        let limit = limit.unwrap_or(10).max(1);
+        let skip = offset(page, limit)
+            .ok_or_else(|| AppError::ValidationError("page is too large".into()))?;
+        let limit_i64 = i64::try_from(limit)
+            .map_err(|_| AppError::ValidationError("limit is too large".into()))?;
 
-        let users = self.repo.find_all(skip, limit as i64).await?;
-        let total = self.repo.count().await?;
+        let users = self.repo.find_all(&filter, skip, limit_i64).await?;
+        let total = self.repo.count(&filter).await?;
 
         let user_responses: Vec<UserResponse> = users.into_iter().map(Into::into).collect();
 
         Ok(PaginationResult::new(user_responses, page, limit, total))
     }
     
+    /// Keyset pagination: no skip and no count, so every page costs the same. An empty or
+    /// missing cursor starts from the first page.
+    async fn list_users_after(
+        &self,
+        filter: UserFilter,
+        cursor: Option<String>,
+        limit: Option<u64>,
+    ) -> Result<CursorPage<UserResponse>, AppError> {
+        if filter.sort.field != UserSortField::CreatedAt {
+            return Err(AppError::ValidationError(
+                "Cursor pagination can only sort by createdAt".into(),
+            ));
+        }
+
+        let limit = limit.unwrap_or(10).max(1);
+        let after = cursor
+            .filter(|cursor| !cursor.is_empty())
+            .map(|cursor| Cursor::decode(&self.cursor_secret, &cursor))
+            .transpose()?;
+
+        // One extra row tells whether there is a next page.
+        let mut users = self.repo.find_page(&filter, after, limit as i64 + 1).await?;
+        let next_cursor = if users.len() as u64 > limit {
+            users.truncate(limit as usize);
+            users.last().map(|last| {
+                Cursor {
+                    created_at: last.created_at,
+                    id: last.id.clone().unwrap_or_default(),
+                }
+                .encode(&self.cursor_secret)
+            })
+        } else {
+            None
+        };
+
+        let data = users.into_iter().map(Into::into).collect();
+        Ok(CursorPage::new(data, limit, next_cursor))
+    }
+
     async fn update_user(&self, id: &str, input: UpdateUser) -> Result<(), AppError> {
-        let mut update_doc = doc! { "updated_at": Utc::now() };
+        let mut update_doc = doc! { "updatedAt": Utc::now() };
         
         if let Some(username) = input.username {
             update_doc.insert("username", username);
         }
         
         if let Some(email) = input.email {
-             if self.repo.find_by_email(&email).await?.is_some() {
+             if self.repo.email_exists(&email).await? {
                  return Err(AppError::ValidationError("Email already exists".into()));
              }
              update_doc.insert("email", email);
+             update_doc.insert("emailVerified", false);
         }
 
         self.repo.update(id, update_doc).await.map_err(Into::into)
@@ -104,10 +208,238 @@ impl IUserService for UserService {
         let user = self.repo.find_by_email(email).await?
             .ok_or(AppError::InvalidCredentials)?;
 
-        if !verify(password, &user.password_hash).map_err(|_| AppError::InvalidCredentials)? {
+        if !self.verify_password(password, &user.password_hash).await? {
             return Err(AppError::InvalidCredentials);
         }
 
+        if self.require_email_verification && !user.email_verified {
+            return Err(AppError::EmailNotVerified);
+        }
+
+        if self.hasher.needs_rehash(&user.password_hash) {
+            self.rehash(&user, password).await;
+        }
+
+        Ok(user)
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<UserResponse>, AppError> {
+        Ok(self.repo.find_by_email(email).await?.map(Into::into))
+    }
+
+    async fn set_password(&self, id: &str, password: &str) -> Result<(), AppError> {
+        self.repo.find_by_id(id).await?.ok_or(AppError::NotFound)?;
+
+        let password_hash = self.hash_password(password).await?;
+
+        self.repo
+            .update(id, doc! { "passwordHash": password_hash, "updatedAt": Utc::now() })
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Requires the current password. Session invalidation is left to the caller.
+    async fn change_password(&self, id: &str, input: ChangePassword) -> Result<UserResponse, AppError> {
+        let user = self.repo.find_by_id(id).await?.ok_or(AppError::NotFound)?;
+
+        if !self.verify_password(&input.current_password, &user.password_hash).await? {
+            return Err(AppError::InvalidCredentials);
+        }
+        if input.new_password == input.current_password {
+            return Err(AppError::ValidationError(
+                "New password must be different from the current one".into(),
+            ));
+        }
+
+        let password_hash = self.hash_password(&input.new_password).await?;
+
+        self.repo
+            .update(id, doc! { "passwordHash": password_hash, "updatedAt": Utc::now() })
+            .await?;
+
+        Ok(user.into())
+    }
+
+    async fn mark_email_verified(&self, id: &str) -> Result<(), AppError> {
+        self.repo
+            .update(id, doc! { "emailVerified": true, "updatedAt": Utc::now() })
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn get_mfa(&self, id: &str) -> Result<Option<UserMfa>, AppError> {
+        let user = self.repo.find_by_id(id).await?.ok_or(AppError::NotFound)?;
+        Ok(user.mfa)
+    }
+
+    async fn set_mfa(&self, id: &str, mfa: Option<UserMfa>) -> Result<(), AppError> {
+        let mfa = mongodb::bson::to_bson(&mfa).map_err(|e| AppError::AnyError(e.into()))?;
+        self.repo
+            .update(id, doc! { "mfa": mfa, "updatedAt": Utc::now() })
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn replace_mfa(&self, id: &str, current: &UserMfa, mfa: Option<UserMfa>) -> Result<(), AppError> {
+        let condition = doc! {
+            "mfa.enabled": current.enabled,
+            "mfa.secret": &current.secret,
+            "mfa.recoveryCodes": &current.recovery_codes,
+            "mfa.lastUsedStep": current.last_used_step,
+        };
+        let mfa = mongodb::bson::to_bson(&mfa).map_err(|e| AppError::AnyError(e.into()))?;
+        if self
+            .repo
+            .update_if(id, condition, doc! { "mfa": mfa, "updatedAt": Utc::now() })
+            .await?
+        {
+            Ok(())
+        } else {
+            Err(AppError::AuthError)
+        }
+    }
+
+    async fn any_mfa(&self) -> Result<bool, AppError> {
+        self.repo.mfa_exists().await.map_err(Into::into)
+    }
+
+    /// Resolves a provider sign-in to a local user: an already linked identity wins, then an
+    /// account with the same email is linked, otherwise a new user is created. Linking or
+    /// creating by email requires the provider to have verified the address.
+    async fn find_or_create_external(&self, profile: ExternalProfile) -> Result<User, AppError> {
+        if let Some(user) = self.repo.find_by_identity(&profile.provider, &profile.subject).await? {
+            return Ok(user);
+        }
+
+        let email = profile
+            .email
+            .filter(|_| profile.email_verified)
+            .ok_or_else(|| {
+                AppError::ValidationError("The provider did not supply a verified email address".into())
+            })?;
+        let identity = ExternalIdentity {
+            provider: profile.provider,
+            subject: profile.subject,
+        };
+
+        if let Some(mut user) = self.repo.find_by_email(&email).await? {
+            // Whoever registered an unverified address may not own it, and their password would
+            // keep working on the linked account.
+            if !user.email_verified {
+                return Err(AppError::ValidationError(
+                    "An account with this email exists but is not verified; sign in with its password \
+                     and verify the email before using this provider"
+                        .into(),
+                ));
+            }
+
+            let id = user.id.clone().unwrap_or_default();
+            user.identities.push(identity);
+            user.updated_at = Utc::now();
+
+            let identities = mongodb::bson::to_bson(&user.identities)
+                .map_err(|e| AppError::AnyError(e.into()))?;
+            self.repo
+                .update(&id, doc! { "identities": identities, "updatedAt": user.updated_at })
+                .await?;
+            tracing::info!("Linked external identity to user {}", id);
+            return Ok(user);
+        }
+
+        // The address belongs to a soft-deleted account that is waiting to be restored or purged.
+        if self.repo.email_exists(&email).await? {
+            return Err(AppError::PermissionDenied);
+        }
+
+        // Random password nobody knows; the user can still set one through the reset flow.
+        let password_hash = self.hash_password(&generate_token()).await?;
+
+        let user = User {
+            id: Some(uuid::Uuid::new_v4().to_string()),
+            username: external_username(profile.name.as_deref(), &email),
+            email,
+            password_hash,
+            role: Role::User.to_string(),
+            email_verified: true,
+            mfa: None,
+            identities: vec![identity],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+        };
+
+        self.repo.create(&user).await?;
         Ok(user)
     }
+
+    /// Soft delete: the user can no longer sign in or be found, but can be restored until purged.
+    async fn delete_user(&self, id: &str) -> Result<(), AppError> {
+        if !self.repo.soft_delete(id, Utc::now()).await? {
+            return Err(AppError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn restore_user(&self, id: &str) -> Result<UserResponse, AppError> {
+        if !self.repo.restore(id, Utc::now()).await? {
+            return Err(AppError::NotFound);
+        }
+        self.get_user(id).await
+    }
+
+    /// Only users soft-deleted before `deleted_before` can be purged.
+    async fn purge_user(&self, id: &str, deleted_before: DateTime<Utc>) -> Result<(), AppError> {
+        if self.repo.purge(id, deleted_before).await? {
+            return Ok(());
+        }
+
+        // Nothing was removed; only look why to pick the error.
+        match self.repo.find_deleted_by_id(id).await? {
+            Some(_) => Err(AppError::ValidationError(
+                "The user is still within the retention window and cannot be purged yet".into(),
+            )),
+            None => Err(AppError::NotFound),
+        }
+    }
+}
+
+impl UserService {
+    async fn hash_password(&self, password: &str) -> Result<String, AppError> {
+        let hasher = self.hasher.clone();
+        let password = password.to_string();
+        Ok(self.pool.run(move || hasher.hash(&password)).await??)
+    }
+
+    async fn verify_password(&self, password: &str, hash: &str) -> Result<bool, AppError> {
+        let hasher = self.hasher.clone();
+        let (password, hash) = (password.to_string(), hash.to_string());
+        self.pool.run(move || hasher.verify(&password, &hash)).await
+    }
+
+    /// Upgrades a hash written with an older algorithm or cost. Failures only cost another
+    /// attempt at the next login, so they are logged rather than failing this one.
+    async fn rehash(&self, user: &User, password: &str) {
+        let id = user.id.as_deref().unwrap_or_default();
+        let result = match self.hash_password(password).await {
+            Ok(password_hash) => self
+                .repo
+                .update(id, doc! { "passwordHash": password_hash, "updatedAt": Utc::now() })
+                .await
+                .map_err(Into::into),
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(()) => tracing::info!("Upgraded password hash for user {}", id),
+            Err(e) => tracing::warn!("Failed to upgrade password hash for user {}: {}", id, e),
+        }
+    }
+}
+
+/// The provider's display name, or the local part of the email when that is too short.
+fn external_username(name: Option<&str>, email: &str) -> String {
+    match name.map(str::trim) {
+        Some(name) if name.chars().count() >= 3 => name.to_string(),
+        _ => email.split('@').next().unwrap_or(email).to_string(),
+    }
 }
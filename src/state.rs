@@ -1,13 +1,21 @@
 use crate::config::AppConfig;
 use crate::db::{redis::IRedisProvider, mongo::IMongoProvider};
-use crate::services::user_service::IUserService;
-use std::sync::Arc;
+use crate::providers::email::{EmailProvider, IEmailProvider};
+use crate::error::AppError;
+use crate::services::{api_key_service::IApiKeyService, user_service::IUserService};
+use crate::utils::{jwt::JwtKeys, password_policy::PasswordPolicy};
+use std::sync::{Arc, OnceLock};
 
 pub struct InnerState {
     pub db: Arc<dyn IMongoProvider>,
     pub config: AppConfig,
     pub redis: Arc<dyn IRedisProvider>,
     pub user_service: Arc<dyn IUserService>,
+    pub email: Arc<dyn IEmailProvider>,
+    /// `None` disables API key authentication; `X-API-Key` requests are then rejected.
+    pub api_key_service: Option<Arc<dyn IApiKeyService>>,
+    pub password_policy: PasswordPolicy,
+    jwt_keys: OnceLock<JwtKeys>,
 }
 
 pub type AppState = Arc<InnerState>;
@@ -19,6 +27,51 @@ impl InnerState {
         redis: Arc<dyn IRedisProvider>,
         user_service: Arc<dyn IUserService>,
     ) -> Self {
-        Self { db, config, redis, user_service }
+        Self {
+            password_policy: PasswordPolicy::new(&config),
+            db,
+            config,
+            redis,
+            user_service,
+            email: Arc::new(EmailProvider::new()),
+            api_key_service: None,
+            jwt_keys: OnceLock::new(),
+        }
+    }
+
+    pub fn with_email_provider(mut self, email: Arc<dyn IEmailProvider>) -> Self {
+        self.email = email;
+        self
+    }
+
+    pub fn with_password_policy(mut self, password_policy: PasswordPolicy) -> Self {
+        self.password_policy = password_policy;
+        self
+    }
+
+    pub fn with_api_key_service(mut self, api_key_service: Arc<dyn IApiKeyService>) -> Self {
+        self.api_key_service = Some(api_key_service);
+        self
+    }
+
+    /// Keys parsed at startup; without this they are parsed from `config` on first use.
+    pub fn with_jwt_keys(self, jwt_keys: JwtKeys) -> Self {
+        let _ = self.jwt_keys.set(jwt_keys);
+        self
+    }
+
+    pub fn jwt_keys(&self) -> Result<&JwtKeys, AppError> {
+        if let Some(keys) = self.jwt_keys.get() {
+            return Ok(keys);
+        }
+        let keys = JwtKeys::new(&self.config)?;
+        Ok(self.jwt_keys.get_or_init(|| keys))
+    }
+
+    pub fn api_keys(&self) -> Result<&Arc<dyn IApiKeyService>, AppError> {
+        self.api_key_service.as_ref().ok_or_else(|| {
+            tracing::error!("API key service is not configured");
+            AppError::InternalServerError
+        })
     }
 }
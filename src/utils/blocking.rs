@@ -0,0 +1,100 @@
+use crate::error::AppError;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+pub const DEFAULT_MAX_QUEUE: usize = 64;
+
+/// Runs CPU-bound work (password hashing) on Tokio's blocking threads so it cannot starve
+/// the async workers that serve every other request.
+///
+/// At most `max_concurrency` jobs run at once and at most `max_queue` more wait for a
+/// worker. Anything beyond that is rejected with `ServiceUnavailable` instead of piling up.
+#[derive(Clone)]
+pub struct BlockingPool {
+    workers: Arc<Semaphore>,
+    admission: Arc<Semaphore>,
+}
+
+impl BlockingPool {
+    pub fn new(max_concurrency: usize, max_queue: usize) -> Self {
+        let max_concurrency = max_concurrency.max(1);
+        Self {
+            workers: Arc::new(Semaphore::new(max_concurrency)),
+            admission: Arc::new(Semaphore::new(max_concurrency + max_queue)),
+        }
+    }
+
+    pub async fn run<F, T>(&self, job: F) -> Result<T, AppError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let admitted = self.admission.clone().try_acquire_owned().map_err(|_| {
+            tracing::warn!("Blocking pool saturated, rejecting job");
+            AppError::ServiceUnavailable
+        })?;
+        let worker = self
+            .workers
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+
+        // The permits move into the job so they are held until it finishes, even if the
+        // request that queued it is cancelled in the meantime.
+        tokio::task::spawn_blocking(move || {
+            let _permits = (admitted, worker);
+            job()
+        })
+        .await
+        .map_err(|e| AppError::AnyError(e.into()))
+    }
+}
+
+impl Default for BlockingPool {
+    fn default() -> Self {
+        Self::new(default_concurrency(), DEFAULT_MAX_QUEUE)
+    }
+}
+
+/// One hashing job per core.
+pub fn default_concurrency() -> usize {
+    std::thread::available_parallelism().map_or(4, usize::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_run_returns_result() {
+        let pool = BlockingPool::new(1, 0);
+        assert_eq!(pool.run(|| 2 + 2).await.unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_when_saturated() {
+        let pool = BlockingPool::new(1, 1);
+        let (release, blocked) = mpsc::channel::<()>();
+
+        let running = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.run(move || blocked.recv().unwrap()).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let queued = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.run(|| ()).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(matches!(pool.run(|| ()).await, Err(AppError::ServiceUnavailable)));
+
+        release.send(()).unwrap();
+        assert!(running.await.unwrap().is_ok());
+        assert!(queued.await.unwrap().is_ok());
+        assert!(pool.run(|| ()).await.is_ok());
+    }
+}
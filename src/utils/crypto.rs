@@ -0,0 +1,146 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Generates an opaque, URL-safe random token (two UUID v4s, 244 random bits from the OS RNG).
+pub fn generate_token() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// Tokens are only ever stored as their SHA-256 digest.
+pub fn sha256_hex(value: &str) -> String {
+    hex::encode(Sha256::digest(value.as_bytes()))
+}
+
+/// Creates a stateless `payload.exp.signature` token. `purpose` is part of the MAC so a token
+/// minted for one flow is never accepted by another.
+pub fn sign_token(secret: &str, purpose: &str, payload: &str, ttl_secs: i64) -> String {
+    let payload = URL_SAFE_NO_PAD.encode(payload);
+    let exp = Utc::now().timestamp() + ttl_secs;
+    let signature = URL_SAFE_NO_PAD.encode(mac(secret, purpose, &payload, exp).finalize().into_bytes());
+    format!("{}.{}.{}", payload, exp, signature)
+}
+
+/// Returns the payload of a token created by [`sign_token`] if the signature matches and it
+/// has not expired.
+pub fn verify_signed_token(secret: &str, purpose: &str, token: &str) -> Option<String> {
+    let mut parts = token.splitn(3, '.');
+    let (payload, exp, signature) = (parts.next()?, parts.next()?, parts.next()?);
+    let exp: i64 = exp.parse().ok()?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+
+    mac(secret, purpose, payload, exp).verify_slice(&signature).ok()?;
+    if exp < Utc::now().timestamp() {
+        return None;
+    }
+
+    String::from_utf8(URL_SAFE_NO_PAD.decode(payload).ok()?).ok()
+}
+
+/// Encrypts with AES-256-GCM under a random nonce. Output is base64 of `nonce || ciphertext`.
+pub fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> anyhow::Result<String> {
+    let cipher = Aes256Gcm::new(key.into());
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| anyhow::anyhow!("encryption failed"))?;
+
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&ciphertext);
+    Ok(STANDARD.encode(out))
+}
+
+pub fn decrypt(key: &[u8; 32], encoded: &str) -> anyhow::Result<Vec<u8>> {
+    let raw = STANDARD.decode(encoded)?;
+    if raw.len() < 12 {
+        anyhow::bail!("ciphertext too short");
+    }
+    let (nonce, ciphertext) = raw.split_at(12);
+
+    Aes256Gcm::new(key.into())
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow::anyhow!("decryption failed"))
+}
+
+fn mac(secret: &str, purpose: &str, payload: &str, exp: i64) -> HmacSha256 {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{}.{}.{}", purpose, payload, exp).as_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_token_unique() {
+        let a = generate_token();
+        let b = generate_token();
+        assert_eq!(a.len(), 64);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_signed_token_roundtrip() {
+        let token = sign_token("secret", "email_verification", "user_1:a@b.com", 60);
+        assert_eq!(
+            verify_signed_token("secret", "email_verification", &token).as_deref(),
+            Some("user_1:a@b.com")
+        );
+    }
+
+    #[test]
+    fn test_signed_token_rejects_tampering() {
+        let token = sign_token("secret", "email_verification", "user_1", 60);
+        assert!(verify_signed_token("other", "email_verification", &token).is_none());
+        assert!(verify_signed_token("secret", "password_reset", &token).is_none());
+
+        let forged = token.replacen(&URL_SAFE_NO_PAD.encode("user_1"), &URL_SAFE_NO_PAD.encode("user_2"), 1);
+        assert!(verify_signed_token("secret", "email_verification", &forged).is_none());
+        assert!(verify_signed_token("secret", "email_verification", "garbage").is_none());
+    }
+
+    #[test]
+    fn test_signed_token_expired() {
+        let token = sign_token("secret", "email_verification", "user_1", -1);
+        assert!(verify_signed_token("secret", "email_verification", &token).is_none());
+    }
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let key = [7u8; 32];
+        let a = encrypt(&key, b"totp secret").unwrap();
+        let b = encrypt(&key, b"totp secret").unwrap();
+        assert_ne!(a, b);
+        assert_eq!(decrypt(&key, &a).unwrap(), b"totp secret");
+    }
+
+    #[test]
+    fn test_decrypt_wrong_key_fails() {
+        let encrypted = encrypt(&[7u8; 32], b"totp secret").unwrap();
+        assert!(decrypt(&[8u8; 32], &encrypted).is_err());
+        assert!(decrypt(&[7u8; 32], "c2hvcnQ=").is_err());
+    }
+}
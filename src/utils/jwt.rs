@@ -0,0 +1,520 @@
+use crate::{
+    config::{AppConfig, JwtKeyConfig},
+    error::AppError,
+    handlers::auth_handler::Claims,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    decode, decode_header, encode,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use simple_asn1::{oid, ASN1Block, OID};
+use std::collections::HashMap;
+
+/// Signing and verification keys parsed from the config once at startup, so tokens are
+/// encoded and decoded without touching the key files.
+pub struct JwtKeys {
+    header: Header,
+    encoding: EncodingKey,
+    /// Keyed by `kid`; a single unnamed entry in HS256 mode.
+    decoding: HashMap<Option<String>, (DecodingKey, Validation)>,
+    jwks: JwkSet,
+}
+
+impl JwtKeys {
+    /// Fails on a missing or unparsable key so a bad config stops startup instead of the first login.
+    pub fn new(config: &AppConfig) -> Result<Self, AppError> {
+        let Some(signing) = signing_key(config)? else {
+            let mut decoding = HashMap::new();
+            decoding.insert(
+                None,
+                (DecodingKey::from_secret(config.jwt_secret.as_ref()), validation(config, Algorithm::HS256)),
+            );
+            return Ok(Self {
+                header: Header::new(Algorithm::HS256),
+                encoding: EncodingKey::from_secret(config.jwt_secret.as_ref()),
+                decoding,
+                jwks: JwkSet { keys: vec![] },
+            });
+        };
+
+        let mut header = Header::new(signing.algorithm);
+        header.kid = Some(signing.kid.clone());
+        let decoding = config
+            .jwt_keys
+            .iter()
+            .map(|key| Ok((Some(key.kid.clone()), (decoding_key(key)?, validation(config, key.algorithm)))))
+            .collect::<Result<_, AppError>>()?;
+
+        Ok(Self {
+            header,
+            encoding: encoding_key(signing)?,
+            decoding,
+            jwks: jwks(config)?,
+        })
+    }
+
+    /// Public half of every configured key, for `/.well-known/jwks.json`.
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+}
+
+pub fn encode_token(keys: &JwtKeys, claims: &Claims) -> Result<String, AppError> {
+    encode(&keys.header, claims, &keys.encoding).map_err(|_| AppError::AuthError)
+}
+
+/// Verifies signature, `exp`, `nbf`, issuer and audience before returning the claims.
+///
+/// With asymmetric keys configured the token must carry a `kid` naming one of `jwt_keys`,
+/// so retired keys keep verifying until they are removed from the config.
+pub fn decode_token(keys: &JwtKeys, token: &str) -> Result<Claims, AppError> {
+    let kid = if keys.decoding.contains_key(&None) {
+        None
+    } else {
+        let header = decode_header(token).map_err(|_| AppError::AuthError)?;
+        Some(header.kid.ok_or(AppError::AuthError)?)
+    };
+    let (key, validation) = keys.decoding.get(&kid).ok_or(AppError::AuthError)?;
+
+    decode::<Claims>(token, key, validation)
+        .map(|data| data.claims)
+        .map_err(|e| {
+            tracing::debug!("JWT rejected: {:?}", e);
+            AppError::AuthError
+        })
+}
+
+/// Public half of every configured key, without requiring a signing key.
+pub fn jwks(config: &AppConfig) -> Result<JwkSet, AppError> {
+    let keys = config
+        .jwt_keys
+        .iter()
+        .map(jwk)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(JwkSet { keys })
+}
+
+fn validation(config: &AppConfig, algorithm: Algorithm) -> Validation {
+    let mut validation = Validation::new(algorithm);
+    validation.validate_nbf = true;
+    validation.set_issuer(&[&config.jwt_issuer]);
+    validation.set_audience(&[&config.jwt_audience]);
+    validation.set_required_spec_claims(&["exp", "nbf", "iss", "aud", "sub"]);
+    validation
+}
+
+fn signing_key(config: &AppConfig) -> Result<Option<&JwtKeyConfig>, AppError> {
+    if config.jwt_keys.is_empty() {
+        return Ok(None);
+    }
+
+    let kid = config
+        .jwt_signing_kid
+        .as_deref()
+        .ok_or_else(|| key_error("jwt_signing_kid is required when jwt_keys is set"))?;
+    config
+        .jwt_keys
+        .iter()
+        .find(|k| k.kid == kid)
+        .map(Some)
+        .ok_or_else(|| key_error(&format!("jwt_signing_kid {} not found in jwt_keys", kid)))
+}
+
+fn encoding_key(key: &JwtKeyConfig) -> Result<EncodingKey, AppError> {
+    let pem = read_pem(&key.private_key_pem, &key.private_key_file)?
+        .ok_or_else(|| key_error(&format!("missing private key for kid {}", key.kid)))?;
+
+    match key.algorithm {
+        Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512
+        | Algorithm::PS256 | Algorithm::PS384 | Algorithm::PS512 => {
+            EncodingKey::from_rsa_pem(pem.as_bytes())
+        }
+        Algorithm::EdDSA => EncodingKey::from_ed_pem(pem.as_bytes()),
+        _ => return Err(unsupported(key)),
+    }
+    .map_err(|e| key_error(&format!("invalid private key for kid {}: {}", key.kid, e)))
+}
+
+fn decoding_key(key: &JwtKeyConfig) -> Result<DecodingKey, AppError> {
+    let pem = public_pem(key)?;
+
+    match key.algorithm {
+        Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512
+        | Algorithm::PS256 | Algorithm::PS384 | Algorithm::PS512 => {
+            DecodingKey::from_rsa_pem(pem.as_bytes())
+        }
+        Algorithm::EdDSA => DecodingKey::from_ed_pem(pem.as_bytes()),
+        _ => return Err(unsupported(key)),
+    }
+    .map_err(|e| key_error(&format!("invalid public key for kid {}: {}", key.kid, e)))
+}
+
+fn jwk(key: &JwtKeyConfig) -> Result<Jwk, AppError> {
+    let invalid = || key_error(&format!("invalid public key for kid {}", key.kid));
+    let pem = pem::parse(public_pem(key)?).map_err(|_| invalid())?;
+
+    let (key_algorithm, algorithm) = match key.algorithm {
+        Algorithm::EdDSA => {
+            let x = spki_public_key(pem.contents(), oid!(1, 3, 101, 112)).ok_or_else(invalid)?;
+            (
+                KeyAlgorithm::EdDSA,
+                AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(x),
+                }),
+            )
+        }
+        algorithm => {
+            let rsa_der = match pem.tag() {
+                "RSA PUBLIC KEY" => pem.contents().to_vec(),
+                _ => spki_public_key(pem.contents(), oid!(1, 2, 840, 113_549, 1, 1, 1))
+                    .ok_or_else(invalid)?,
+            };
+            let (n, e) = rsa_components(&rsa_der).ok_or_else(invalid)?;
+            (
+                rsa_key_algorithm(algorithm).ok_or_else(|| unsupported(key))?,
+                AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n: URL_SAFE_NO_PAD.encode(n),
+                    e: URL_SAFE_NO_PAD.encode(e),
+                }),
+            )
+        }
+    };
+
+    Ok(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(key.kid.clone()),
+            ..Default::default()
+        },
+        algorithm,
+    })
+}
+
+fn rsa_key_algorithm(algorithm: Algorithm) -> Option<KeyAlgorithm> {
+    match algorithm {
+        Algorithm::RS256 => Some(KeyAlgorithm::RS256),
+        Algorithm::RS384 => Some(KeyAlgorithm::RS384),
+        Algorithm::RS512 => Some(KeyAlgorithm::RS512),
+        Algorithm::PS256 => Some(KeyAlgorithm::PS256),
+        Algorithm::PS384 => Some(KeyAlgorithm::PS384),
+        Algorithm::PS512 => Some(KeyAlgorithm::PS512),
+        _ => None,
+    }
+}
+
+/// SubjectPublicKeyInfo -> subjectPublicKey bits, if the key is of the `expected` algorithm.
+fn spki_public_key(der: &[u8], expected: OID) -> Option<Vec<u8>> {
+    let blocks = simple_asn1::from_der(der).ok()?;
+    let ASN1Block::Sequence(_, parts) = blocks.first()? else { return None };
+    let ASN1Block::Sequence(_, algorithm) = parts.first()? else { return None };
+    let ASN1Block::ObjectIdentifier(_, algorithm_oid) = algorithm.first()? else { return None };
+    let ASN1Block::BitString(_, _, bits) = parts.get(1)? else { return None };
+
+    (*algorithm_oid == expected).then(|| bits.clone())
+}
+
+/// PKCS#1 RSAPublicKey -> big-endian (modulus, exponent).
+fn rsa_components(der: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let blocks = simple_asn1::from_der(der).ok()?;
+    let ASN1Block::Sequence(_, parts) = blocks.first()? else { return None };
+    let ASN1Block::Integer(_, n) = parts.first()? else { return None };
+    let ASN1Block::Integer(_, e) = parts.get(1)? else { return None };
+
+    Some((n.to_bytes_be().1, e.to_bytes_be().1))
+}
+
+fn public_pem(key: &JwtKeyConfig) -> Result<String, AppError> {
+    read_pem(&key.public_key_pem, &key.public_key_file)?
+        .ok_or_else(|| key_error(&format!("missing public key for kid {}", key.kid)))
+}
+
+fn read_pem(inline: &Option<String>, file: &Option<String>) -> Result<Option<String>, AppError> {
+    match (inline, file) {
+        (Some(pem), _) => Ok(Some(pem.clone())),
+        (None, Some(path)) => std::fs::read_to_string(path)
+            .map(Some)
+            .map_err(|e| key_error(&format!("cannot read {}: {}", path, e))),
+        (None, None) => Ok(None),
+    }
+}
+
+fn unsupported(key: &JwtKeyConfig) -> AppError {
+    key_error(&format!("unsupported algorithm {:?} for kid {}", key.algorithm, key.kid))
+}
+
+fn key_error(message: &str) -> AppError {
+    AppError::AnyError(anyhow::anyhow!("JWT key configuration: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::get_mock_state;
+
+    const ED25519_PRIVATE: &str = include_str!("../../tests/fixtures/keys/ed25519_private.pem");
+    const ED25519_PUBLIC: &str = include_str!("../../tests/fixtures/keys/ed25519_public.pem");
+    const ED25519_OLD_PRIVATE: &str = include_str!("../../tests/fixtures/keys/ed25519_old_private.pem");
+    const ED25519_OLD_PUBLIC: &str = include_str!("../../tests/fixtures/keys/ed25519_old_public.pem");
+    const RSA_PRIVATE: &str = include_str!("../../tests/fixtures/keys/rsa_private.pem");
+    const RSA_PUBLIC: &str = include_str!("../../tests/fixtures/keys/rsa_public.pem");
+
+    fn key(kid: &str, algorithm: Algorithm, private: Option<&str>, public: &str) -> JwtKeyConfig {
+        JwtKeyConfig {
+            kid: kid.into(),
+            algorithm,
+            private_key_pem: private.map(Into::into),
+            private_key_file: None,
+            public_key_pem: Some(public.into()),
+            public_key_file: None,
+        }
+    }
+
+    fn keys(config: &AppConfig) -> JwtKeys {
+        JwtKeys::new(config).unwrap()
+    }
+
+    fn asymmetric_config(keys: Vec<JwtKeyConfig>, signing_kid: &str) -> AppConfig {
+        let mut config = get_mock_state().config.clone();
+        config.jwt_keys = keys;
+        config.jwt_signing_kid = Some(signing_kid.into());
+        config
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let config = get_mock_state().config.clone();
+        let claims = Claims::new(&config, "user_1".into(), "user".into());
+
+        let token = encode_token(&keys(&config), &claims).unwrap();
+        let decoded = decode_token(&keys(&config), &token).unwrap();
+
+        assert_eq!(decoded.sub, "user_1");
+        assert_eq!(decoded.role, "user");
+    }
+
+    #[test]
+    fn test_decode_rejects_wrong_secret() {
+        let config = get_mock_state().config.clone();
+        let claims = Claims::new(&config, "user_1".into(), "user".into());
+        let token = encode_token(&keys(&config), &claims).unwrap();
+
+        let mut other = config.clone();
+        other.jwt_secret = "another-secret".into();
+        assert!(decode_token(&keys(&other), &token).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_expired() {
+        let config = get_mock_state().config.clone();
+        let mut claims = Claims::new(&config, "user_1".into(), "user".into());
+        claims.nbf -= 7200;
+        claims.exp = claims.iat - 3600;
+        let token = encode_token(&keys(&config), &claims).unwrap();
+
+        assert!(decode_token(&keys(&config), &token).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_not_yet_valid() {
+        let config = get_mock_state().config.clone();
+        let mut claims = Claims::new(&config, "user_1".into(), "user".into());
+        claims.nbf += 3600;
+        let token = encode_token(&keys(&config), &claims).unwrap();
+
+        assert!(decode_token(&keys(&config), &token).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_wrong_issuer_and_audience() {
+        let config = get_mock_state().config.clone();
+
+        let mut claims = Claims::new(&config, "user_1".into(), "user".into());
+        claims.iss = "someone-else".into();
+        let token = encode_token(&keys(&config), &claims).unwrap();
+        assert!(decode_token(&keys(&config), &token).is_err());
+
+        let mut claims = Claims::new(&config, "user_1".into(), "user".into());
+        claims.aud = "another-api".into();
+        let token = encode_token(&keys(&config), &claims).unwrap();
+        assert!(decode_token(&keys(&config), &token).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_garbage() {
+        let config = get_mock_state().config.clone();
+        assert!(decode_token(&keys(&config), "not-a-jwt").is_err());
+    }
+
+    #[test]
+    fn test_eddsa_roundtrip_with_kid() {
+        let config = asymmetric_config(
+            vec![key("ed-1", Algorithm::EdDSA, Some(ED25519_PRIVATE), ED25519_PUBLIC)],
+            "ed-1",
+        );
+        let claims = Claims::new(&config, "user_1".into(), "user".into());
+        let token = encode_token(&keys(&config), &claims).unwrap();
+
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.alg, Algorithm::EdDSA);
+        assert_eq!(header.kid.as_deref(), Some("ed-1"));
+        assert_eq!(decode_token(&keys(&config), &token).unwrap().sub, "user_1");
+    }
+
+    #[test]
+    fn test_rs256_roundtrip() {
+        let config = asymmetric_config(
+            vec![key("rsa-1", Algorithm::RS256, Some(RSA_PRIVATE), RSA_PUBLIC)],
+            "rsa-1",
+        );
+        let claims = Claims::new(&config, "user_1".into(), "user".into());
+        let token = encode_token(&keys(&config), &claims).unwrap();
+
+        assert_eq!(decode_token(&keys(&config), &token).unwrap().sub, "user_1");
+    }
+
+    #[test]
+    fn test_rotation_keeps_retired_key_verifying() {
+        let old = asymmetric_config(
+            vec![key("ed-old", Algorithm::EdDSA, Some(ED25519_OLD_PRIVATE), ED25519_OLD_PUBLIC)],
+            "ed-old",
+        );
+        let claims = Claims::new(&old, "user_1".into(), "user".into());
+        let old_token = encode_token(&keys(&old), &claims).unwrap();
+
+        let rotated = asymmetric_config(
+            vec![
+                key("ed-new", Algorithm::EdDSA, Some(ED25519_PRIVATE), ED25519_PUBLIC),
+                key("ed-old", Algorithm::EdDSA, None, ED25519_OLD_PUBLIC),
+            ],
+            "ed-new",
+        );
+        assert!(decode_token(&keys(&rotated), &old_token).is_ok());
+
+        let new_token = encode_token(&keys(&rotated), &claims).unwrap();
+        assert_eq!(decode_header(&new_token).unwrap().kid.as_deref(), Some("ed-new"));
+
+        let retired = asymmetric_config(
+            vec![key("ed-new", Algorithm::EdDSA, Some(ED25519_PRIVATE), ED25519_PUBLIC)],
+            "ed-new",
+        );
+        assert!(decode_token(&keys(&retired), &old_token).is_err());
+    }
+
+    #[test]
+    fn test_asymmetric_rejects_hs256_and_unknown_kid() {
+        let config = asymmetric_config(
+            vec![key("ed-1", Algorithm::EdDSA, Some(ED25519_PRIVATE), ED25519_PUBLIC)],
+            "ed-1",
+        );
+        let claims = Claims::new(&config, "user_1".into(), "user".into());
+
+        let hmac_token = encode_token(&keys(&get_mock_state().config), &claims).unwrap();
+        assert!(decode_token(&keys(&config), &hmac_token).is_err());
+
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("ed-1".into());
+        let confused = encode(&header, &claims, &EncodingKey::from_secret(ED25519_PUBLIC.as_bytes())).unwrap();
+        assert!(decode_token(&keys(&config), &confused).is_err());
+
+        let other = asymmetric_config(
+            vec![key("ed-x", Algorithm::EdDSA, Some(ED25519_OLD_PRIVATE), ED25519_OLD_PUBLIC)],
+            "ed-x",
+        );
+        let unknown_kid = encode_token(&keys(&other), &claims).unwrap();
+        assert!(decode_token(&keys(&config), &unknown_kid).is_err());
+    }
+
+    #[test]
+    fn test_key_files_read_once() {
+        let dir = std::env::temp_dir().join(format!("jwt-keys-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (private, public) = (dir.join("private.pem"), dir.join("public.pem"));
+        std::fs::write(&private, ED25519_PRIVATE).unwrap();
+        std::fs::write(&public, ED25519_PUBLIC).unwrap();
+
+        let mut key = key("ed-1", Algorithm::EdDSA, None, ED25519_PUBLIC);
+        key.public_key_pem = None;
+        key.private_key_file = Some(private.to_string_lossy().into());
+        key.public_key_file = Some(public.to_string_lossy().into());
+        let config = asymmetric_config(vec![key], "ed-1");
+        let keys = keys(&config);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let claims = Claims::new(&config, "user_1".into(), "user".into());
+        let token = encode_token(&keys, &claims).unwrap();
+        assert_eq!(decode_token(&keys, &token).unwrap().sub, "user_1");
+    }
+
+    #[test]
+    fn test_jwks_publishes_public_keys() {
+        let config = asymmetric_config(
+            vec![
+                key("ed-1", Algorithm::EdDSA, Some(ED25519_PRIVATE), ED25519_PUBLIC),
+                key("rsa-1", Algorithm::RS256, None, RSA_PUBLIC),
+            ],
+            "ed-1",
+        );
+        let set = keys(&config).jwks().clone();
+        assert_eq!(set.keys.len(), 2);
+
+        let claims = Claims::new(&config, "user_1".into(), "user".into());
+        let token = encode_token(&keys(&config), &claims).unwrap();
+
+        // A third party verifying with nothing but the published JWK.
+        let jwk = set.find("ed-1").unwrap();
+        let mut validation = Validation::new(Algorithm::EdDSA);
+        validation.set_audience(&[&config.jwt_audience]);
+        let decoded = decode::<Claims>(&token, &DecodingKey::from_jwk(jwk).unwrap(), &validation);
+        assert!(decoded.is_ok());
+
+        let json = serde_json::to_value(&set).unwrap();
+        assert_eq!(json["keys"][1]["kty"], "RSA");
+        assert_eq!(json["keys"][1]["e"], "AQAB");
+        assert!(json["keys"][0].get("d").is_none());
+    }
+
+    #[test]
+    fn test_jwks_empty_for_hs256() {
+        let config = get_mock_state().config.clone();
+        assert!(jwks(&config).unwrap().keys.is_empty());
+        assert!(keys(&config).jwks().keys.is_empty());
+    }
+
+    #[test]
+    fn test_keys_fail_on_bad_config() {
+        assert!(JwtKeys::new(&get_mock_state().config).is_ok());
+
+        let good = asymmetric_config(
+            vec![key("ed-1", Algorithm::EdDSA, Some(ED25519_PRIVATE), ED25519_PUBLIC)],
+            "ed-1",
+        );
+        assert!(JwtKeys::new(&good).is_ok());
+
+        let missing_private = asymmetric_config(
+            vec![key("ed-1", Algorithm::EdDSA, None, ED25519_PUBLIC)],
+            "ed-1",
+        );
+        assert!(JwtKeys::new(&missing_private).is_err());
+
+        let unknown_kid = asymmetric_config(
+            vec![key("ed-1", Algorithm::EdDSA, Some(ED25519_PRIVATE), ED25519_PUBLIC)],
+            "nope",
+        );
+        assert!(JwtKeys::new(&unknown_kid).is_err());
+
+        let hmac_alg = asymmetric_config(
+            vec![key("h-1", Algorithm::HS256, Some("x"), "y")],
+            "h-1",
+        );
+        assert!(JwtKeys::new(&hmac_alg).is_err());
+    }
+}
@@ -1,3 +1,8 @@
+pub mod blocking;
+pub mod crypto;
+pub mod jwt;
 pub mod pagination;
+pub mod password;
+pub mod password_policy;
 pub mod response;
 pub mod time;
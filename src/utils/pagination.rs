@@ -1,9 +1,200 @@
+use crate::{
+    error::AppError,
+    state::AppState,
+    utils::crypto::{sign_token, verify_signed_token},
+};
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, OriginalUri, Query},
+    http::{request::Parts, Uri},
+};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use validator::{ValidationError, ValidationErrors};
+
+const CURSOR: &str = "pagination_cursor";
+
+/// How long a cursor can be followed. Long enough for an export, short enough to not leak ids forever.
+const CURSOR_TTL_SECS: i64 = 24 * 60 * 60;
 
 #[derive(Debug, Deserialize)]
 pub struct PaginationParams {
     pub page: Option<u64>,
     pub limit: Option<u64>,
+    /// Switches to keyset pagination. Send it empty for the first page.
+    pub cursor: Option<String>,
+}
+
+/// The query string before validation. Numbers are parsed by hand so that a value like
+/// `page=abc` is reported per field, the same way as an out-of-range one.
+#[derive(Debug, Deserialize)]
+struct RawPaginationParams {
+    page: Option<String>,
+    limit: Option<String>,
+    cursor: Option<String>,
+}
+
+impl RawPaginationParams {
+    fn parse(self, errors: &mut ValidationErrors) -> PaginationParams {
+        let mut number = |field: &'static str, value: Option<String>| {
+            let value = value?;
+            let parsed = value.trim().parse::<u64>().ok();
+            if parsed.is_none() {
+                errors.add(field, field_error("invalid", format!("{} must be a positive integer", field)));
+            }
+            parsed
+        };
+
+        PaginationParams {
+            page: number("page", self.page),
+            limit: number("limit", self.limit),
+            cursor: self.cursor,
+        }
+    }
+}
+
+fn field_error(code: &'static str, message: String) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(Cow::Owned(message));
+    error
+}
+
+/// Validated paging parameters of a list request, plus what is needed to link to other pages.
+///
+/// `page` is at least 1 and `limit` is between 1 and `AppConfig::pagination_max_limit`, with
+/// `AppConfig::pagination_default_limit` when absent. Bad values are rejected with a 400.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pagination {
+    pub page: u64,
+    pub limit: u64,
+    /// Set in keyset mode, possibly empty for the first page.
+    pub cursor: Option<String>,
+    path: String,
+    /// Query pairs other than the paging ones, kept verbatim for the links.
+    query: Vec<String>,
+}
+
+/// Documents to skip before `page`, or `None` when that does not fit the `i64` MongoDB takes.
+pub fn offset(page: u64, limit: u64) -> Option<u64> {
+    page.checked_sub(1)?
+        .checked_mul(limit)
+        .filter(|skip| *skip <= i64::MAX as u64)
+}
+
+impl Pagination {
+    pub fn from_params(params: PaginationParams, uri: &Uri, default_limit: u64, max_limit: u64) -> Result<Self, AppError> {
+        Self::validate(params, uri, default_limit, max_limit, ValidationErrors::new())
+    }
+
+    /// Checks the ranges, adding to `errors` already found while parsing the query.
+    fn validate(
+        params: PaginationParams,
+        uri: &Uri,
+        default_limit: u64,
+        max_limit: u64,
+        mut errors: ValidationErrors,
+    ) -> Result<Self, AppError> {
+        let mut fail = |field: &'static str, code: &'static str, message: String| {
+            errors.add(field, field_error(code, message));
+        };
+
+        let page = params.page.unwrap_or(1);
+        if page == 0 {
+            fail("page", "range", "page must be at least 1".into());
+        }
+        let limit = params.limit.unwrap_or(default_limit);
+        if limit == 0 || limit > max_limit {
+            fail("limit", "range", format!("limit must be between 1 and {}", max_limit));
+        } else if page > 0 && offset(page, limit).is_none() {
+            fail("page", "range", "page is too large".into());
+        }
+        if params.page.is_some() && params.cursor.is_some() {
+            fail("cursor", "conflict", "Use either page or cursor, not both".into());
+        }
+
+        if !errors.is_empty() {
+            return Err(AppError::InvalidFields(errors));
+        }
+
+        let query = uri
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .filter(|pair| !matches!(pair.split('=').next(), Some("page" | "limit" | "cursor")))
+            .map(str::to_string)
+            .collect();
+
+        Ok(Self {
+            page,
+            limit,
+            cursor: params.cursor,
+            path: uri.path().to_string(),
+            query,
+        })
+    }
+
+    pub fn skip(&self) -> u64 {
+        (self.page - 1) * self.limit
+    }
+
+    /// RFC 8288 `Link` header value with first, prev, next and last for an offset page.
+    pub fn links(&self, total: u64) -> String {
+        let last = total.div_ceil(self.limit).max(1);
+        let mut links = vec![self.link("page=1", "first")];
+        if self.page > 1 {
+            links.push(self.link(&format!("page={}", (self.page - 1).min(last)), "prev"));
+        }
+        if self.page < last {
+            links.push(self.link(&format!("page={}", self.page + 1), "next"));
+        }
+        links.push(self.link(&format!("page={}", last), "last"));
+        links.join(", ")
+    }
+
+    /// `Link` header value for a keyset page. Cursors only go forward, so there is no prev or last.
+    pub fn cursor_links(&self, next_cursor: Option<&str>) -> String {
+        let mut links = vec![self.link("cursor=", "first")];
+        if let Some(next) = next_cursor {
+            links.push(self.link(&format!("cursor={}", next), "next"));
+        }
+        links.join(", ")
+    }
+
+    fn link(&self, position: &str, rel: &str) -> String {
+        let mut query = self.query.clone();
+        query.push(position.to_string());
+        query.push(format!("limit={}", self.limit));
+        format!("<{}?{}>; rel=\"{}\"", self.path, query.join("&"), rel)
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Pagination {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let Query(raw) = Query::<RawPaginationParams>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| AppError::ValidationError(e.body_text()))?;
+        let mut errors = ValidationErrors::new();
+        let params = raw.parse(&mut errors);
+        // Links must carry the full path, not the one seen inside a nested router.
+        let uri = parts
+            .extensions
+            .get::<OriginalUri>()
+            .map(|OriginalUri(uri)| uri.clone())
+            .unwrap_or_else(|| parts.uri.clone());
+
+        Self::validate(
+            params,
+            &uri,
+            state.config.pagination_default_limit,
+            state.config.pagination_max_limit,
+            errors,
+        )
+    }
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -17,7 +208,8 @@ pub struct PaginationResult<T> {
 
 impl<T> PaginationResult<T> {
     pub fn new(data: Vec<T>, page: u64, limit: u64, total: u64) -> Self {
-        let total_pages = (total as f64 / limit as f64).ceil() as u64;
+        // A zero limit would divide by zero; the `Pagination` extractor never produces one.
+        let total_pages = if limit == 0 { 0 } else { total.div_ceil(limit) };
         Self {
             data,
             page,
@@ -27,3 +219,188 @@ impl<T> PaginationResult<T> {
         }
     }
 }
+
+/// Position of the last item of a keyset page, ordered by creation time with the id as tie-breaker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: String,
+}
+
+impl Cursor {
+    /// Signed so clients cannot craft positions; the content is not secret.
+    pub fn encode(&self, secret: &str) -> String {
+        let payload = serde_json::to_string(self).unwrap_or_default();
+        sign_token(secret, CURSOR, &payload, CURSOR_TTL_SECS)
+    }
+
+    pub fn decode(secret: &str, token: &str) -> Result<Self, AppError> {
+        verify_signed_token(secret, CURSOR, token)
+            .and_then(|payload| serde_json::from_str(&payload).ok())
+            .ok_or_else(|| AppError::ValidationError("Invalid or expired cursor".into()))
+    }
+}
+
+/// A keyset page. There is no total: follow `next_cursor` until it is absent.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorPage<T> {
+    pub data: Vec<T>,
+    pub limit: u64,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> CursorPage<T> {
+    pub fn new(data: Vec<T>, limit: u64, next_cursor: Option<String>) -> Self {
+        Self {
+            data,
+            limit,
+            next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cursor() -> Cursor {
+        Cursor {
+            created_at: "2024-05-01T12:00:00.123Z".parse().unwrap(),
+            id: "user_1".into(),
+        }
+    }
+
+    fn params(page: Option<u64>, limit: Option<u64>) -> PaginationParams {
+        PaginationParams { page, limit, cursor: None }
+    }
+
+    fn paginate(params: PaginationParams, uri: &str) -> Result<Pagination, AppError> {
+        Pagination::from_params(params, &uri.parse().unwrap(), 10, 100)
+    }
+
+    #[test]
+    fn test_pagination_defaults() {
+        let pagination = paginate(params(None, None), "/api/v1/users").unwrap();
+        assert_eq!((pagination.page, pagination.limit, pagination.skip()), (1, 10, 0));
+
+        let pagination = paginate(params(Some(3), Some(25)), "/api/v1/users").unwrap();
+        assert_eq!(pagination.skip(), 50);
+    }
+
+    #[test]
+    fn test_pagination_rejects_out_of_range() {
+        for (page, limit, field) in [(Some(0), None, "page"), (None, Some(0), "limit"), (None, Some(101), "limit")] {
+            let Err(AppError::InvalidFields(errors)) = paginate(params(page, limit), "/") else {
+                panic!("expected a validation error for {}", field);
+            };
+            let fields = errors.field_errors();
+            assert_eq!(fields.keys().collect::<Vec<_>>(), [&field], "{:?}", errors);
+            assert_eq!(fields[field][0].code, "range");
+        }
+
+        let both = PaginationParams { page: Some(1), limit: None, cursor: Some("c".into()) };
+        let Err(AppError::InvalidFields(errors)) = paginate(both, "/") else {
+            panic!("expected a validation error for page and cursor");
+        };
+        assert!(errors.field_errors().contains_key("cursor"));
+    }
+
+    #[test]
+    fn test_pagination_rejects_page_past_offset_range() {
+        for page in [u64::MAX, 1_844_674_407_370_955_162, 922_337_203_685_477_582] {
+            let Err(AppError::InvalidFields(errors)) = paginate(params(Some(page), Some(10)), "/") else {
+                panic!("expected a validation error for page {}", page);
+            };
+            assert_eq!(errors.field_errors().keys().collect::<Vec<_>>(), [&"page"], "{:?}", errors);
+        }
+
+        let last = paginate(params(Some(922_337_203_685_477_581), Some(10)), "/").unwrap();
+        assert_eq!(last.skip(), 9_223_372_036_854_775_800);
+        assert_eq!(offset(0, 10), None);
+    }
+
+    #[test]
+    fn test_raw_params_report_unparsable_numbers() {
+        let raw = RawPaginationParams { page: Some("abc".into()), limit: Some("-1".into()), cursor: None };
+        let mut errors = ValidationErrors::new();
+        let params = raw.parse(&mut errors);
+
+        assert_eq!((params.page, params.limit), (None, None));
+        let fields = errors.field_errors();
+        assert_eq!(fields["page"][0].code, "invalid");
+        assert_eq!(fields["limit"][0].code, "invalid");
+
+        let raw = RawPaginationParams { page: Some("2".into()), limit: None, cursor: None };
+        let mut errors = ValidationErrors::new();
+        assert_eq!(raw.parse(&mut errors).page, Some(2));
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_links_keep_filters() {
+        let pagination = paginate(params(Some(2), Some(10)), "/api/v1/users?role=user&page=2&limit=10").unwrap();
+        assert_eq!(
+            pagination.links(35),
+            "</api/v1/users?role=user&page=1&limit=10>; rel=\"first\", \
+             </api/v1/users?role=user&page=1&limit=10>; rel=\"prev\", \
+             </api/v1/users?role=user&page=3&limit=10>; rel=\"next\", \
+             </api/v1/users?role=user&page=4&limit=10>; rel=\"last\""
+        );
+    }
+
+    #[test]
+    fn test_links_edges() {
+        let pagination = paginate(params(None, None), "/users").unwrap();
+        assert_eq!(
+            pagination.links(0),
+            "</users?page=1&limit=10>; rel=\"first\", </users?page=1&limit=10>; rel=\"last\""
+        );
+
+        let pagination = paginate(params(Some(9), None), "/users").unwrap();
+        assert!(pagination.links(15).contains("</users?page=2&limit=10>; rel=\"prev\""));
+        assert!(!pagination.links(15).contains("rel=\"next\""));
+    }
+
+    #[test]
+    fn test_cursor_links() {
+        let pagination = paginate(PaginationParams { page: None, limit: None, cursor: Some(String::new()) }, "/users?cursor=").unwrap();
+        assert_eq!(
+            pagination.cursor_links(Some("abc")),
+            "</users?cursor=&limit=10>; rel=\"first\", </users?cursor=abc&limit=10>; rel=\"next\""
+        );
+        assert!(!pagination.cursor_links(None).contains("next"));
+    }
+
+    #[test]
+    fn test_pagination_result_zero_limit() {
+        assert_eq!(PaginationResult::<u8>::new(vec![], 1, 0, 5).total_pages, 0);
+        assert_eq!(PaginationResult::<u8>::new(vec![], 1, 10, 21).total_pages, 3);
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let token = cursor().encode("secret");
+        assert_eq!(Cursor::decode("secret", &token).unwrap(), cursor());
+    }
+
+    #[test]
+    fn test_cursor_rejects_tampering() {
+        let token = cursor().encode("secret");
+        assert!(Cursor::decode("other-secret", &token).is_err());
+        assert!(Cursor::decode("secret", "garbage").is_err());
+
+        // A token signed for another purpose is not a cursor.
+        let foreign = sign_token("secret", "email_verification", "{}", 60);
+        assert!(Cursor::decode("secret", &foreign).is_err());
+    }
+
+    #[test]
+    fn test_cursor_page_serializes_camel_case() {
+        let page = CursorPage::new(vec![1, 2], 2, Some("next".to_string()));
+        let json = serde_json::to_value(&page).unwrap();
+        assert_eq!(json["nextCursor"], "next");
+        assert!(json.get("total").is_none());
+    }
+}
@@ -0,0 +1,138 @@
+use crate::config::AppConfig;
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, SaltString},
+    Algorithm, Argon2, Params, PasswordHasher as _, PasswordVerifier, Version,
+};
+
+/// Hashes and verifies passwords. Implementations must keep verifying hashes written by
+/// earlier algorithms and report them through `needs_rehash`.
+pub trait IPasswordHasher: Send + Sync {
+    fn hash(&self, password: &str) -> anyhow::Result<String>;
+    /// Unknown or malformed hashes never match.
+    fn verify(&self, password: &str, hash: &str) -> bool;
+    /// True when `hash` was not produced with the current algorithm and parameters.
+    fn needs_rehash(&self, hash: &str) -> bool;
+}
+
+/// Argon2id with configurable cost. Legacy bcrypt hashes (`$2a$`, `$2b$`, `$2y$`) are still
+/// accepted so existing accounts can sign in and be upgraded.
+#[derive(Clone, Default)]
+pub struct PasswordHasher {
+    params: Params,
+}
+
+impl PasswordHasher {
+    pub fn new(config: &AppConfig) -> anyhow::Result<Self> {
+        let params = Params::new(
+            config.password_hash_memory_kib,
+            config.password_hash_iterations,
+            config.password_hash_parallelism,
+            None,
+        )
+        .map_err(|e| anyhow::anyhow!("invalid Argon2 parameters: {}", e))?;
+        Ok(Self { params })
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+}
+
+impl IPasswordHasher for PasswordHasher {
+    fn hash(&self, password: &str) -> anyhow::Result<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        self.argon2()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| anyhow::anyhow!("password hashing failed: {}", e))
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> bool {
+        if is_bcrypt(hash) {
+            return bcrypt::verify(password, hash).unwrap_or(false);
+        }
+
+        // The algorithm and cost are read from the hash itself, so older parameters still verify.
+        PasswordHash::new(hash)
+            .map(|parsed| self.argon2().verify_password(password.as_bytes(), &parsed).is_ok())
+            .unwrap_or(false)
+    }
+
+    fn needs_rehash(&self, hash: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return true;
+        };
+        if parsed.algorithm != Algorithm::Argon2id.ident() || parsed.version != Some(Version::V0x13.into()) {
+            return true;
+        }
+
+        Params::try_from(&parsed)
+            .map(|params| {
+                params.m_cost() != self.params.m_cost()
+                    || params.t_cost() != self.params.t_cost()
+                    || params.p_cost() != self.params.p_cost()
+            })
+            .unwrap_or(true)
+    }
+}
+
+fn is_bcrypt(hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2y$"].iter().any(|prefix| hash.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::get_mock_state;
+
+    fn hasher(memory_kib: u32) -> PasswordHasher {
+        let mut config = get_mock_state().config.clone();
+        config.password_hash_memory_kib = memory_kib;
+        config.password_hash_iterations = 1;
+        PasswordHasher::new(&config).unwrap()
+    }
+
+    #[test]
+    fn test_hash_and_verify() {
+        let hasher = hasher(1024);
+        let hash = hasher.hash("password123").unwrap();
+
+        assert!(hash.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+        assert!(hasher.verify("password123", &hash));
+        assert!(!hasher.verify("wrong", &hash));
+        assert!(!hasher.needs_rehash(&hash));
+    }
+
+    #[test]
+    fn test_verifies_bcrypt_and_asks_for_rehash() {
+        let hasher = hasher(1024);
+        let legacy = bcrypt::hash("password123", 4).unwrap();
+
+        assert!(hasher.verify("password123", &legacy));
+        assert!(!hasher.verify("wrong", &legacy));
+        assert!(hasher.needs_rehash(&legacy));
+    }
+
+    #[test]
+    fn test_changed_params_need_rehash() {
+        let hash = hasher(1024).hash("password123").unwrap();
+
+        let stronger = hasher(2048);
+        assert!(stronger.verify("password123", &hash));
+        assert!(stronger.needs_rehash(&hash));
+    }
+
+    #[test]
+    fn test_garbage_hash() {
+        let hasher = hasher(1024);
+        assert!(!hasher.verify("password123", "not-a-hash"));
+        assert!(hasher.needs_rehash("not-a-hash"));
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let mut config = get_mock_state().config.clone();
+        config.password_hash_iterations = 0;
+        assert!(PasswordHasher::new(&config).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+use crate::{config::AppConfig, error::AppError};
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use std::{borrow::Cow, io::BufRead, sync::Arc};
+use validator::{Validate, ValidationError, ValidationErrors};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CharacterClass {
+    Lowercase,
+    Uppercase,
+    Digit,
+    Symbol,
+}
+
+impl CharacterClass {
+    fn matches(self, c: char) -> bool {
+        match self {
+            CharacterClass::Lowercase => c.is_lowercase(),
+            CharacterClass::Uppercase => c.is_uppercase(),
+            CharacterClass::Digit => c.is_numeric(),
+            CharacterClass::Symbol => !c.is_alphanumeric(),
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            CharacterClass::Lowercase => "Password must contain a lowercase letter",
+            CharacterClass::Uppercase => "Password must contain an uppercase letter",
+            CharacterClass::Digit => "Password must contain a digit",
+            CharacterClass::Symbol => "Password must contain a symbol",
+        }
+    }
+}
+
+/// Known-breached passwords as a sorted list of SHA-1 digests, 20 bytes per entry.
+///
+/// The file holds one uppercase or lowercase hex SHA-1 per line. The `HASH:COUNT` lines of the
+/// Have I Been Pwned downloads are accepted as-is, so a top-N slice of that list can be used
+/// directly. Blank lines and `#` comments are skipped; order does not matter.
+pub struct BreachedPasswords {
+    digests: Vec<[u8; 20]>,
+}
+
+impl BreachedPasswords {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path)
+            .map_err(|e| anyhow::anyhow!("cannot open breached password list {}: {}", path, e))?;
+        Self::from_reader(std::io::BufReader::new(file))
+    }
+
+    pub fn from_reader(reader: impl BufRead) -> anyhow::Result<Self> {
+        let mut digests = Vec::new();
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let entry = line.split(':').next().unwrap_or_default().trim();
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+
+            let mut digest = [0u8; 20];
+            hex::decode_to_slice(entry, &mut digest).map_err(|_| {
+                anyhow::anyhow!("line {} of the breached password list is not a SHA-1 hash", number + 1)
+            })?;
+            digests.push(digest);
+        }
+
+        digests.sort_unstable();
+        digests.dedup();
+        Ok(Self { digests })
+    }
+
+    pub fn contains(&self, password: &str) -> bool {
+        let digest: [u8; 20] = Sha1::digest(password.as_bytes()).into();
+        self.digests.binary_search(&digest).is_ok()
+    }
+
+    pub fn len(&self) -> usize {
+        self.digests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.digests.is_empty()
+    }
+}
+
+/// Strength rules applied wherever a user chooses a password. Violations are reported as
+/// field errors alongside the DTO's own validation.
+#[derive(Clone)]
+pub struct PasswordPolicy {
+    min_length: usize,
+    required_classes: Vec<CharacterClass>,
+    reject_personal_info: bool,
+    breached: Option<Arc<BreachedPasswords>>,
+}
+
+impl PasswordPolicy {
+    /// The breached-password list is not loaded here; see [`Self::with_breached_list`].
+    pub fn new(config: &AppConfig) -> Self {
+        Self {
+            min_length: config.password_min_length,
+            required_classes: config.password_required_classes.clone(),
+            reject_personal_info: config.password_reject_personal_info,
+            breached: None,
+        }
+    }
+
+    pub fn with_breached_list(mut self, breached: Arc<BreachedPasswords>) -> Self {
+        self.breached = Some(breached);
+        self
+    }
+
+    /// Validates `payload` and checks `password` (the value of `field`) against the policy.
+    /// `personal` holds the username and email the password must not contain.
+    pub fn validate(
+        &self,
+        payload: &impl Validate,
+        field: &'static str,
+        password: &str,
+        personal: &[&str],
+    ) -> Result<(), AppError> {
+        let mut errors = payload.validate().err().unwrap_or_default();
+        self.check(field, password, personal, &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::InvalidFields(errors))
+        }
+    }
+
+    pub fn check(&self, field: &'static str, password: &str, personal: &[&str], errors: &mut ValidationErrors) {
+        let mut fail = |code: &'static str, message: String| {
+            let mut error = ValidationError::new(code);
+            error.message = Some(Cow::Owned(message));
+            errors.add(field, error);
+        };
+
+        if password.chars().count() < self.min_length {
+            fail(
+                "password_length",
+                format!("Password must be at least {} characters", self.min_length),
+            );
+        }
+
+        for class in &self.required_classes {
+            if !password.chars().any(|c| class.matches(c)) {
+                fail("password_character_class", class.message().into());
+            }
+        }
+
+        if self.reject_personal_info && contains_personal_info(password, personal) {
+            fail(
+                "password_personal_info",
+                "Password must not contain your username or email".into(),
+            );
+        }
+
+        if self.breached.as_ref().is_some_and(|list| list.contains(password)) {
+            fail(
+                "password_breached",
+                "This password has appeared in a data breach, choose a different one".into(),
+            );
+        }
+    }
+}
+
+/// Matches the whole email, its local part and the username, ignoring case. Values shorter
+/// than three characters are ignored so short names do not block common substrings.
+fn contains_personal_info(password: &str, personal: &[&str]) -> bool {
+    let password = password.to_lowercase();
+    personal
+        .iter()
+        .flat_map(|value| [*value, value.split('@').next().unwrap_or_default()])
+        .map(|value| value.trim().to_lowercase())
+        .any(|value| value.chars().count() >= 3 && password.contains(&value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::get_mock_state;
+
+    fn policy(required_classes: Vec<CharacterClass>) -> PasswordPolicy {
+        let mut config = get_mock_state().config.clone();
+        config.password_required_classes = required_classes;
+        PasswordPolicy::new(&config)
+    }
+
+    fn codes(policy: &PasswordPolicy, password: &str, personal: &[&str]) -> Vec<String> {
+        let mut errors = ValidationErrors::new();
+        policy.check("password", password, personal, &mut errors);
+        errors
+            .field_errors()
+            .get("password")
+            .map(|errors| errors.iter().map(|e| e.code.to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_min_length() {
+        let policy = policy(vec![]);
+        assert_eq!(codes(&policy, "short", &[]), vec!["password_length"]);
+        assert!(codes(&policy, "long enough", &[]).is_empty());
+        // Counted in characters, not bytes.
+        assert_eq!(codes(&policy, "ééééééé", &[]), vec!["password_length"]);
+    }
+
+    #[test]
+    fn test_character_classes() {
+        let policy = policy(vec![CharacterClass::Uppercase, CharacterClass::Digit, CharacterClass::Symbol]);
+        assert_eq!(
+            codes(&policy, "alllowercase", &[]),
+            vec!["password_character_class"; 3]
+        );
+        assert!(codes(&policy, "Lower-and-1", &[]).is_empty());
+    }
+
+    #[test]
+    fn test_personal_info() {
+        let policy = policy(vec![]);
+        let personal = ["alice", "alice.smith@example.com"];
+        assert_eq!(codes(&policy, "xxAlice2024", &personal), vec!["password_personal_info"]);
+        assert_eq!(codes(&policy, "ALICE.SMITH!!", &personal), vec!["password_personal_info"]);
+        assert!(codes(&policy, "correct horse", &personal).is_empty());
+        assert!(codes(&policy, "about jo stuff", &["jo"]).is_empty());
+    }
+
+    #[test]
+    fn test_breached_list() {
+        let list = "# top passwords\n\
+                    5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:9545824\n\
+                    \n\
+                    7c4a8d09ca3762af61e59520943dc26494f8941b\n";
+        let breached = BreachedPasswords::from_reader(list.as_bytes()).unwrap();
+        assert_eq!(breached.len(), 2);
+        assert!(breached.contains("password"));
+        assert!(breached.contains("123456"));
+        assert!(!breached.contains("correct horse battery staple"));
+
+        let policy = policy(vec![]).with_breached_list(Arc::new(breached));
+        assert_eq!(codes(&policy, "password", &[]), vec!["password_breached"]);
+    }
+
+    #[test]
+    fn test_breached_list_rejects_garbage() {
+        assert!(BreachedPasswords::from_reader("not-a-hash\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_validate_merges_dto_errors() {
+        #[derive(Validate)]
+        struct Payload {
+            #[validate(length(min = 3, message = "Username must be at least 3 characters"))]
+            username: String,
+        }
+
+        let payload = Payload { username: "ab".into() };
+        let Err(AppError::InvalidFields(errors)) = policy(vec![]).validate(&payload, "password", "short", &[]) else {
+            panic!("expected a validation error");
+        };
+        let fields = errors.field_errors();
+        assert_eq!(fields["username"][0].code, "length");
+        assert_eq!(fields["password"][0].code, "password_length");
+    }
+}
@@ -0,0 +1,263 @@
+use fldp_rust_backend_template::handlers::admin_handler::AdminHandler;
+use fldp_rust_backend_template::mock::get_mock_state;
+use fldp_rust_backend_template::mock::db_mock::MockRedisProvider;
+use fldp_rust_backend_template::mock::services::user_service_mock::MockUserService;
+use fldp_rust_backend_template::dtos::user::UserResponse;
+use fldp_rust_backend_template::error::AppError;
+use fldp_rust_backend_template::middlewares::auth::AuthUser;
+use fldp_rust_backend_template::state::InnerState;
+use fldp_rust_backend_template::utils::jwt;
+use axum::response::IntoResponse;
+use axum::extract::{State, Path};
+use std::sync::Arc;
+use mockall::predicate::*;
+use chrono::Utc;
+
+#[tokio::test]
+async fn test_revoke_user_sessions_handler() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user()
+        .with(eq("123"))
+        .times(1)
+        .returning(|id| Ok(UserResponse {
+            id: id.to_string(),
+            username: "test".into(),
+            email: "test@test.com".into(),
+            role: "user".into(),
+            email_verified: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }));
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_smembers()
+        .with(eq("user_sessions:123"))
+        .returning(|_| Ok(vec!["s1".into()]));
+    mock_redis.expect_del()
+        .with(eq("refresh_family:s1"))
+        .times(1)
+        .returning(|_| Ok(()));
+    mock_redis.expect_del()
+        .with(eq("session:s1"))
+        .times(1)
+        .returning(|_| Ok(()));
+    mock_redis.expect_srem()
+        .with(eq("user_sessions:123"), eq("s1"))
+        .times(1)
+        .returning(|_, _| Ok(()));
+    mock_redis.expect_set_ex()
+        .withf(|key, _, _| key == "tokens_valid_after:123")
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let state = get_mock_state();
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    let res = AdminHandler::revoke_user_sessions(State(state), Path("123".into())).await;
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn test_revoke_user_sessions_handler_not_found() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user()
+        .returning(|_| Err(AppError::NotFound));
+
+    let state = get_mock_state();
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        state.redis.clone(),
+        Arc::new(mock_service),
+    ));
+
+    let res = AdminHandler::revoke_user_sessions(State(state), Path("nonexistent".into())).await;
+    assert!(matches!(res, Err(AppError::NotFound)));
+}
+
+fn account(id: &str, role: &str) -> UserResponse {
+    UserResponse {
+        id: id.to_string(),
+        username: "test".into(),
+        email: "test@test.com".into(),
+        role: role.into(),
+        email_verified: true,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn state_with_target(role: &'static str) -> fldp_rust_backend_template::state::AppState {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user().returning(move |id| Ok(account(id, role)));
+
+    let state = get_mock_state();
+    Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        state.redis.clone(),
+        Arc::new(mock_service),
+    ))
+}
+
+#[tokio::test]
+async fn test_impersonate_issues_short_lived_token_with_actor() {
+    let state = state_with_target("user");
+    let admin = AuthUser::new("admin_1", "admin", vec![]);
+
+    let res = AdminHandler::impersonate(State(state.clone()), admin, Path("123".into()))
+        .await
+        .unwrap()
+        .into_response();
+    let body = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    let claims = jwt::decode_token(state.jwt_keys().unwrap(), body["data"]["token"].as_str().unwrap()).unwrap();
+    assert_eq!(claims.sub, "123");
+    assert_eq!(claims.act.unwrap().sub, "admin_1");
+    assert!(claims.sid.is_none());
+    assert_eq!(claims.exp - claims.iat, state.config.impersonation_ttl_secs as usize);
+    assert_eq!(body["data"]["expiresIn"], state.config.impersonation_ttl_secs);
+}
+
+#[tokio::test]
+async fn test_impersonate_rejects_admin_target() {
+    let state = state_with_target("admin");
+    let admin = AuthUser::new("admin_1", "admin", vec![]);
+
+    let res = AdminHandler::impersonate(State(state), admin, Path("admin_2".into())).await;
+    assert!(matches!(res, Err(AppError::PermissionDenied)));
+}
+
+#[tokio::test]
+async fn test_impersonate_rejects_roles_with_admin_permission() {
+    use fldp_rust_backend_template::models::permission::Permission;
+    use std::collections::HashMap;
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user().returning(|id| Ok(account(id, "support")));
+
+    let state = get_mock_state();
+    let mut config = state.config.clone();
+    config.role_permissions = HashMap::from([("support".to_string(), vec![Permission::UsersAdmin])]);
+    let state = Arc::new(InnerState::new(state.db.clone(), config, state.redis.clone(), Arc::new(mock_service)));
+    let admin = AuthUser::new("admin_1", "admin", vec![]);
+
+    let res = AdminHandler::impersonate(State(state), admin, Path("support_1".into())).await;
+    assert!(matches!(res, Err(AppError::PermissionDenied)));
+}
+
+#[tokio::test]
+async fn test_impersonate_rejects_self() {
+    let state = state_with_target("admin");
+    let admin = AuthUser::new("admin_1", "admin", vec![]);
+
+    let res = AdminHandler::impersonate(State(state), admin, Path("admin_1".into())).await;
+    assert!(matches!(res, Err(AppError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_impersonate_cannot_be_chained() {
+    let state = state_with_target("user");
+    let caller = AuthUser {
+        actor_id: Some("admin_0".into()),
+        ..AuthUser::new("admin_1", "admin", vec![])
+    };
+
+    let res = AdminHandler::impersonate(State(state), caller, Path("123".into())).await;
+    assert!(matches!(res, Err(AppError::PermissionDenied)));
+}
+
+#[tokio::test]
+async fn test_restore_user_handler() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_restore_user()
+        .with(eq("123"))
+        .times(1)
+        .returning(|id| Ok(account(id, "user")));
+
+    let state = get_mock_state();
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        state.redis.clone(),
+        Arc::new(mock_service),
+    ));
+
+    let res = AdminHandler::restore_user(State(state), Path("123".into())).await;
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn test_purge_user_handler_applies_retention() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_purge_user()
+        .withf(|id, deleted_before| {
+            let age = Utc::now() - *deleted_before;
+            id == "123" && age >= chrono::Duration::days(30) && age < chrono::Duration::days(31)
+        })
+        .times(1)
+        .returning(|_, _| Ok(()));
+
+    let state = get_mock_state();
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        state.redis.clone(),
+        Arc::new(mock_service),
+    ));
+
+    let admin = AuthUser::new("admin_1", "admin", vec![]);
+    let res = AdminHandler::purge_user(State(state), admin, Path("123".into())).await;
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn test_admin_routes_reject_api_key_without_admin_scope() {
+    use axum::{body::Body, http::{Request, StatusCode}};
+    use fldp_rust_backend_template::mock::services::api_key_service_mock::MockApiKeyService;
+    use fldp_rust_backend_template::models::{api_key::ApiKey, permission::Permission};
+    use fldp_rust_backend_template::routes::admin_routes::admin_routes;
+    use tower::ServiceExt;
+
+    // The owner is an admin, but the key was only granted users:read.
+    let mut mock_keys = MockApiKeyService::new();
+    mock_keys.expect_authenticate().returning(|_| Ok(ApiKey {
+        id: "key_1".into(),
+        name: "batch".into(),
+        prefix: "sk_12345678".into(),
+        key_hash: "hash".into(),
+        owner_id: "admin_1".into(),
+        scopes: vec![Permission::UsersRead],
+        expires_at: None,
+        last_used_at: None,
+        revoked_at: None,
+        created_at: Utc::now(),
+    }));
+
+    let state = state_with_target("admin");
+    let state = Arc::new(
+        InnerState::new(state.db.clone(), state.config.clone(), state.redis.clone(), state.user_service.clone())
+            .with_api_key_service(Arc::new(mock_keys)),
+    );
+
+    let response = admin_routes(state.clone())
+        .with_state(state)
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri("/admin/users/user_1")
+                .header("X-API-Key", "sk_valid")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,158 @@
+use fldp_rust_backend_template::handlers::api_key_handler::ApiKeyHandler;
+use fldp_rust_backend_template::mock::get_mock_state;
+use fldp_rust_backend_template::mock::services::api_key_service_mock::MockApiKeyService;
+use fldp_rust_backend_template::mock::services::user_service_mock::MockUserService;
+use fldp_rust_backend_template::dtos::api_key::{ApiKeyResponse, CreateApiKey, CreatedApiKey};
+use fldp_rust_backend_template::dtos::user::UserResponse;
+use fldp_rust_backend_template::middlewares::auth::AuthUser;
+use fldp_rust_backend_template::models::permission::Permission;
+use fldp_rust_backend_template::error::AppError;
+use fldp_rust_backend_template::state::InnerState;
+use axum::extract::{State, Path, Json};
+use axum::response::IntoResponse;
+use std::sync::Arc;
+use mockall::predicate::*;
+use chrono::Utc;
+
+fn admin() -> AuthUser {
+    AuthUser::new("admin_1", "admin", vec![Permission::UsersAdmin])
+}
+
+fn input() -> CreateApiKey {
+    CreateApiKey {
+        name: "batch".into(),
+        owner_id: None,
+        scopes: vec![Permission::UsersRead],
+        expires_at: None,
+    }
+}
+
+fn user(id: &str) -> UserResponse {
+    UserResponse {
+        id: id.to_string(),
+        username: "admin".into(),
+        email: "admin@test.com".into(),
+        role: "admin".into(),
+        email_verified: true,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[tokio::test]
+async fn test_create_api_key_defaults_owner_to_caller() {
+    let mut mock_users = MockUserService::new();
+    mock_users.expect_get_user()
+        .with(eq("admin_1"))
+        .returning(|id| Ok(user(id)));
+
+    let mut mock_keys = MockApiKeyService::new();
+    mock_keys.expect_create_key()
+        .withf(|owner, _| owner == "admin_1")
+        .times(1)
+        .returning(|owner, input| Ok(CreatedApiKey {
+            key: "sk_full".into(),
+            api_key: ApiKeyResponse {
+                id: "key_1".into(),
+                name: input.name,
+                prefix: "sk_full".into(),
+                owner_id: owner.to_string(),
+                scopes: input.scopes,
+                expires_at: None,
+                last_used_at: None,
+                revoked_at: None,
+                created_at: Utc::now(),
+            },
+        }));
+
+    let state = get_mock_state();
+    let state = Arc::new(
+        InnerState::new(state.db.clone(), state.config.clone(), state.redis.clone(), Arc::new(mock_users))
+            .with_api_key_service(Arc::new(mock_keys)),
+    );
+
+    let res = ApiKeyHandler::create(State(state), admin(), Json(input())).await;
+    assert_eq!(res.unwrap().into_response().status(), 201);
+}
+
+#[tokio::test]
+async fn test_create_api_key_unknown_owner() {
+    let mut mock_users = MockUserService::new();
+    mock_users.expect_get_user().returning(|_| Err(AppError::NotFound));
+
+    let mut mock_keys = MockApiKeyService::new();
+    mock_keys.expect_create_key().never();
+
+    let state = get_mock_state();
+    let state = Arc::new(
+        InnerState::new(state.db.clone(), state.config.clone(), state.redis.clone(), Arc::new(mock_users))
+            .with_api_key_service(Arc::new(mock_keys)),
+    );
+
+    let mut payload = input();
+    payload.owner_id = Some("ghost".into());
+    let res = ApiKeyHandler::create(State(state), admin(), Json(payload)).await;
+    assert!(matches!(res, Err(AppError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_create_api_key_rejects_api_key_caller() {
+    let mut caller = admin();
+    caller.api_key_id = Some("key_0".into());
+
+    let state = get_mock_state();
+    let res = ApiKeyHandler::create(State(state), caller, Json(input())).await;
+    assert!(matches!(res, Err(AppError::PermissionDenied)));
+}
+
+#[tokio::test]
+async fn test_create_api_key_rejects_impersonation() {
+    let mut caller = admin();
+    caller.actor_id = Some("admin_0".into());
+
+    let state = get_mock_state();
+    let res = ApiKeyHandler::create(State(state), caller, Json(input())).await;
+    assert!(matches!(res, Err(AppError::PermissionDenied)));
+}
+
+#[tokio::test]
+async fn test_create_api_key_validation_error() {
+    let mut payload = input();
+    payload.scopes.clear();
+
+    let state = get_mock_state();
+    let res = ApiKeyHandler::create(State(state), admin(), Json(payload)).await;
+    assert!(matches!(res, Err(AppError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_list_api_keys() {
+    let mut mock_keys = MockApiKeyService::new();
+    mock_keys.expect_list_keys().times(1).returning(|| Ok(vec![]));
+
+    let state = get_mock_state();
+    let state = Arc::new(
+        InnerState::new(state.db.clone(), state.config.clone(), state.redis.clone(), state.user_service.clone())
+            .with_api_key_service(Arc::new(mock_keys)),
+    );
+
+    let res = ApiKeyHandler::list(State(state)).await;
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn test_revoke_api_key_not_found() {
+    let mut mock_keys = MockApiKeyService::new();
+    mock_keys.expect_revoke_key()
+        .with(eq("missing"))
+        .returning(|_| Err(AppError::NotFound));
+
+    let state = get_mock_state();
+    let state = Arc::new(
+        InnerState::new(state.db.clone(), state.config.clone(), state.redis.clone(), state.user_service.clone())
+            .with_api_key_service(Arc::new(mock_keys)),
+    );
+
+    let res = ApiKeyHandler::revoke(State(state), Path("missing".into())).await;
+    assert!(matches!(res, Err(AppError::NotFound)));
+}
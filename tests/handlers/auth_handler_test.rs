@@ -1,10 +1,24 @@
-use fldp_rust_backend_template::handlers::auth_handler::{AuthHandler, LoginRequest};
+use fldp_rust_backend_template::handlers::auth_handler::{
+    AuthHandler, Claims, ForgotPasswordRequest, LoginRequest, LoginResponse, MagicLinkQuery, MagicLinkRequest,
+    MfaLoginRequest, RefreshRequest, ResendVerificationRequest, ResetPasswordRequest, VerifyEmailQuery,
+};
+use fldp_rust_backend_template::models::user::UserMfa;
+use fldp_rust_backend_template::middlewares::client_ip::{ClientIp, UserAgent};
+use fldp_rust_backend_template::error::AppError;
+use fldp_rust_backend_template::utils::crypto::{encrypt, sha256_hex, sign_token};
+use fldp_rust_backend_template::utils::password_policy::{BreachedPasswords, PasswordPolicy};
+use axum::extract::Query;
+use axum::http::HeaderMap;
+use fldp_rust_backend_template::mock::providers::email_provider_mock::MockEmailProvider;
 use fldp_rust_backend_template::mock::get_mock_state;
+use fldp_rust_backend_template::mock::db_mock::MockRedisProvider;
 use fldp_rust_backend_template::mock::services::user_service_mock::MockUserService;
 use fldp_rust_backend_template::dtos::user::{CreateUser, UserResponse};
 use fldp_rust_backend_template::state::InnerState;
 use fldp_rust_backend_template::models::user::User;
 use axum::extract::{State, Json};
+use axum::Extension;
+use axum::response::IntoResponse;
 use std::sync::Arc;
 use mockall::predicate::*;
 use chrono::Utc;
@@ -23,6 +37,7 @@ async fn test_register_handler_success() {
         username: "test".into(),
         email: "test@test.com".into(),
         role: "user".into(),
+        email_verified: false,
         created_at: Utc::now(),
         updated_at: Utc::now(),
     };
@@ -31,13 +46,22 @@ async fn test_register_handler_success() {
         .times(1)
         .returning(move |_| Ok(response.clone()));
 
+    let mut mock_email = MockEmailProvider::new();
+    mock_email.expect_send_email()
+        .withf(|to, _, body| to == "test@test.com" && body.contains("/api/v1/auth/verify-email?token="))
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
     let state = get_mock_state();
-    let state = Arc::new(InnerState::new(
-        state.db.clone(),
-        state.config.clone(),
-        state.redis.clone(),
-        Arc::new(mock_service),
-    ));
+    let state = Arc::new(
+        InnerState::new(
+            state.db.clone(),
+            state.config.clone(),
+            state.redis.clone(),
+            Arc::new(mock_service),
+        )
+        .with_email_provider(Arc::new(mock_email)),
+    );
 
     let res = AuthHandler::register(State(state), Json(input)).await;
     assert!(res.is_ok());
@@ -57,8 +81,12 @@ async fn test_login_handler_success() {
         email: "test@test.com".into(),
         password_hash: "hash".into(),
         role: "user".into(),
+        email_verified: false,
+        mfa: None,
+        identities: vec![],
         created_at: Utc::now(),
         updated_at: Utc::now(),
+        deleted_at: None,
     };
 
     mock_service.expect_authenticate()
@@ -66,18 +94,140 @@ async fn test_login_handler_success() {
         .times(1)
         .returning(move |_, _| Ok(mock_user.clone()));
 
+    let mut mock_redis = not_locked_redis();
+    mock_redis.expect_del()
+        .withf(|k| k.starts_with("login_failures:email:"))
+        .times(1)
+        .returning(|_| Ok(()));
+    mock_redis.expect_del()
+        .with(eq("login_failures:ip:127.0.0.1"))
+        .times(1)
+        .returning(|_| Ok(()));
+    mock_redis.expect_set_ex()
+        .times(3)
+        .returning(|_, _, _| Ok(()));
+    mock_redis.expect_sadd_ex()
+        .withf(|key, _, _| key == "user_sessions:123")
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
     let state = get_mock_state();
     let state = Arc::new(InnerState::new(
         state.db.clone(),
         state.config.clone(),
-        state.redis.clone(),
+        Arc::new(mock_redis),
         Arc::new(mock_service),
     ));
 
-    let res = AuthHandler::login(State(state), Json(payload)).await;
+    let res = AuthHandler::login(State(state), ClientIp("127.0.0.1".into()), UserAgent(None), Json(payload)).await;
+    assert!(res.is_ok());
+    let LoginResponse::Authenticated(auth_res) = res.unwrap().0 else {
+        panic!("expected tokens");
+    };
+    assert!(!auth_res.token.is_empty());
+    assert!(!auth_res.refresh_token.is_empty());
+}
+
+#[tokio::test]
+async fn test_refresh_handler_success() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user()
+        .with(eq("123"))
+        .times(1)
+        .returning(|id| Ok(UserResponse {
+            id: id.to_string(),
+            username: "test".into(),
+            email: "test@test.com".into(),
+            role: "user".into(),
+            email_verified: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }));
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get()
+        .withf(|key| key.starts_with("refresh_token:"))
+        .returning(|_| Ok(Some(r#"{"user_id":"123","family_id":"fam","issued_at":0}"#.into())));
+    mock_redis.expect_get()
+        .with(eq("refresh_family:fam"))
+        .returning(|_| Ok(Some("123".into())));
+    mock_redis.expect_get()
+        .with(eq("tokens_valid_after:123"))
+        .returning(|_| Ok(None));
+    mock_redis.expect_get()
+        .with(eq("session:fam"))
+        .returning(|_| Ok(Some(format!(
+            r#"{{"id":"fam","userId":"123","ip":"10.0.0.1","userAgent":null,"createdAt":"{0}","lastSeenAt":"{0}"}}"#,
+            Utc::now().to_rfc3339()
+        ))));
+    mock_redis.expect_set_nx_ex()
+        .times(1)
+        .returning(|_, _, _| Ok(true));
+    mock_redis.expect_set_ex()
+        .withf(|key, value, _| key == "session:fam" && value.contains("127.0.0.1"))
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+    mock_redis.expect_set_ex()
+        .times(2)
+        .returning(|_, _, _| Ok(()));
+    mock_redis.expect_sadd_ex()
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let state = get_mock_state();
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    let payload = RefreshRequest { refresh_token: "old".into() };
+    let res = AuthHandler::refresh(State(state), ClientIp("127.0.0.1".into()), Json(payload)).await;
     assert!(res.is_ok());
     let auth_res = res.unwrap();
     assert!(!auth_res.token.is_empty());
+    assert_ne!(auth_res.refresh_token, "old");
+}
+
+#[tokio::test]
+async fn test_refresh_handler_reused_token() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get()
+        .withf(|key| key.starts_with("refresh_token:"))
+        .returning(|_| Ok(Some(r#"{"user_id":"123","family_id":"fam","issued_at":0}"#.into())));
+    mock_redis.expect_get()
+        .with(eq("refresh_family:fam"))
+        .returning(|_| Ok(Some("123".into())));
+    mock_redis.expect_get()
+        .with(eq("tokens_valid_after:123"))
+        .returning(|_| Ok(None));
+    mock_redis.expect_set_nx_ex()
+        .returning(|_, _, _| Ok(false));
+    mock_redis.expect_del()
+        .with(eq("refresh_family:fam"))
+        .times(1)
+        .returning(|_| Ok(()));
+
+    let state = get_mock_state();
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        state.user_service.clone(),
+    ));
+
+    let payload = RefreshRequest { refresh_token: "old".into() };
+    let res = AuthHandler::refresh(State(state), ClientIp("127.0.0.1".into()), Json(payload)).await;
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn test_refresh_handler_validation_error() {
+    let state = get_mock_state();
+    let payload = RefreshRequest { refresh_token: "".into() };
+    let res = AuthHandler::refresh(State(state), ClientIp("127.0.0.1".into()), Json(payload)).await;
+    assert!(res.is_err());
 }
 
 #[tokio::test]
@@ -93,6 +243,30 @@ async fn test_register_handler_validation_error() {
     assert!(res.is_err());
 }
 
+#[tokio::test]
+async fn test_register_rejects_breached_password() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_create_user().never();
+
+    let state = get_mock_state();
+    let breached = BreachedPasswords::from_reader("5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:9545824\n".as_bytes()).unwrap();
+    let state = Arc::new(
+        InnerState::new(state.db.clone(), state.config.clone(), state.redis.clone(), Arc::new(mock_service))
+            .with_password_policy(PasswordPolicy::new(&state.config).with_breached_list(Arc::new(breached))),
+    );
+
+    let input = CreateUser {
+        username: "newuser".into(),
+        email: "new@test.com".into(),
+        password: "password".into(),
+    };
+    let res = AuthHandler::register(State(state), Json(input)).await;
+    let Err(AppError::InvalidFields(errors)) = res else {
+        panic!("expected a validation error");
+    };
+    assert_eq!(errors.field_errors()["password"][0].code, "password_breached");
+}
+
 #[tokio::test]
 async fn test_login_handler_validation_error() {
     let payload = LoginRequest {
@@ -101,7 +275,7 @@ async fn test_login_handler_validation_error() {
     };
 
     let state = get_mock_state();
-    let res = AuthHandler::login(State(state), Json(payload)).await;
+    let res = AuthHandler::login(State(state), ClientIp("127.0.0.1".into()), UserAgent(None), Json(payload)).await;
     assert!(res.is_err());
 }
 
@@ -111,11 +285,16 @@ async fn test_login_handler_fail() {
     mock_service.expect_authenticate()
         .returning(|_, _| Err(fldp_rust_backend_template::error::AppError::InvalidCredentials));
 
+    let mut mock_redis = not_locked_redis();
+    mock_redis.expect_incr_ex()
+        .times(2)
+        .returning(|_, _| Ok(1));
+
     let state = get_mock_state();
     let state = Arc::new(InnerState::new(
         state.db.clone(),
         state.config.clone(),
-        state.redis.clone(),
+        Arc::new(mock_redis),
         Arc::new(mock_service),
     ));
 
@@ -124,6 +303,756 @@ async fn test_login_handler_fail() {
         password: "wrong".into(),
     };
 
-    let res = AuthHandler::login(State(state), Json(payload)).await;
+    let res = AuthHandler::login(State(state), ClientIp("127.0.0.1".into()), UserAgent(None), Json(payload)).await;
+    assert!(matches!(res, Err(AppError::InvalidCredentials)));
+}
+
+fn not_locked_redis() -> MockRedisProvider {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_ttl()
+        .withf(|k| k.starts_with("login_lock:"))
+        .returning(|_| Ok(-2));
+    mock_redis
+}
+
+#[tokio::test]
+async fn test_login_handler_locked_out() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_authenticate().never();
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_ttl()
+        .with(eq("login_lock:ip:127.0.0.1"))
+        .returning(|_| Ok(120));
+    mock_redis.expect_ttl().returning(|_| Ok(-2));
+
+    let state = get_mock_state();
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    let payload = LoginRequest {
+        email: "test@test.com".into(),
+        password: "password123".into(),
+    };
+
+    let res = AuthHandler::login(State(state), ClientIp("127.0.0.1".into()), UserAgent(None), Json(payload)).await;
+    let res = res.err().unwrap().into_response();
+    assert_eq!(res.status(), 429);
+    assert_eq!(res.headers()["retry-after"], "120");
+}
+
+#[tokio::test]
+async fn test_login_handler_locks_after_max_attempts() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_authenticate()
+        .returning(|_, _| Err(AppError::InvalidCredentials));
+
+    let mut mock_redis = not_locked_redis();
+    mock_redis.expect_incr_ex()
+        .withf(|k, _| k.starts_with("login_failures:email:"))
+        .returning(|_, _| Ok(5));
+    mock_redis.expect_incr_ex()
+        .withf(|k, _| k == "login_failures:ip:127.0.0.1")
+        .returning(|_, _| Ok(5));
+    mock_redis.expect_set_ex()
+        .withf(|k, _, ttl| k.starts_with("login_lock:email:") && *ttl == 30)
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let state = get_mock_state();
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    let payload = LoginRequest {
+        email: "test@test.com".into(),
+        password: "wrong".into(),
+    };
+
+    let res = AuthHandler::login(State(state), ClientIp("127.0.0.1".into()), UserAgent(None), Json(payload)).await;
+    assert!(matches!(res, Err(AppError::InvalidCredentials)));
+}
+
+#[tokio::test]
+async fn test_logout_handler_denylists_token() {
+    let state = get_mock_state();
+    let claims = Claims::new(&state.config, "123".into(), "user".into());
+    let key = format!("revoked_jti:{}", claims.jti);
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_set_ex()
+        .withf(move |k, _, ttl| k == key && *ttl > 0)
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        state.user_service.clone(),
+    ));
+
+    let res = AuthHandler::logout(State(state), Some(Extension(claims))).await;
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn test_logout_handler_rejects_api_key() {
+    let res = AuthHandler::logout(State(get_mock_state()), None).await;
+    assert!(matches!(res, Err(AppError::PermissionDenied)));
+}
+
+#[tokio::test]
+async fn test_forgot_password_sends_email() {
+    let state = get_mock_state();
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_find_by_email()
+        .with(eq("test@test.com"))
+        .times(1)
+        .returning(|_| Ok(Some(UserResponse {
+            id: "123".into(),
+            username: "test".into(),
+            email: "test@test.com".into(),
+            role: "user".into(),
+            email_verified: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })));
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_set_ex()
+        .withf(|k, v, ttl| k.starts_with("one_time_token:password_reset:") && v == "123" && *ttl == 3600)
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let (sent, mut sent_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut mock_email = MockEmailProvider::new();
+    mock_email.expect_send_email()
+        .withf(|to, _, body| to == "test@test.com" && body.contains("/reset-password?token="))
+        .times(1)
+        .returning(move |_, _, _| {
+            sent.send(()).unwrap();
+            Ok(())
+        });
+
+    let state = Arc::new(
+        InnerState::new(state.db.clone(), state.config.clone(), Arc::new(mock_redis), Arc::new(mock_service))
+            .with_email_provider(Arc::new(mock_email)),
+    );
+
+    let payload = ForgotPasswordRequest { email: "test@test.com".into() };
+    let res = AuthHandler::forgot_password(State(state), Json(payload)).await;
+    assert_eq!(res.unwrap().into_response().status(), 200);
+
+    // The email goes out in the background after the response.
+    let sent = tokio::time::timeout(std::time::Duration::from_secs(5), sent_rx.recv()).await;
+    assert!(matches!(sent, Ok(Some(()))));
+}
+
+#[tokio::test]
+async fn test_forgot_password_unknown_email_same_response() {
+    let state = get_mock_state();
+
+    let (looked_up, mut looked_up_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_find_by_email().times(1).returning(move |_| {
+        looked_up.send(()).unwrap();
+        Ok(None)
+    });
+
+    let mut mock_email = MockEmailProvider::new();
+    mock_email.expect_send_email().never();
+
+    let state = Arc::new(
+        InnerState::new(
+            state.db.clone(),
+            state.config.clone(),
+            Arc::new(MockRedisProvider::new()),
+            Arc::new(mock_service),
+        )
+        .with_email_provider(Arc::new(mock_email)),
+    );
+
+    let payload = ForgotPasswordRequest { email: "nobody@test.com".into() };
+    let res = AuthHandler::forgot_password(State(state), Json(payload)).await;
+    assert_eq!(res.unwrap().into_response().status(), 200);
+
+    let looked_up = tokio::time::timeout(std::time::Duration::from_secs(5), looked_up_rx.recv()).await;
+    assert!(matches!(looked_up, Ok(Some(()))));
+}
+
+#[tokio::test]
+async fn test_reset_password_success_revokes_sessions() {
+    let state = get_mock_state();
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get()
+        .withf(|k| k.starts_with("one_time_token:password_reset:"))
+        .returning(|_| Ok(Some("123".into())));
+    mock_redis.expect_get_del()
+        .withf(|k| k.starts_with("one_time_token:password_reset:"))
+        .times(1)
+        .returning(|_| Ok(Some("123".into())));
+    mock_redis.expect_smembers()
+        .with(eq("user_sessions:123"))
+        .returning(|_| Ok(vec![]));
+    mock_redis.expect_set_ex()
+        .withf(|k, _, _| k == "tokens_valid_after:123")
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user().returning(|_| Ok(unverified_user()));
+    mock_service.expect_set_password()
+        .with(eq("123"), eq("new_password"))
+        .times(1)
+        .returning(|_, _| Ok(()));
+
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    let payload = ResetPasswordRequest { token: "token".into(), new_password: "new_password".into() };
+    let res = AuthHandler::reset_password(State(state), Json(payload)).await;
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn test_reset_password_invalid_token() {
+    let state = get_mock_state();
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().times(1).returning(|_| Ok(None));
+    mock_redis.expect_get_del().never();
+
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        state.user_service.clone(),
+    ));
+
+    let payload = ResetPasswordRequest { token: "used".into(), new_password: "new_password".into() };
+    let res = AuthHandler::reset_password(State(state), Json(payload)).await;
     assert!(res.is_err());
 }
+
+#[tokio::test]
+async fn test_reset_password_validation_error_keeps_token() {
+    let state = get_mock_state();
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().returning(|_| Ok(Some("123".into())));
+    mock_redis.expect_get_del().never();
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user().returning(|_| Ok(unverified_user()));
+    mock_service.expect_set_password().never();
+
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    let payload = ResetPasswordRequest { token: "token".into(), new_password: "123".into() };
+    let res = AuthHandler::reset_password(State(state), Json(payload)).await;
+    let Err(AppError::InvalidFields(errors)) = res else {
+        panic!("expected a validation error");
+    };
+    assert!(errors.field_errors().contains_key("new_password"));
+}
+
+fn unverified_user() -> UserResponse {
+    UserResponse {
+        id: "123".into(),
+        username: "test".into(),
+        email: "test@test.com".into(),
+        role: "user".into(),
+        email_verified: false,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[tokio::test]
+async fn test_verify_email_success() {
+    let state = get_mock_state();
+    let token = sign_token(&state.config.jwt_secret, "email_verification", "123:test@test.com", 60);
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user()
+        .with(eq("123"))
+        .times(1)
+        .returning(|_| Ok(unverified_user()));
+    mock_service.expect_mark_email_verified()
+        .with(eq("123"))
+        .times(1)
+        .returning(|_| Ok(()));
+
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        state.redis.clone(),
+        Arc::new(mock_service),
+    ));
+
+    let res = AuthHandler::verify_email(State(state), Query(VerifyEmailQuery { token })).await;
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn test_verify_email_rejects_changed_address() {
+    let state = get_mock_state();
+    let token = sign_token(&state.config.jwt_secret, "email_verification", "123:old@test.com", 60);
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user().returning(|_| Ok(unverified_user()));
+    mock_service.expect_mark_email_verified().never();
+
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        state.redis.clone(),
+        Arc::new(mock_service),
+    ));
+
+    let res = AuthHandler::verify_email(State(state), Query(VerifyEmailQuery { token })).await;
+    assert!(matches!(res, Err(AppError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_verify_email_rejects_forged_token() {
+    let state = get_mock_state();
+    let token = sign_token("wrong-secret", "email_verification", "123:test@test.com", 60);
+
+    let res = AuthHandler::verify_email(State(state), Query(VerifyEmailQuery { token })).await;
+    assert!(matches!(res, Err(AppError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_resend_verification_sends_email() {
+    let state = get_mock_state();
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_set_nx_ex()
+        .withf(|k, _, ttl| k.starts_with("email_verification_resend:") && *ttl == 60)
+        .times(1)
+        .returning(|_, _, _| Ok(true));
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_find_by_email()
+        .with(eq("test@test.com"))
+        .returning(|_| Ok(Some(unverified_user())));
+
+    let mut mock_email = MockEmailProvider::new();
+    mock_email.expect_send_email().times(1).returning(|_, _, _| Ok(()));
+
+    let state = Arc::new(
+        InnerState::new(state.db.clone(), state.config.clone(), Arc::new(mock_redis), Arc::new(mock_service))
+            .with_email_provider(Arc::new(mock_email)),
+    );
+
+    let payload = ResendVerificationRequest { email: "test@test.com".into() };
+    let res = AuthHandler::resend_verification(State(state), Json(payload)).await;
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn test_resend_verification_throttled() {
+    let state = get_mock_state();
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_set_nx_ex().times(1).returning(|_, _, _| Ok(false));
+
+    let mut mock_email = MockEmailProvider::new();
+    mock_email.expect_send_email().never();
+
+    let state = Arc::new(
+        InnerState::new(state.db.clone(), state.config.clone(), Arc::new(mock_redis), state.user_service.clone())
+            .with_email_provider(Arc::new(mock_email)),
+    );
+
+    let payload = ResendVerificationRequest { email: "test@test.com".into() };
+    let res = AuthHandler::resend_verification(State(state), Json(payload)).await;
+    let res = res.err().unwrap().into_response();
+    assert_eq!(res.status(), 429);
+    assert_eq!(res.headers()["retry-after"], "60");
+}
+
+fn enabled_mfa(config: &fldp_rust_backend_template::config::AppConfig, recovery_codes: Vec<String>) -> UserMfa {
+    UserMfa {
+        enabled: true,
+        secret: encrypt(&config.mfa_key().unwrap(), &[42u8; 20]).unwrap(),
+        recovery_codes,
+        last_used_step: None,
+    }
+}
+
+#[tokio::test]
+async fn test_login_with_mfa_returns_challenge() {
+    let state = get_mock_state();
+    let mfa = enabled_mfa(&state.config, vec![]);
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_authenticate().returning(move |_, _| Ok(User {
+        id: Some("123".into()),
+        username: "test".into(),
+        email: "test@test.com".into(),
+        password_hash: "hash".into(),
+        role: "user".into(),
+        email_verified: true,
+        mfa: Some(mfa.clone()),
+        identities: vec![],
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        deleted_at: None,
+    }));
+
+    let mut mock_redis = not_locked_redis();
+    // The failure counters survive until the second factor passes.
+    mock_redis.expect_del().never();
+    mock_redis.expect_set_ex()
+        .withf(|k, v, ttl| k.starts_with("one_time_token:mfa_pending:") && v == "123" && *ttl == 300)
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    let payload = LoginRequest { email: "test@test.com".into(), password: "password123".into() };
+    let res = AuthHandler::login(State(state), ClientIp("127.0.0.1".into()), UserAgent(None), Json(payload)).await.unwrap();
+    let LoginResponse::MfaRequired(challenge) = res.0 else {
+        panic!("expected an MFA challenge");
+    };
+    assert!(challenge.mfa_required);
+    assert_eq!(challenge.mfa_token.len(), 64);
+}
+
+#[tokio::test]
+async fn test_login_mfa_with_recovery_code() {
+    let state = get_mock_state();
+    let mfa = enabled_mfa(&state.config, vec![sha256_hex("aaaaabbbbb")]);
+
+    let mut mock_redis = not_locked_redis();
+    mock_redis.expect_get_del()
+        .withf(|k| k == format!("one_time_token:mfa_pending:{}", sha256_hex("pending")))
+        .times(1)
+        .returning(|_| Ok(Some("123".into())));
+    mock_redis.expect_del()
+        .withf(|k| k.starts_with("login_failures:email:"))
+        .times(1)
+        .returning(|_| Ok(()));
+    mock_redis.expect_del()
+        .with(eq("login_failures:ip:127.0.0.1"))
+        .times(1)
+        .returning(|_| Ok(()));
+    mock_redis.expect_set_ex()
+        .times(3)
+        .returning(|_, _, _| Ok(()));
+    mock_redis.expect_sadd_ex()
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_mfa().returning(move |_| Ok(Some(mfa.clone())));
+    mock_service.expect_replace_mfa()
+        .withf(|id, _, mfa| id == "123" && mfa.as_ref().unwrap().recovery_codes.is_empty())
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+    mock_service.expect_get_user().returning(|_| Ok(unverified_user()));
+
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    let payload = MfaLoginRequest { mfa_token: "pending".into(), code: "aaaaa-bbbbb".into() };
+    let res = AuthHandler::login_mfa(State(state), ClientIp("127.0.0.1".into()), UserAgent(None), Json(payload)).await.unwrap();
+    assert!(!res.token.is_empty());
+}
+
+#[tokio::test]
+async fn test_login_mfa_wrong_code() {
+    let state = get_mock_state();
+    let mfa = enabled_mfa(&state.config, vec![]);
+
+    let mut mock_redis = not_locked_redis();
+    mock_redis.expect_get_del().returning(|_| Ok(Some("123".into())));
+    mock_redis.expect_incr_ex()
+        .times(2)
+        .returning(|_, _| Ok(1));
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_mfa().returning(move |_| Ok(Some(mfa.clone())));
+    mock_service.expect_replace_mfa().never();
+    mock_service.expect_get_user().returning(|_| Ok(unverified_user()));
+
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    let payload = MfaLoginRequest { mfa_token: "pending".into(), code: "000000".into() };
+    let res = AuthHandler::login_mfa(State(state), ClientIp("127.0.0.1".into()), UserAgent(None), Json(payload)).await;
+    assert!(matches!(res, Err(AppError::AuthError)));
+}
+
+#[tokio::test]
+async fn test_login_mfa_wrong_codes_lock_out() {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    let state = get_mock_state();
+    let mfa = enabled_mfa(&state.config, vec![]);
+
+    // Redis counters and locks as a real server would keep them across attempts.
+    let failures = Arc::new(Mutex::new(HashMap::<String, i64>::new()));
+    let locks = Arc::new(Mutex::new(HashMap::<String, i64>::new()));
+
+    let mut mock_redis = MockRedisProvider::new();
+    // Each attempt starts from a fresh pending token, as after logging in with the password again.
+    mock_redis.expect_get_del().returning(|_| Ok(Some("123".into())));
+    let lock_ttls = locks.clone();
+    mock_redis.expect_ttl()
+        .returning(move |k| Ok(lock_ttls.lock().unwrap().get(k).copied().unwrap_or(-2)));
+    mock_redis.expect_incr_ex().returning(move |k, _| {
+        let mut failures = failures.lock().unwrap();
+        let count = failures.entry(k.to_string()).or_default();
+        *count += 1;
+        Ok(*count)
+    });
+    mock_redis.expect_set_ex()
+        .withf(|k, _, _| k.starts_with("login_lock:"))
+        .returning(move |k, _, ttl| {
+            locks.lock().unwrap().insert(k.to_string(), ttl as i64);
+            Ok(())
+        });
+    mock_redis.expect_del().never();
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_mfa().returning(move |_| Ok(Some(mfa.clone())));
+    mock_service.expect_get_user().returning(|_| Ok(unverified_user()));
+
+    let max_attempts = state.config.login_max_attempts;
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    for _ in 0..max_attempts {
+        let payload = MfaLoginRequest { mfa_token: "pending".into(), code: "000000".into() };
+        let res = AuthHandler::login_mfa(State(state.clone()), ClientIp("127.0.0.1".into()), UserAgent(None), Json(payload)).await;
+        assert!(matches!(res, Err(AppError::AuthError)));
+    }
+
+    let payload = MfaLoginRequest { mfa_token: "pending".into(), code: "000000".into() };
+    let res = AuthHandler::login_mfa(State(state), ClientIp("127.0.0.1".into()), UserAgent(None), Json(payload)).await;
+    assert_eq!(res.err().unwrap().into_response().status(), 429);
+}
+
+#[tokio::test]
+async fn test_login_mfa_unknown_pending_token() {
+    let state = get_mock_state();
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get_del().returning(|_| Ok(None));
+
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        state.user_service.clone(),
+    ));
+
+    let payload = MfaLoginRequest { mfa_token: "expired".into(), code: "123456".into() };
+    let res = AuthHandler::login_mfa(State(state), ClientIp("127.0.0.1".into()), UserAgent(None), Json(payload)).await;
+    assert!(matches!(res, Err(AppError::AuthError)));
+}
+
+fn magic_link_state(
+    mock_redis: MockRedisProvider,
+    mock_service: MockUserService,
+    mock_email: MockEmailProvider,
+) -> fldp_rust_backend_template::state::AppState {
+    let state = get_mock_state();
+    let mut config = state.config.clone();
+    config.magic_link_enabled = true;
+    Arc::new(
+        InnerState::new(state.db.clone(), config, Arc::new(mock_redis), Arc::new(mock_service))
+            .with_email_provider(Arc::new(mock_email)),
+    )
+}
+
+#[tokio::test]
+async fn test_magic_link_disabled_by_default() {
+    let payload = MagicLinkRequest { email: "test@test.com".into() };
+    let res = AuthHandler::request_magic_link(State(get_mock_state()), Json(payload)).await;
+    assert!(matches!(res, Err(AppError::NotFound)));
+
+    let res = AuthHandler::magic_link_callback(
+        State(get_mock_state()),
+        ClientIp("127.0.0.1".into()),
+        UserAgent(None),
+        HeaderMap::new(),
+        Query(MagicLinkQuery { token: "token".into() }),
+    )
+    .await;
+    assert!(matches!(res, Err(AppError::NotFound)));
+}
+
+#[tokio::test]
+async fn test_magic_link_request_sends_link_and_sets_nonce_cookie() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_set_nx_ex()
+        .withf(|k, _, ttl| k.starts_with("magic_link_resend:") && *ttl == 60)
+        .times(1)
+        .returning(|_, _, _| Ok(true));
+    mock_redis.expect_set_ex()
+        .withf(|k, v, ttl| k.starts_with("one_time_token:magic_link:") && v.starts_with("123:") && *ttl == 600)
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_find_by_email().returning(|_| Ok(Some(unverified_user())));
+
+    let mut mock_email = MockEmailProvider::new();
+    mock_email.expect_send_email()
+        .withf(|to, _, body| to == "test@test.com" && body.contains("/api/v1/auth/magic-link/callback?token="))
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let state = magic_link_state(mock_redis, mock_service, mock_email);
+    let payload = MagicLinkRequest { email: "test@test.com".into() };
+    let res = AuthHandler::request_magic_link(State(state), Json(payload)).await.unwrap().into_response();
+
+    assert_eq!(res.status(), 200);
+    let cookie = res.headers()["set-cookie"].to_str().unwrap();
+    assert!(cookie.starts_with("magic_link_nonce="));
+    assert!(cookie.contains("HttpOnly"));
+    assert!(cookie.contains("Max-Age=600"));
+}
+
+#[tokio::test]
+async fn test_magic_link_request_unknown_email_same_response() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_set_nx_ex().returning(|_, _, _| Ok(true));
+    mock_redis.expect_set_ex().never();
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_find_by_email().returning(|_| Ok(None));
+
+    let mut mock_email = MockEmailProvider::new();
+    mock_email.expect_send_email().never();
+
+    let state = magic_link_state(mock_redis, mock_service, mock_email);
+    let payload = MagicLinkRequest { email: "nobody@test.com".into() };
+    let res = AuthHandler::request_magic_link(State(state), Json(payload)).await.unwrap().into_response();
+
+    assert_eq!(res.status(), 200);
+    assert!(res.headers().contains_key("set-cookie"));
+}
+
+#[tokio::test]
+async fn test_magic_link_request_throttled() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_set_nx_ex().returning(|_, _, _| Ok(false));
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_find_by_email().never();
+
+    let state = magic_link_state(mock_redis, mock_service, MockEmailProvider::new());
+    let payload = MagicLinkRequest { email: "test@test.com".into() };
+    let res = AuthHandler::request_magic_link(State(state), Json(payload)).await;
+    assert!(matches!(res, Err(AppError::TooManyRequests(60))));
+}
+
+#[tokio::test]
+async fn test_magic_link_callback_signs_in_and_verifies_email() {
+    let subject = format!("123:{}", sha256_hex("nonce_1"));
+
+    let mut mock_redis = MockRedisProvider::new();
+    let stored = subject.clone();
+    mock_redis.expect_get()
+        .withf(|k| k.starts_with("one_time_token:magic_link:"))
+        .returning(move |_| Ok(Some(stored.clone())));
+    mock_redis.expect_get_del()
+        .withf(|k| k.starts_with("one_time_token:magic_link:"))
+        .times(1)
+        .returning(move |_| Ok(Some(subject.clone())));
+    mock_redis.expect_set_ex().times(3).returning(|_, _, _| Ok(()));
+    mock_redis.expect_sadd_ex().times(1).returning(|_, _, _| Ok(()));
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user().with(eq("123")).returning(|_| Ok(unverified_user()));
+    mock_service.expect_mark_email_verified().with(eq("123")).times(1).returning(|_| Ok(()));
+    mock_service.expect_get_mfa().returning(|_| Ok(None));
+
+    let mut headers = HeaderMap::new();
+    headers.insert("cookie", "theme=dark; magic_link_nonce=nonce_1".parse().unwrap());
+
+    let state = magic_link_state(mock_redis, mock_service, MockEmailProvider::new());
+    let res = AuthHandler::magic_link_callback(
+        State(state),
+        ClientIp("127.0.0.1".into()),
+        UserAgent(None),
+        headers,
+        Query(MagicLinkQuery { token: "token".into() }),
+    )
+    .await
+    .unwrap()
+    .into_response();
+
+    assert_eq!(res.status(), 200);
+    assert!(res.headers()["set-cookie"].to_str().unwrap().contains("Max-Age=0"));
+    let body = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(body["token"].is_string());
+    assert!(body["refreshToken"].is_string());
+    assert_eq!(body["user"]["emailVerified"], true);
+}
+
+#[tokio::test]
+async fn test_magic_link_callback_requires_nonce_cookie() {
+    let subject = format!("123:{}", sha256_hex("nonce_1"));
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().returning(move |_| Ok(Some(subject.clone())));
+    mock_redis.expect_get_del().never();
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user().never();
+
+    let state = magic_link_state(mock_redis, mock_service, MockEmailProvider::new());
+    let res = AuthHandler::magic_link_callback(
+        State(state),
+        ClientIp("127.0.0.1".into()),
+        UserAgent(None),
+        HeaderMap::new(),
+        Query(MagicLinkQuery { token: "token".into() }),
+    )
+    .await;
+    assert!(matches!(res, Err(AppError::ValidationError(_))));
+}
@@ -1,2 +1,5 @@
 pub mod user_handler_test;
 pub mod auth_handler_test;
+pub mod admin_handler_test;
+pub mod api_key_handler_test;
+pub mod session_handler_test;
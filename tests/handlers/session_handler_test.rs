@@ -0,0 +1,136 @@
+use fldp_rust_backend_template::handlers::session_handler::SessionHandler;
+use fldp_rust_backend_template::middlewares::auth::AuthUser;
+use fldp_rust_backend_template::mock::db_mock::MockRedisProvider;
+use fldp_rust_backend_template::mock::get_mock_state;
+use fldp_rust_backend_template::error::AppError;
+use fldp_rust_backend_template::state::{AppState, InnerState};
+use axum::extract::{State, Path};
+use axum::response::IntoResponse;
+use std::sync::Arc;
+use mockall::predicate::*;
+use chrono::Utc;
+
+fn signed_in(session_id: Option<&str>) -> AuthUser {
+    AuthUser {
+        session_id: session_id.map(Into::into),
+        ..AuthUser::new("user_1", "user", vec![])
+    }
+}
+
+fn session(id: &str, user_id: &str) -> String {
+    format!(
+        r#"{{"id":"{}","userId":"{}","ip":"1.2.3.4","userAgent":"curl/8","createdAt":"{2}","lastSeenAt":"{2}"}}"#,
+        id,
+        user_id,
+        Utc::now().to_rfc3339()
+    )
+}
+
+fn state(mock_redis: MockRedisProvider) -> AppState {
+    let state = get_mock_state();
+    Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        state.user_service.clone(),
+    ))
+}
+
+async fn body(response: impl IntoResponse) -> serde_json::Value {
+    let bytes = axum::body::to_bytes(response.into_response().into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_list_sessions_marks_current() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_smembers()
+        .with(eq("user_sessions:user_1"))
+        .returning(|_| Ok(vec!["s1".into(), "s2".into()]));
+    mock_redis.expect_get()
+        .with(eq("session:s1"))
+        .returning(|_| Ok(Some(session("s1", "user_1"))));
+    mock_redis.expect_get()
+        .with(eq("session:s2"))
+        .returning(|_| Ok(Some(session("s2", "user_1"))));
+    mock_redis.expect_get()
+        .withf(|key| key.starts_with("refresh_family:"))
+        .returning(|_| Ok(Some("user_1".into())));
+
+    let res = SessionHandler::list(State(state(mock_redis)), signed_in(Some("s2"))).await.unwrap();
+    let json = body(res).await;
+    let sessions = json["data"].as_array().unwrap();
+
+    assert_eq!(sessions.len(), 2);
+    let current: Vec<_> = sessions.iter().filter(|s| s["current"] == true).collect();
+    assert_eq!(current.len(), 1);
+    assert_eq!(current[0]["id"], "s2");
+    assert_eq!(current[0]["userAgent"], "curl/8");
+}
+
+#[tokio::test]
+async fn test_revoke_session() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get()
+        .with(eq("session:s1"))
+        .returning(|_| Ok(Some(session("s1", "user_1"))));
+    mock_redis.expect_del()
+        .with(eq("refresh_family:s1"))
+        .times(1)
+        .returning(|_| Ok(()));
+    mock_redis.expect_del()
+        .with(eq("session:s1"))
+        .times(1)
+        .returning(|_| Ok(()));
+    mock_redis.expect_srem()
+        .times(1)
+        .returning(|_, _| Ok(()));
+
+    let res = SessionHandler::revoke(State(state(mock_redis)), signed_in(Some("s2")), Path("s1".into())).await;
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn test_revoke_unknown_session() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().returning(|_| Ok(None));
+
+    let res = SessionHandler::revoke(State(state(mock_redis)), signed_in(None), Path("nope".into())).await;
+    assert!(matches!(res, Err(AppError::NotFound)));
+}
+
+#[tokio::test]
+async fn test_revoke_others() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_smembers()
+        .returning(|_| Ok(vec!["s1".into(), "s2".into(), "s3".into()]));
+    mock_redis.expect_del()
+        .withf(|key| key.ends_with("s2"))
+        .never();
+    mock_redis.expect_del()
+        .times(4)
+        .returning(|_| Ok(()));
+    mock_redis.expect_srem()
+        .times(2)
+        .returning(|_, _| Ok(()));
+
+    let res = SessionHandler::revoke_others(State(state(mock_redis)), signed_in(Some("s2"))).await.unwrap();
+    assert_eq!(body(res).await["data"]["revoked"], 2);
+}
+
+#[tokio::test]
+async fn test_revoke_others_requires_session_token() {
+    let res = SessionHandler::revoke_others(State(get_mock_state()), signed_in(None)).await;
+    assert!(matches!(res, Err(AppError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_sessions_reject_api_keys() {
+    let caller = AuthUser {
+        api_key_id: Some("key_1".into()),
+        ..AuthUser::new("user_1", "user", vec![])
+    };
+
+    let res = SessionHandler::list(State(get_mock_state()), caller).await;
+    assert!(matches!(res, Err(AppError::PermissionDenied)));
+}
@@ -1,13 +1,21 @@
 use fldp_rust_backend_template::handlers::user_handler::UserHandler;
 use fldp_rust_backend_template::mock::get_mock_state;
 use fldp_rust_backend_template::mock::services::user_service_mock::MockUserService;
-use fldp_rust_backend_template::dtos::user::{CreateUser, UserResponse, UpdateUser};
+use fldp_rust_backend_template::dtos::user::{ChangePassword, CreateUser, ListUsersQuery, UserResponse, UpdateUser};
+use fldp_rust_backend_template::error::AppError;
+use fldp_rust_backend_template::middlewares::auth::AuthUser;
+use fldp_rust_backend_template::middlewares::client_ip::{ClientIp, UserAgent};
+use fldp_rust_backend_template::middlewares::ownership::Owned;
+use fldp_rust_backend_template::mock::db_mock::MockRedisProvider;
+use fldp_rust_backend_template::mock::providers::email_provider_mock::MockEmailProvider;
 use fldp_rust_backend_template::state::InnerState;
+use fldp_rust_backend_template::models::permission::Permission;
+use axum::response::IntoResponse;
 use axum::extract::{State, Path, Query, Json};
 use std::sync::Arc;
 use mockall::predicate::*;
 use chrono::Utc;
-use fldp_rust_backend_template::utils::pagination::{PaginationParams, PaginationResult};
+use fldp_rust_backend_template::utils::pagination::{CursorPage, Pagination, PaginationParams, PaginationResult};
 
 #[tokio::test]
 async fn test_create_user_handler() {
@@ -23,6 +31,7 @@ async fn test_create_user_handler() {
         username: "test".into(),
         email: "test@test.com".into(),
         role: "user".into(),
+        email_verified: false,
         created_at: Utc::now(),
         updated_at: Utc::now(),
     };
@@ -52,6 +61,7 @@ async fn test_get_user_handler() {
         username: "test".into(),
         email: "test@test.com".into(),
         role: "user".into(),
+        email_verified: false,
         created_at: Utc::now(),
         updated_at: Utc::now(),
     };
@@ -69,10 +79,14 @@ async fn test_get_user_handler() {
         Arc::new(mock_service),
     ));
 
-    let res = UserHandler::get_user(State(state), Path("123".into())).await;
+    let res = UserHandler::get_user(State(state), Owned::new("123")).await;
     assert!(res.is_ok());
 }
 
+fn pagination(params: PaginationParams) -> Pagination {
+    Pagination::from_params(params, &"/api/v1/users".parse().unwrap(), 10, 100).unwrap()
+}
+
 #[tokio::test]
 async fn test_list_users_handler() {
     let mut mock_service = MockUserService::new();
@@ -86,7 +100,7 @@ async fn test_list_users_handler() {
 
     mock_service.expect_list_users()
         .times(1)
-        .returning(move |_, _| Ok(result.clone()));
+        .returning(move |_, _, _| Ok(result.clone()));
 
     let state = get_mock_state();
     let state = Arc::new(InnerState::new(
@@ -96,11 +110,44 @@ async fn test_list_users_handler() {
         Arc::new(mock_service),
     ));
 
-    let params = PaginationParams { page: Some(1), limit: Some(10) };
-    let res = UserHandler::list_users(State(state), Query(params)).await;
+    let params = PaginationParams { page: Some(1), limit: Some(10), cursor: None };
+    let res = UserHandler::list_users(State(state), pagination(params), Query(ListUsersQuery::default())).await;
     assert!(res.is_ok());
 }
 
+#[tokio::test]
+async fn test_list_users_handler_rejects_unsorted_field() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_list_users().never();
+
+    let params = PaginationParams { page: None, limit: None, cursor: None };
+    let query = ListUsersQuery { sort: Some("passwordHash:asc".into()), ..Default::default() };
+    let res = UserHandler::list_users(State(state_with_service(mock_service)), pagination(params), Query(query)).await;
+    assert!(matches!(res, Err(AppError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_list_users_handler_cursor_mode() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_list_users().never();
+    mock_service.expect_list_users_after()
+        .withf(|_, cursor, limit| cursor.as_deref() == Some("") && *limit == Some(20))
+        .times(1)
+        .returning(|_, _, limit| Ok(CursorPage::new(vec![], limit.unwrap(), None)));
+
+    let params = PaginationParams { page: None, limit: Some(20), cursor: Some(String::new()) };
+    let res = UserHandler::list_users(State(state_with_service(mock_service)), pagination(params), Query(ListUsersQuery::default()))
+        .await
+        .unwrap();
+
+    let res = res.into_response();
+    assert_eq!(res.headers()["link"], "</api/v1/users?cursor=&limit=20>; rel=\"first\"");
+    let body = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["data"]["nextCursor"], serde_json::Value::Null);
+    assert!(body["data"].get("total").is_none());
+}
+
 #[tokio::test]
 async fn test_update_user_handler() {
     let mut mock_service = MockUserService::new();
@@ -117,7 +164,7 @@ async fn test_update_user_handler() {
     ));
 
     let input = UpdateUser { username: Some("newname".into()), email: None };
-    let res = UserHandler::update_user(State(state), Path("123".into()), Json(input)).await;
+    let res = UserHandler::update_user(State(state), AuthUser::new("admin_1", "admin", vec![]), Owned::new("123"), Json(input)).await;
     assert!(res.is_ok());
 }
 
@@ -136,7 +183,7 @@ async fn test_get_user_not_found_handler() {
         Arc::new(mock_service),
     ));
 
-    let res = UserHandler::get_user(State(state), Path("nonexistent".into())).await;
+    let res = UserHandler::get_user(State(state), Owned::new("nonexistent")).await;
     assert!(res.is_err());
 }
 
@@ -155,7 +202,7 @@ async fn test_update_user_handler_fail() {
     ));
 
     let input = UpdateUser { username: Some("newname".into()), email: None };
-    let res = UserHandler::update_user(State(state), Path("123".into()), Json(input)).await;
+    let res = UserHandler::update_user(State(state), AuthUser::new("admin_1", "admin", vec![]), Owned::new("123"), Json(input)).await;
     assert!(res.is_err());
 }
 
@@ -163,7 +210,7 @@ async fn test_update_user_handler_fail() {
 async fn test_list_users_handler_fail() {
     let mut mock_service = MockUserService::new();
     mock_service.expect_list_users()
-        .returning(|_, _| Err(fldp_rust_backend_template::error::AppError::InternalServerError));
+        .returning(|_, _, _| Err(fldp_rust_backend_template::error::AppError::InternalServerError));
 
     let state = get_mock_state();
     let state = Arc::new(InnerState::new(
@@ -173,8 +220,8 @@ async fn test_list_users_handler_fail() {
         Arc::new(mock_service),
     ));
 
-    let params = PaginationParams { page: Some(1), limit: Some(10) };
-    let res = UserHandler::list_users(State(state), Query(params)).await;
+    let params = PaginationParams { page: Some(1), limit: Some(10), cursor: None };
+    let res = UserHandler::list_users(State(state), pagination(params), Query(ListUsersQuery::default())).await;
     assert!(res.is_err());
 }
 
@@ -198,6 +245,410 @@ async fn test_update_user_validation_error() {
         username: Some("u".to_string()), // too short
         email: None,
     };
-    let res = UserHandler::update_user(State(state), Path("123".into()), Json(payload)).await;
+    let res = UserHandler::update_user(State(state), AuthUser::new("admin_1", "admin", vec![]), Owned::new("123"), Json(payload)).await;
     assert!(res.is_err());
 }
+
+fn account(id: &str) -> UserResponse {
+    UserResponse {
+        id: id.to_string(),
+        username: "test".into(),
+        email: "test@test.com".into(),
+        role: "user".into(),
+        email_verified: true,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn change_password_input() -> ChangePassword {
+    ChangePassword {
+        current_password: "old_password".into(),
+        new_password: "new_password".into(),
+    }
+}
+
+#[tokio::test]
+async fn test_change_password_handler_signs_out_everywhere() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user().returning(|id| Ok(account(id)));
+    mock_service.expect_change_password()
+        .withf(|id, input| id == "123" && input.new_password == "new_password")
+        .times(1)
+        .returning(|id, _| Ok(account(id)));
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_ttl()
+        .withf(|k| k.starts_with("login_lock:"))
+        .returning(|_| Ok(-2));
+    mock_redis.expect_del()
+        .withf(|k| k.starts_with("login_failures:email:"))
+        .times(1)
+        .returning(|_| Ok(()));
+    mock_redis.expect_del()
+        .with(eq("login_failures:ip:127.0.0.1"))
+        .times(1)
+        .returning(|_| Ok(()));
+    mock_redis.expect_smembers()
+        .with(eq("user_sessions:123"))
+        .returning(|_| Ok(vec!["old_session".into()]));
+    mock_redis.expect_del().times(2).returning(|_| Ok(()));
+    mock_redis.expect_srem().times(1).returning(|_, _| Ok(()));
+    mock_redis.expect_set_ex()
+        .withf(|key, _, _| key == "tokens_valid_after:123")
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+    mock_redis.expect_set_ex()
+        .times(3)
+        .returning(|_, _, _| Ok(()));
+    mock_redis.expect_sadd_ex().times(1).returning(|_, _, _| Ok(()));
+
+    let mut mock_email = MockEmailProvider::new();
+    mock_email.expect_send_email()
+        .withf(|to, subject, _| to == "test@test.com" && subject == "Your password was changed")
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let state = get_mock_state();
+    let state = Arc::new(
+        InnerState::new(state.db.clone(), state.config.clone(), Arc::new(mock_redis), Arc::new(mock_service))
+            .with_email_provider(Arc::new(mock_email)),
+    );
+
+    let res = UserHandler::change_password(
+        State(state),
+        AuthUser::new("123", "user", vec![]),
+        ClientIp("127.0.0.1".into()),
+        UserAgent(None),
+        Json(change_password_input()),
+    )
+    .await;
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn test_change_password_handler_wrong_current_password() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user().returning(|id| Ok(account(id)));
+    mock_service.expect_change_password()
+        .returning(|_, _| Err(AppError::InvalidCredentials));
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_ttl().returning(|_| Ok(-2));
+    mock_redis.expect_incr_ex()
+        .withf(|k, _| k.starts_with("login_failures:email:") || k == "login_failures:ip:127.0.0.1")
+        .times(2)
+        .returning(|_, _| Ok(1));
+
+    let state = get_mock_state();
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    let res = UserHandler::change_password(
+        State(state),
+        AuthUser::new("123", "user", vec![]),
+        ClientIp("127.0.0.1".into()),
+        UserAgent(None),
+        Json(change_password_input()),
+    )
+    .await;
+    assert!(matches!(res, Err(AppError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_change_password_handler_locked_out() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user().returning(|id| Ok(account(id)));
+    mock_service.expect_change_password().never();
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_ttl()
+        .withf(|k| k.starts_with("login_lock:email:"))
+        .returning(|_| Ok(60));
+    mock_redis.expect_ttl().returning(|_| Ok(-2));
+
+    let state = get_mock_state();
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    let res = UserHandler::change_password(
+        State(state),
+        AuthUser::new("123", "user", vec![]),
+        ClientIp("127.0.0.1".into()),
+        UserAgent(None),
+        Json(change_password_input()),
+    )
+    .await;
+    assert_eq!(res.err().unwrap().into_response().status(), 429);
+}
+
+#[tokio::test]
+async fn test_change_password_handler_rejects_impersonation() {
+    let caller = AuthUser {
+        actor_id: Some("admin_1".into()),
+        ..AuthUser::new("123", "user", vec![])
+    };
+
+    let res = UserHandler::change_password(
+        State(get_mock_state()),
+        caller,
+        ClientIp("127.0.0.1".into()),
+        UserAgent(None),
+        Json(change_password_input()),
+    )
+    .await;
+    assert!(matches!(res, Err(AppError::PermissionDenied)));
+}
+
+#[tokio::test]
+async fn test_change_password_handler_rejects_api_key() {
+    let caller = AuthUser {
+        api_key_id: Some("key_1".into()),
+        ..AuthUser::new("123", "user", vec![])
+    };
+
+    let res = UserHandler::change_password(
+        State(get_mock_state()),
+        caller,
+        ClientIp("127.0.0.1".into()),
+        UserAgent(None),
+        Json(change_password_input()),
+    )
+    .await;
+    assert!(matches!(res, Err(AppError::PermissionDenied)));
+}
+
+async fn change_password_rejected_by_policy(new_password: &str) -> Vec<String> {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user().returning(|id| Ok(account(id)));
+    mock_service.expect_change_password().never();
+
+    let state = get_mock_state();
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        state.redis.clone(),
+        Arc::new(mock_service),
+    ));
+    let input = ChangePassword {
+        current_password: "old_password".into(),
+        new_password: new_password.into(),
+    };
+
+    let res = UserHandler::change_password(
+        State(state),
+        AuthUser::new("123", "user", vec![]),
+        ClientIp("127.0.0.1".into()),
+        UserAgent(None),
+        Json(input),
+    )
+    .await;
+    match res {
+        Err(AppError::InvalidFields(errors)) => errors.field_errors()["new_password"]
+            .iter()
+            .map(|e| e.code.to_string())
+            .collect(),
+        _ => panic!("expected a validation error"),
+    }
+}
+
+#[tokio::test]
+async fn test_change_password_handler_validation_error() {
+    let codes = change_password_rejected_by_policy("short").await;
+    assert!(codes.contains(&"password_length".to_string()), "{:?}", codes);
+}
+
+#[tokio::test]
+async fn test_change_password_handler_rejects_personal_info() {
+    let codes = change_password_rejected_by_policy("my-test-password").await;
+    assert!(codes.contains(&"password_personal_info".to_string()), "{:?}", codes);
+}
+
+#[tokio::test]
+async fn test_delete_user_handler_signs_user_out() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_delete_user()
+        .with(eq("123"))
+        .times(1)
+        .returning(|_| Ok(()));
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_smembers()
+        .with(eq("user_sessions:123"))
+        .returning(|_| Ok(vec![]));
+    mock_redis.expect_set_ex()
+        .withf(|key, _, _| key == "tokens_valid_after:123")
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let state = get_mock_state();
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    let res = UserHandler::delete_user(State(state), Path("123".into())).await;
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn test_delete_user_handler_not_found() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_delete_user().returning(|_| Err(AppError::NotFound));
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_set_ex().never();
+
+    let state = get_mock_state();
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    let res = UserHandler::delete_user(State(state), Path("123".into())).await;
+    assert!(matches!(res, Err(AppError::NotFound)));
+}
+
+fn state_with_service(mock_service: MockUserService) -> fldp_rust_backend_template::state::AppState {
+    let state = get_mock_state();
+    Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        state.redis.clone(),
+        Arc::new(mock_service),
+    ))
+}
+
+#[tokio::test]
+async fn test_get_me_returns_profile() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_get_user().with(eq("123")).returning(|id| Ok(account(id)));
+    mock_service.expect_get_mfa().with(eq("123")).returning(|_| Ok(None));
+
+    let caller = AuthUser {
+        actor_id: Some("admin_1".into()),
+        ..AuthUser::new("123", "user", vec![Permission::UsersRead])
+    };
+    let res = UserHandler::get_me(State(state_with_service(mock_service)), caller)
+        .await
+        .unwrap()
+        .into_response();
+
+    let body = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["data"]["id"], "123");
+    assert_eq!(body["data"]["email"], "test@test.com");
+    assert_eq!(body["data"]["mfaEnabled"], false);
+    assert_eq!(body["data"]["permissions"], serde_json::json!(["users:read"]));
+    assert_eq!(body["data"]["impersonatedBy"], "admin_1");
+}
+
+#[tokio::test]
+async fn test_update_me_updates_caller() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_update_user()
+        .withf(|id, input| id == "123" && input.username.as_deref() == Some("renamed"))
+        .times(1)
+        .returning(|_, _| Ok(()));
+    mock_service.expect_get_user().returning(|id| Ok(account(id)));
+    mock_service.expect_get_mfa().returning(|_| Ok(None));
+
+    let input = UpdateUser { username: Some("renamed".into()), email: None };
+    let caller = AuthUser::new("123", "user", vec![]);
+    let res = UserHandler::update_me(State(state_with_service(mock_service)), caller, Json(input)).await;
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn test_update_me_email_change_rejects_impersonation() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_update_user().never();
+
+    let input = UpdateUser { username: None, email: Some("new@test.com".into()) };
+    let caller = AuthUser {
+        actor_id: Some("admin_1".into()),
+        ..AuthUser::new("123", "user", vec![])
+    };
+    let res = UserHandler::update_me(State(state_with_service(mock_service)), caller, Json(input)).await;
+    assert!(matches!(res, Err(AppError::PermissionDenied)));
+}
+
+#[tokio::test]
+async fn test_update_user_email_change_rejects_api_key() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_update_user().never();
+
+    let input = UpdateUser { username: None, email: Some("new@test.com".into()) };
+    let caller = AuthUser {
+        api_key_id: Some("key_1".into()),
+        ..AuthUser::new("admin_1", "admin", vec![Permission::UsersAdmin])
+    };
+    let res = UserHandler::update_user(
+        State(state_with_service(mock_service)),
+        caller,
+        Owned::new("123"),
+        Json(input),
+    )
+    .await;
+    assert!(matches!(res, Err(AppError::PermissionDenied)));
+}
+
+#[tokio::test]
+async fn test_delete_me_deletes_caller() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_delete_user()
+        .with(eq("123"))
+        .times(1)
+        .returning(|_| Ok(()));
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_smembers().returning(|_| Ok(vec![]));
+    mock_redis.expect_set_ex()
+        .withf(|key, _, _| key == "tokens_valid_after:123")
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let state = get_mock_state();
+    let state = Arc::new(InnerState::new(
+        state.db.clone(),
+        state.config.clone(),
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    let res = UserHandler::delete_me(State(state), AuthUser::new("123", "user", vec![])).await;
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn test_delete_me_rejects_api_key_and_impersonation() {
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_delete_user().never();
+    let state = state_with_service(mock_service);
+
+    let api_key = AuthUser {
+        api_key_id: Some("key_1".into()),
+        ..AuthUser::new("123", "user", vec![])
+    };
+    let res = UserHandler::delete_me(State(state.clone()), api_key).await;
+    assert!(matches!(res, Err(AppError::PermissionDenied)));
+
+    let impersonated = AuthUser {
+        actor_id: Some("admin_1".into()),
+        ..AuthUser::new("123", "user", vec![])
+    };
+    let res = UserHandler::delete_me(State(state), impersonated).await;
+    assert!(matches!(res, Err(AppError::PermissionDenied)));
+}
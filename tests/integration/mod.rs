@@ -1 +1,2 @@
 pub mod route_test;
+pub mod oidc_test;
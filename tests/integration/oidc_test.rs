@@ -0,0 +1,331 @@
+use fldp_rust_backend_template::config::{JwtKeyConfig, OidcProviderConfig};
+use fldp_rust_backend_template::dtos::user::ExternalProfile;
+use fldp_rust_backend_template::error::AppError;
+use fldp_rust_backend_template::handlers::auth_handler::{AuthHandler, LoginResponse, OidcCallbackRequest};
+use fldp_rust_backend_template::middlewares::client_ip::{ClientIp, UserAgent};
+use fldp_rust_backend_template::mock::db_mock::MockRedisProvider;
+use fldp_rust_backend_template::mock::get_mock_state;
+use fldp_rust_backend_template::mock::services::user_service_mock::MockUserService;
+use fldp_rust_backend_template::models::user::User;
+use fldp_rust_backend_template::state::{AppState, InnerState};
+use fldp_rust_backend_template::utils::jwt;
+use axum::extract::{Path, State};
+use axum::{routing::{get, post}, Form, Json, Router};
+use axum::http::StatusCode;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+const RSA_PRIVATE: &str = include_str!("../fixtures/keys/rsa_private.pem");
+const RSA_PUBLIC: &str = include_str!("../fixtures/keys/rsa_public.pem");
+const CLIENT_ID: &str = "client";
+
+/// What the mock provider remembers about an authorization code it handed out.
+#[derive(Clone)]
+struct IssuedCode {
+    code_challenge: String,
+    nonce: String,
+}
+
+/// A minimal OpenID provider: discovery, JWKS and a token endpoint that checks PKCE.
+#[derive(Clone)]
+struct MockProvider {
+    issuer: String,
+    codes: Arc<Mutex<HashMap<String, IssuedCode>>>,
+    /// Id of the provider's current signing key.
+    kid: Arc<Mutex<String>>,
+    /// Requests per path, to see what was served from our cache.
+    hits: Arc<Mutex<HashMap<&'static str, usize>>>,
+}
+
+impl MockProvider {
+    async fn start() -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let provider = Self {
+            issuer: format!("http://{}", listener.local_addr().unwrap()),
+            codes: Arc::default(),
+            kid: Arc::new(Mutex::new("provider-key".into())),
+            hits: Arc::default(),
+        };
+
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/jwks", get(jwks))
+            .route("/token", post(token))
+            .with_state(provider.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        provider
+    }
+
+    /// Plays the part of the user approving the sign-in at the provider.
+    fn approve(&self, code: &str, code_challenge: &str, nonce: &str) {
+        self.codes.lock().unwrap().insert(
+            code.into(),
+            IssuedCode { code_challenge: code_challenge.into(), nonce: nonce.into() },
+        );
+    }
+
+    /// Starts signing with a new key id, as after a key rotation.
+    fn rotate_key(&self, kid: &str) {
+        *self.kid.lock().unwrap() = kid.into();
+    }
+
+    fn hit(&self, path: &'static str) {
+        *self.hits.lock().unwrap().entry(path).or_default() += 1;
+    }
+
+    fn hits(&self, path: &'static str) -> usize {
+        self.hits.lock().unwrap().get(path).copied().unwrap_or_default()
+    }
+}
+
+async fn discovery(State(provider): State<MockProvider>) -> Json<Value> {
+    provider.hit("discovery");
+    Json(json!({
+        "issuer": provider.issuer,
+        "authorization_endpoint": format!("{}/authorize", provider.issuer),
+        "token_endpoint": format!("{}/token", provider.issuer),
+        "jwks_uri": format!("{}/jwks", provider.issuer),
+    }))
+}
+
+async fn jwks(State(provider): State<MockProvider>) -> Json<Value> {
+    provider.hit("jwks");
+    let mut config = get_mock_state().config.clone();
+    config.jwt_keys = vec![JwtKeyConfig {
+        kid: provider.kid.lock().unwrap().clone(),
+        algorithm: Algorithm::RS256,
+        private_key_pem: None,
+        private_key_file: None,
+        public_key_pem: Some(RSA_PUBLIC.into()),
+        public_key_file: None,
+    }];
+    Json(serde_json::to_value(jwt::jwks(&config).unwrap()).unwrap())
+}
+
+async fn token(
+    State(provider): State<MockProvider>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Result<Json<Value>, StatusCode> {
+    let issued = provider
+        .codes
+        .lock()
+        .unwrap()
+        .remove(&form["code"])
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(form["code_verifier"].as_bytes()));
+    if challenge != issued.code_challenge || form["client_id"] != CLIENT_ID {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let claims = json!({
+        "iss": provider.issuer,
+        "aud": CLIENT_ID,
+        "sub": "provider_user_1",
+        "exp": Utc::now().timestamp() + 300,
+        "iat": Utc::now().timestamp(),
+        "nonce": issued.nonce,
+        "email": "oidc@test.com",
+        "email_verified": true,
+        "name": "Oidc User",
+    });
+    let mut header = Header::new(Algorithm::RS256);
+    header.kid = Some(provider.kid.lock().unwrap().clone());
+    let id_token = encode(&header, &claims, &EncodingKey::from_rsa_pem(RSA_PRIVATE.as_bytes()).unwrap()).unwrap();
+
+    Ok(Json(json!({ "access_token": "opaque", "token_type": "Bearer", "id_token": id_token })))
+}
+
+/// Redis stand-in that actually stores values, so state written by one call is read by the next.
+fn memory_redis() -> MockRedisProvider {
+    let store: Arc<Mutex<HashMap<String, String>>> = Arc::default();
+    let mut redis = MockRedisProvider::new();
+
+    let values = store.clone();
+    redis.expect_set_ex().returning(move |key, value, _| {
+        values.lock().unwrap().insert(key.into(), value.into());
+        Ok(())
+    });
+    let values = store.clone();
+    redis.expect_get().returning(move |key| Ok(values.lock().unwrap().get(key).cloned()));
+    redis.expect_get_del().returning(move |key| Ok(store.lock().unwrap().remove(key)));
+    redis.expect_sadd_ex().returning(|_, _, _| Ok(()));
+    redis
+}
+
+fn oidc_user(profile: &ExternalProfile) -> User {
+    User {
+        id: Some("user_1".into()),
+        username: profile.name.clone().unwrap_or_default(),
+        email: profile.email.clone().unwrap_or_default(),
+        password_hash: "hash".into(),
+        role: "user".into(),
+        email_verified: true,
+        mfa: None,
+        identities: vec![],
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        deleted_at: None,
+    }
+}
+
+fn state(provider: &MockProvider, users: MockUserService) -> AppState {
+    let mut config = get_mock_state().config.clone();
+    config.oidc_providers.insert(
+        "mock".into(),
+        OidcProviderConfig {
+            issuer: provider.issuer.clone(),
+            client_id: CLIENT_ID.into(),
+            client_secret: Some("client-secret".into()),
+            redirect_uri: "http://localhost:5173/oidc/callback".into(),
+            scopes: vec!["openid".into(), "email".into()],
+        },
+    );
+
+    Arc::new(InnerState::new(
+        get_mock_state().db.clone(),
+        config,
+        Arc::new(memory_redis()),
+        Arc::new(users),
+    ))
+}
+
+/// Starts a sign-in and returns the query parameters of the authorization URL.
+async fn authorize(state: &AppState) -> HashMap<String, String> {
+    let Json(response) = AuthHandler::oidc_authorize(State(state.clone()), Path("mock".into()))
+        .await
+        .unwrap();
+    let url = reqwest::Url::parse(&response.authorization_url).unwrap();
+    assert!(url.path().ends_with("/authorize"));
+    url.query_pairs().into_owned().collect()
+}
+
+async fn callback(state: &AppState, code: &str, oidc_state: &str) -> Result<LoginResponse, AppError> {
+    AuthHandler::oidc_callback(
+        State(state.clone()),
+        Path("mock".into()),
+        ClientIp("127.0.0.1".into()),
+        UserAgent(Some("integration-test".into())),
+        Json(OidcCallbackRequest { code: code.into(), state: oidc_state.into() }),
+    )
+    .await
+    .map(|Json(response)| response)
+}
+
+#[tokio::test]
+async fn test_oidc_login_flow() {
+    let provider = MockProvider::start().await;
+
+    let mut users = MockUserService::new();
+    users.expect_find_or_create_external()
+        .withf(|profile| {
+            profile.provider == "mock"
+                && profile.subject == "provider_user_1"
+                && profile.email.as_deref() == Some("oidc@test.com")
+                && profile.email_verified
+        })
+        .times(1)
+        .returning(|profile| Ok(oidc_user(&profile)));
+    let state = state(&provider, users);
+
+    let params = authorize(&state).await;
+    assert_eq!(params["client_id"], CLIENT_ID);
+    assert_eq!(params["code_challenge_method"], "S256");
+    assert_eq!(params["scope"], "openid email");
+    provider.approve("code_1", &params["code_challenge"], &params["nonce"]);
+
+    let response = callback(&state, "code_1", &params["state"]).await.unwrap();
+    let LoginResponse::Authenticated(auth) = response else {
+        panic!("expected tokens");
+    };
+    assert_eq!(auth.user.email, "oidc@test.com");
+    assert_eq!(jwt::decode_token(state.jwt_keys().unwrap(), &auth.token).unwrap().sub, "user_1");
+}
+
+#[tokio::test]
+async fn test_oidc_state_is_single_use() {
+    let provider = MockProvider::start().await;
+
+    let mut users = MockUserService::new();
+    users.expect_find_or_create_external()
+        .times(1)
+        .returning(|profile| Ok(oidc_user(&profile)));
+    let state = state(&provider, users);
+
+    let params = authorize(&state).await;
+    provider.approve("code_1", &params["code_challenge"], &params["nonce"]);
+    provider.approve("code_2", &params["code_challenge"], &params["nonce"]);
+
+    assert!(callback(&state, "code_1", &params["state"]).await.is_ok());
+    let replay = callback(&state, "code_2", &params["state"]).await;
+    assert!(matches!(replay, Err(AppError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_oidc_rejects_wrong_nonce() {
+    let provider = MockProvider::start().await;
+
+    let mut users = MockUserService::new();
+    users.expect_find_or_create_external().never();
+    let state = state(&provider, users);
+
+    let params = authorize(&state).await;
+    provider.approve("code_1", &params["code_challenge"], "someone-elses-nonce");
+
+    let result = callback(&state, "code_1", &params["state"]).await;
+    assert!(matches!(result, Err(AppError::AuthError)));
+}
+
+#[tokio::test]
+async fn test_oidc_rejects_failed_pkce_exchange() {
+    let provider = MockProvider::start().await;
+
+    let mut users = MockUserService::new();
+    users.expect_find_or_create_external().never();
+    let state = state(&provider, users);
+
+    let params = authorize(&state).await;
+    provider.approve("code_1", "challenge-of-another-request", &params["nonce"]);
+
+    let result = callback(&state, "code_1", &params["state"]).await;
+    assert!(matches!(result, Err(AppError::AuthError)));
+}
+
+#[tokio::test]
+async fn test_oidc_caches_discovery_and_jwks() {
+    let provider = MockProvider::start().await;
+
+    let mut users = MockUserService::new();
+    users.expect_find_or_create_external()
+        .times(3)
+        .returning(|profile| Ok(oidc_user(&profile)));
+    let state = state(&provider, users);
+
+    for code in ["code_1", "code_2"] {
+        let params = authorize(&state).await;
+        provider.approve(code, &params["code_challenge"], &params["nonce"]);
+        assert!(callback(&state, code, &params["state"]).await.is_ok());
+    }
+    assert_eq!(provider.hits("discovery"), 1);
+    assert_eq!(provider.hits("jwks"), 1);
+
+    // A token signed with a key we have not seen yet makes us fetch the JWKS again.
+    provider.rotate_key("provider-key-2");
+    let params = authorize(&state).await;
+    provider.approve("code_3", &params["code_challenge"], &params["nonce"]);
+    assert!(callback(&state, "code_3", &params["state"]).await.is_ok());
+    assert_eq!(provider.hits("discovery"), 1);
+    assert_eq!(provider.hits("jwks"), 2);
+}
+
+#[tokio::test]
+async fn test_oidc_unknown_provider() {
+    let result = AuthHandler::oidc_authorize(State(get_mock_state()), Path("unknown".into())).await;
+    assert!(matches!(result, Err(AppError::NotFound)));
+}
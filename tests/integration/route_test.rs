@@ -94,6 +94,493 @@ async fn test_api_v1_user_list_route_unauthorized() {
     assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 }
 
+fn bearer_token(config: &AppConfig, sub: &str, role: &str) -> String {
+    use fldp_rust_backend_template::handlers::auth_handler::Claims;
+    use fldp_rust_backend_template::utils::jwt;
+
+    let claims = Claims::new(config, sub.into(), role.into());
+    format!("Bearer {}", jwt::encode_token(&jwt::JwtKeys::new(config).unwrap(), &claims).unwrap())
+}
+
+#[tokio::test]
+async fn test_api_v1_user_list_route_forged_token() {
+    let state = Arc::new(InnerState::new(
+        Arc::new(MockMongoProvider::new()),
+        get_mock_config(),
+        Arc::new(MockRedisProvider::new()),
+        Arc::new(MockUserService::new()),
+    ));
+
+    let app = init_routes(state.clone()).with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/users")
+                .header("Authorization", "Bearer anything-goes")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_api_v1_user_get_route_authorized() {
+    use fldp_rust_backend_template::dtos::user::UserResponse;
+
+    let mut mock_user_service = MockUserService::new();
+    mock_user_service.expect_get_user()
+        .with(eq("123"))
+        .returning(|id| Ok(UserResponse {
+            id: id.to_string(),
+            username: "test".to_string(),
+            email: "test@test.com".to_string(),
+            role: "user".to_string(),
+            email_verified: false,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }));
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().returning(|_| Ok(None));
+
+    let config = get_mock_config();
+    let token = bearer_token(&config, "123", "user");
+    let state = Arc::new(InnerState::new(
+        Arc::new(MockMongoProvider::new()),
+        config,
+        Arc::new(mock_redis),
+        Arc::new(mock_user_service),
+    ));
+
+    let app = init_routes(state.clone()).with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/users/123")
+                .header("Authorization", token)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_api_v1_auth_logout_route() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().returning(|_| Ok(None));
+    mock_redis.expect_set_ex()
+        .withf(|key, _, _| key.starts_with("revoked_jti:"))
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let config = get_mock_config();
+    let token = bearer_token(&config, "123", "user");
+    let state = Arc::new(InnerState::new(
+        Arc::new(MockMongoProvider::new()),
+        config,
+        Arc::new(mock_redis),
+        Arc::new(MockUserService::new()),
+    ));
+
+    let app = init_routes(state.clone()).with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/logout")
+                .header("Authorization", token)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_api_v1_auth_sessions_route() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().returning(|_| Ok(None));
+    mock_redis.expect_smembers()
+        .with(eq("user_sessions:123"))
+        .returning(|_| Ok(vec![]));
+
+    let config = get_mock_config();
+    let token = bearer_token(&config, "123", "user");
+    let state = Arc::new(InnerState::new(
+        Arc::new(MockMongoProvider::new()),
+        config,
+        Arc::new(mock_redis),
+        Arc::new(MockUserService::new()),
+    ));
+
+    let app = init_routes(state.clone()).with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/auth/sessions")
+                .header("Authorization", token)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_api_v1_admin_revoke_sessions_forbidden_for_user() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().returning(|_| Ok(None));
+
+    let config = get_mock_config();
+    let token = bearer_token(&config, "123", "user");
+    let state = Arc::new(InnerState::new(
+        Arc::new(MockMongoProvider::new()),
+        config,
+        Arc::new(mock_redis),
+        Arc::new(MockUserService::new()),
+    ));
+
+    let app = init_routes(state.clone()).with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/admin/users/456/revoke-sessions")
+                .header("Authorization", token)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_api_v1_delete_user_requires_admin_permission() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().returning(|_| Ok(None));
+
+    let mut mock_service = MockUserService::new();
+    mock_service.expect_delete_user().never();
+
+    let config = get_mock_config();
+    let token = bearer_token(&config, "123", "user");
+    let state = Arc::new(InnerState::new(
+        Arc::new(MockMongoProvider::new()),
+        config,
+        Arc::new(mock_redis),
+        Arc::new(mock_service),
+    ));
+
+    let app = init_routes(state.clone()).with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri("/api/v1/users/456")
+                .header("Authorization", token)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_api_v1_admin_impersonate_forbidden_for_user() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().returning(|_| Ok(None));
+
+    let config = get_mock_config();
+    let token = bearer_token(&config, "123", "user");
+    let state = Arc::new(InnerState::new(
+        Arc::new(MockMongoProvider::new()),
+        config,
+        Arc::new(mock_redis),
+        Arc::new(MockUserService::new()),
+    ));
+
+    let app = init_routes(state.clone()).with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/admin/users/456/impersonate")
+                .header("Authorization", token)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_api_v1_user_list_route_requires_admin() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().returning(|_| Ok(None));
+
+    let config = get_mock_config();
+    let token = bearer_token(&config, "123", "user");
+    let state = Arc::new(InnerState::new(
+        Arc::new(MockMongoProvider::new()),
+        config,
+        Arc::new(mock_redis),
+        Arc::new(MockUserService::new()),
+    ));
+
+    let app = init_routes(state.clone()).with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/users")
+                .header("Authorization", token)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["details"]["missingPermission"], "users:admin");
+}
+
+#[tokio::test]
+async fn test_api_v1_user_list_route_admin() {
+    use fldp_rust_backend_template::models::user::{Role, UserSortField};
+    use fldp_rust_backend_template::utils::pagination::PaginationResult;
+
+    let mut mock_user_service = MockUserService::new();
+    mock_user_service.expect_list_users()
+        .withf(|filter, page, limit| {
+            filter.role == Some(Role::User)
+                && filter.search.as_deref() == Some("al")
+                && filter.sort.field == UserSortField::Username
+                && *page == Some(1)
+                && *limit == Some(10)
+        })
+        .times(1)
+        .returning(|_, _, _| Ok(PaginationResult::new(vec![], 1, 10, 0)));
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().returning(|_| Ok(None));
+
+    let config = get_mock_config();
+    let token = bearer_token(&config, "1", "admin");
+    let state = Arc::new(InnerState::new(
+        Arc::new(MockMongoProvider::new()),
+        config,
+        Arc::new(mock_redis),
+        Arc::new(mock_user_service),
+    ));
+
+    let app = init_routes(state.clone()).with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/users?page=1&limit=10&role=user&q=al&sort=username:asc")
+                .header("Authorization", token)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let link = response.headers()["link"].to_str().unwrap();
+    assert!(link.starts_with("</api/v1/users?role=user&q=al&sort=username:asc&page=1&limit=10>; rel=\"first\""));
+}
+
+#[tokio::test]
+async fn test_api_v1_user_list_route_rejects_bad_pagination() {
+    let mut mock_user_service = MockUserService::new();
+    mock_user_service.expect_list_users().never();
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().returning(|_| Ok(None));
+
+    let config = get_mock_config();
+    let token = bearer_token(&config, "1", "admin");
+    let state = Arc::new(InnerState::new(
+        Arc::new(MockMongoProvider::new()),
+        config,
+        Arc::new(mock_redis),
+        Arc::new(mock_user_service),
+    ));
+
+    let app = init_routes(state.clone()).with_state(state);
+
+    for (uri, field, code) in [
+        ("/api/v1/users?page=0", "page", "range"),
+        ("/api/v1/users?limit=0", "limit", "range"),
+        ("/api/v1/users?limit=1000", "limit", "range"),
+        ("/api/v1/users?page=-1", "page", "invalid"),
+        ("/api/v1/users?limit=abc", "limit", "invalid"),
+        ("/api/v1/users?page=1844674407370955162", "page", "range"),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(uri)
+                    .header("Authorization", token.clone())
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["ok"], false);
+        assert_eq!(json["details"][field][0]["code"], code, "{}", uri);
+        assert!(json["details"][field][0]["message"].is_string(), "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn test_api_v1_users_me_route_resolves_caller() {
+    use fldp_rust_backend_template::dtos::user::UserResponse;
+
+    let mut mock_user_service = MockUserService::new();
+    mock_user_service.expect_get_user()
+        .with(eq("123"))
+        .returning(|id| Ok(UserResponse {
+            id: id.to_string(),
+            username: "test".to_string(),
+            email: "test@test.com".to_string(),
+            role: "user".to_string(),
+            email_verified: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }));
+    mock_user_service.expect_get_mfa().returning(|_| Ok(None));
+
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().returning(|_| Ok(None));
+
+    let config = get_mock_config();
+    let token = bearer_token(&config, "123", "user");
+    let state = Arc::new(InnerState::new(
+        Arc::new(MockMongoProvider::new()),
+        config,
+        Arc::new(mock_redis),
+        Arc::new(mock_user_service),
+    ));
+
+    let app = init_routes(state.clone()).with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/users/me")
+                .header("Authorization", token)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["id"], "123");
+}
+
+#[tokio::test]
+async fn test_api_v1_user_update_route_other_user_forbidden() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().returning(|_| Ok(None));
+
+    let mut mock_user_service = MockUserService::new();
+    mock_user_service.expect_update_user().never();
+
+    let config = get_mock_config();
+    let token = bearer_token(&config, "123", "user");
+    let state = Arc::new(InnerState::new(
+        Arc::new(MockMongoProvider::new()),
+        config,
+        Arc::new(mock_redis),
+        Arc::new(mock_user_service),
+    ));
+
+    let app = init_routes(state.clone()).with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri("/api/v1/users/456")
+                .header("Authorization", token)
+                .header("Content-Type", "application/json")
+                .body(Body::from(r#"{"username":"stolen"}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_api_v1_user_get_route_missing_permission() {
+    let mut mock_redis = MockRedisProvider::new();
+    mock_redis.expect_get().returning(|_| Ok(None));
+
+    let config = get_mock_config();
+    let token = bearer_token(&config, "123", "guest");
+    let state = Arc::new(InnerState::new(
+        Arc::new(MockMongoProvider::new()),
+        config,
+        Arc::new(mock_redis),
+        Arc::new(MockUserService::new()),
+    ));
+
+    let app = init_routes(state.clone()).with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/users/123")
+                .header("Authorization", token)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["details"]["missingPermission"], "users:read");
+}
+
 #[tokio::test]
 async fn test_api_v1_auth_register_route() {
     let mut mock_user_service = MockUserService::new();
@@ -106,6 +593,7 @@ async fn test_api_v1_auth_register_route() {
             username: input.username,
             email: input.email,
             role: "user".to_string(),
+            email_verified: false,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         }));
@@ -185,6 +673,43 @@ async fn test_root_route() {
     assert_eq!(response.status(), StatusCode::OK);
 }
 
+#[tokio::test]
+async fn test_jwks_route() {
+    use fldp_rust_backend_template::config::JwtKeyConfig;
+
+    let mut config = get_mock_config();
+    config.jwt_keys = vec![JwtKeyConfig {
+        kid: "ed-1".into(),
+        algorithm: jsonwebtoken::Algorithm::EdDSA,
+        private_key_pem: None,
+        private_key_file: Some("tests/fixtures/keys/ed25519_private.pem".into()),
+        public_key_pem: None,
+        public_key_file: Some("tests/fixtures/keys/ed25519_public.pem".into()),
+    }];
+    config.jwt_signing_kid = Some("ed-1".into());
+
+    let state = Arc::new(InnerState::new(
+        Arc::new(MockMongoProvider::new()),
+        config,
+        Arc::new(MockRedisProvider::new()),
+        Arc::new(MockUserService::new()),
+    ));
+
+    let app = init_routes(state.clone()).with_state(state);
+
+    let response = app
+        .oneshot(Request::builder().uri("/.well-known/jwks.json").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["keys"][0]["kid"], "ed-1");
+    assert_eq!(json["keys"][0]["kty"], "OKP");
+    assert_eq!(json["keys"][0]["crv"], "Ed25519");
+}
+
 #[tokio::test]
 async fn test_ws_route() {
     let state = Arc::new(InnerState::new(
@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use fldp_rust_backend_template::services::api_key_service::{ApiKeyService, IApiKeyService};
+    use fldp_rust_backend_template::dtos::api_key::CreateApiKey;
+    use fldp_rust_backend_template::models::api_key::ApiKey;
+    use fldp_rust_backend_template::models::permission::Permission;
+    use fldp_rust_backend_template::error::AppError;
+    use fldp_rust_backend_template::mock::repositories::api_key_repository_mock::MockApiKeyRepository;
+    use fldp_rust_backend_template::utils::crypto::sha256_hex;
+    use std::sync::Arc;
+    use mockall::predicate::*;
+    use chrono::{Duration, Utc};
+
+    fn stored_key() -> ApiKey {
+        ApiKey {
+            id: "key_1".into(),
+            name: "batch".into(),
+            prefix: "sk_12345678".into(),
+            key_hash: sha256_hex("sk_secret"),
+            owner_id: "owner_1".into(),
+            scopes: vec![Permission::UsersRead],
+            expires_at: None,
+            last_used_at: None,
+            revoked_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_key_stores_only_hash() {
+        let mut mock_repo = MockApiKeyRepository::new();
+        mock_repo.expect_create()
+            .withf(|key| key.owner_id == "owner_1" && key.key_hash.len() == 64 && key.prefix.starts_with("sk_"))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = ApiKeyService::new(Arc::new(mock_repo));
+        let input = CreateApiKey {
+            name: "batch".into(),
+            owner_id: None,
+            scopes: vec![Permission::UsersRead],
+            expires_at: None,
+        };
+
+        let created = service.create_key("owner_1", input).await.unwrap();
+        assert!(created.key.starts_with(&created.api_key.prefix));
+        assert_eq!(created.key.len(), 67);
+    }
+
+    #[tokio::test]
+    async fn test_create_key_rejects_past_expiry() {
+        let mut mock_repo = MockApiKeyRepository::new();
+        mock_repo.expect_create().never();
+
+        let service = ApiKeyService::new(Arc::new(mock_repo));
+        let input = CreateApiKey {
+            name: "batch".into(),
+            owner_id: None,
+            scopes: vec![Permission::UsersRead],
+            expires_at: Some(Utc::now() - Duration::days(1)),
+        };
+
+        let result = service.create_key("owner_1", input).await;
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_valid_key_updates_last_used() {
+        let mut mock_repo = MockApiKeyRepository::new();
+        mock_repo.expect_find_by_hash()
+            .with(eq(sha256_hex("sk_secret")))
+            .returning(|_| Ok(Some(stored_key())));
+        mock_repo.expect_touch_last_used()
+            .withf(|id, _| id == "key_1")
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let service = ApiKeyService::new(Arc::new(mock_repo));
+        let key = service.authenticate("sk_secret").await.unwrap();
+        assert_eq!(key.owner_id, "owner_1");
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_recently_used_skips_write() {
+        let mut mock_repo = MockApiKeyRepository::new();
+        mock_repo.expect_find_by_hash().returning(|_| {
+            let mut key = stored_key();
+            key.last_used_at = Some(Utc::now());
+            Ok(Some(key))
+        });
+        mock_repo.expect_touch_last_used().never();
+
+        let service = ApiKeyService::new(Arc::new(mock_repo));
+        assert!(service.authenticate("sk_secret").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_rejects_revoked_and_expired() {
+        let mut mock_repo = MockApiKeyRepository::new();
+        mock_repo.expect_find_by_hash()
+            .with(eq(sha256_hex("sk_revoked")))
+            .returning(|_| {
+                let mut key = stored_key();
+                key.revoked_at = Some(Utc::now());
+                Ok(Some(key))
+            });
+        mock_repo.expect_find_by_hash()
+            .with(eq(sha256_hex("sk_expired")))
+            .returning(|_| {
+                let mut key = stored_key();
+                key.expires_at = Some(Utc::now() - Duration::minutes(1));
+                Ok(Some(key))
+            });
+        mock_repo.expect_touch_last_used().never();
+
+        let service = ApiKeyService::new(Arc::new(mock_repo));
+        assert!(matches!(service.authenticate("sk_revoked").await, Err(AppError::AuthError)));
+        assert!(matches!(service.authenticate("sk_expired").await, Err(AppError::AuthError)));
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_unknown_key() {
+        let mut mock_repo = MockApiKeyRepository::new();
+        mock_repo.expect_find_by_hash().returning(|_| Ok(None));
+
+        let service = ApiKeyService::new(Arc::new(mock_repo));
+        assert!(matches!(service.authenticate("sk_unknown").await, Err(AppError::AuthError)));
+        assert!(matches!(service.authenticate("not-a-key").await, Err(AppError::AuthError)));
+    }
+
+    #[tokio::test]
+    async fn test_revoke_key_not_found() {
+        let mut mock_repo = MockApiKeyRepository::new();
+        mock_repo.expect_revoke().returning(|_, _| Ok(false));
+
+        let service = ApiKeyService::new(Arc::new(mock_repo));
+        assert!(matches!(service.revoke_key("missing").await, Err(AppError::NotFound)));
+    }
+}
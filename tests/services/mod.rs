@@ -1 +1,2 @@
 pub mod user_service_test;
+pub mod api_key_service_test;
@@ -1,22 +1,25 @@
 #[cfg(test)]
 mod tests {
     use fldp_rust_backend_template::services::user_service::{UserService, IUserService};
-    use fldp_rust_backend_template::dtos::user::{CreateUser, UpdateUser};
-    use fldp_rust_backend_template::models::user::User;
+    use fldp_rust_backend_template::dtos::user::{ChangePassword, CreateUser, ExternalProfile, UpdateUser};
+    use fldp_rust_backend_template::models::user::{ExternalIdentity, Role, User, UserFilter};
     use fldp_rust_backend_template::mock::repositories::user_repository_mock::MockUserRepository;
     use std::sync::Arc;
     use mockall::predicate::*;
     use chrono::Utc;
+    use fldp_rust_backend_template::utils::blocking::BlockingPool;
+    use fldp_rust_backend_template::utils::pagination::Cursor;
+    use fldp_rust_backend_template::utils::password::{IPasswordHasher, PasswordHasher};
     use bcrypt::{hash, DEFAULT_COST};
 
     #[tokio::test]
     async fn test_create_user_logic() {
         let mut mock_repo = MockUserRepository::new();
         
-        mock_repo.expect_find_by_email()
+        mock_repo.expect_email_exists()
             .with(eq("test@example.com"))
             .times(1)
-            .returning(|_| Ok(None));
+            .returning(|_| Ok(false));
             
         mock_repo.expect_create()
             .times(1)
@@ -47,8 +50,12 @@ mod tests {
             email: "test@test.com".into(),
             password_hash: "hash".into(),
             role: "user".into(),
+            email_verified: false,
+            mfa: None,
+            identities: vec![],
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            deleted_at: None,
         };
 
         mock_repo.expect_find_by_id()
@@ -68,13 +75,13 @@ mod tests {
         let mut mock_repo = MockUserRepository::new();
         mock_repo.expect_find_all()
             .times(1)
-            .returning(|_, _| Ok(vec![]));
+            .returning(|_, _, _| Ok(vec![]));
         mock_repo.expect_count()
             .times(1)
-            .returning(|| Ok(0));
+            .returning(|_| Ok(0));
 
         let service = UserService::new(Arc::new(mock_repo));
-        let result = service.list_users(Some(1), Some(10)).await;
+        let result = service.list_users(UserFilter::default(), Some(1), Some(10)).await;
 
         assert!(result.is_ok());
         let paged = result.unwrap();
@@ -82,12 +89,108 @@ mod tests {
         assert_eq!(paged.data.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_list_users_rejects_overflowing_page() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_all().never();
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let result = service.list_users(UserFilter::default(), Some(u64::MAX), Some(10)).await;
+
+        assert!(matches!(result, Err(fldp_rust_backend_template::error::AppError::ValidationError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_list_users_counts_with_same_filter() {
+        let filter = UserFilter {
+            role: Some(Role::Admin),
+            search: Some("ali".into()),
+            ..Default::default()
+        };
+
+        let mut mock_repo = MockUserRepository::new();
+        let expected = filter.clone();
+        mock_repo.expect_find_all()
+            .withf(move |f, skip, limit| *f == expected && *skip == 20 && *limit == 10)
+            .times(1)
+            .returning(|_, _, _| Ok(vec![]));
+        let expected = filter.clone();
+        mock_repo.expect_count()
+            .withf(move |f| *f == expected)
+            .times(1)
+            .returning(|_| Ok(21));
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let paged = service.list_users(filter, Some(3), Some(10)).await.unwrap();
+        assert_eq!(paged.total, 21);
+        assert_eq!(paged.total_pages, 3);
+    }
+
+    fn user_created(id: &str, minutes_ago: i64) -> User {
+        User {
+            id: Some(id.into()),
+            created_at: Utc::now() - chrono::Duration::minutes(minutes_ago),
+            ..local_user()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_users_after_pages_with_cursor() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_page()
+            .withf(|_, after, limit| after.is_none() && *limit == 3)
+            .times(1)
+            .returning(|_, _, _| Ok(vec![user_created("u1", 1), user_created("u2", 2), user_created("u3", 3)]));
+        mock_repo.expect_find_page()
+            .withf(|_, after, _| after.as_ref().is_some_and(|c| c.id == "u2"))
+            .times(1)
+            .returning(|_, _, _| Ok(vec![user_created("u3", 3)]));
+        mock_repo.expect_count().never();
+
+        let service = UserService::new(Arc::new(mock_repo)).with_cursor_secret("secret");
+
+        let first = service.list_users_after(UserFilter::default(), None, Some(2)).await.unwrap();
+        assert_eq!(first.data.iter().map(|u| u.id.as_str()).collect::<Vec<_>>(), ["u1", "u2"]);
+        let next = first.next_cursor.expect("a second page");
+
+        let second = service.list_users_after(UserFilter::default(), Some(next), Some(2)).await.unwrap();
+        assert_eq!(second.data.len(), 1);
+        assert!(second.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_users_after_rejects_foreign_cursor() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_page().never();
+
+        let service = UserService::new(Arc::new(mock_repo)).with_cursor_secret("secret");
+        let forged = Cursor { created_at: Utc::now(), id: "u1".into() }.encode("other-secret");
+        assert!(matches!(
+            service.list_users_after(UserFilter::default(), Some(forged), None).await,
+            Err(fldp_rust_backend_template::error::AppError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_users_after_requires_created_at_sort() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_page().never();
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let filter = UserFilter { sort: "username:asc".parse().unwrap(), ..Default::default() };
+        assert!(matches!(
+            service.list_users_after(filter, None, None).await,
+            Err(fldp_rust_backend_template::error::AppError::ValidationError(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_update_user_success() {
         let mut mock_repo = MockUserRepository::new();
         let user_id = "user_123";
         
         mock_repo.expect_update()
+            .withf(|_, update| update.contains_key("updatedAt") && !update.contains_key("updated_at"))
             .times(1)
             .returning(|_, _| Ok(()));
 
@@ -114,14 +217,25 @@ mod tests {
             email: email.into(),
             password_hash,
             role: "user".into(),
+            email_verified: false,
+            mfa: None,
+            identities: vec![],
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            deleted_at: None,
         };
 
         mock_repo.expect_find_by_email()
             .with(eq(email))
             .times(1)
             .returning(move |_| Ok(Some(mock_user.clone())));
+        mock_repo.expect_update()
+            .withf(move |id, update| {
+                let new_hash = update.get_str("passwordHash").unwrap();
+                id == "id" && new_hash.starts_with("$argon2id$") && PasswordHasher::default().verify(password, new_hash)
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
 
         let service = UserService::new(Arc::new(mock_repo));
         let result = service.authenticate(email, password).await;
@@ -129,4 +243,478 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap().email, email);
     }
+
+    #[tokio::test]
+    async fn test_authenticate_current_hash_is_kept() {
+        let mut mock_repo = MockUserRepository::new();
+        let mock_user = User {
+            password_hash: PasswordHasher::default().hash("pass").unwrap(),
+            ..local_user()
+        };
+
+        mock_repo.expect_find_by_email()
+            .returning(move |_| Ok(Some(mock_user.clone())));
+        mock_repo.expect_update().never();
+
+        let service = UserService::new(Arc::new(mock_repo));
+        assert!(service.authenticate("test@test.com", "pass").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_survives_failed_rehash() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_by_email()
+            .returning(|_| Ok(Some(user_with_password("pass"))));
+        mock_repo.expect_update()
+            .times(1)
+            .returning(|_, _| Err(mongodb::error::Error::custom("write failed")));
+
+        let service = UserService::new(Arc::new(mock_repo));
+        assert!(service.authenticate("test@test.com", "pass").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_rejects_when_hashing_is_saturated() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_by_email()
+            .returning(|_| Ok(Some(user_with_password("pass"))));
+        mock_repo.expect_update().never();
+
+        let pool = BlockingPool::new(1, 0);
+        let (release, blocked) = std::sync::mpsc::channel::<()>();
+        let busy = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.run(move || blocked.recv().unwrap()).await }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let service = UserService::new(Arc::new(mock_repo)).with_blocking_pool(pool);
+        let result = service.authenticate("test@test.com", "pass").await;
+        assert!(matches!(result, Err(fldp_rust_backend_template::error::AppError::ServiceUnavailable)));
+
+        release.send(()).unwrap();
+        busy.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_wrong_password_does_not_rehash() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_by_email()
+            .returning(|_| Ok(Some(user_with_password("pass"))));
+        mock_repo.expect_update().never();
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let result = service.authenticate("test@test.com", "wrong").await;
+        assert!(matches!(result, Err(fldp_rust_backend_template::error::AppError::InvalidCredentials)));
+    }
+
+    #[tokio::test]
+    async fn test_set_password_rehashes() {
+        let mut mock_repo = MockUserRepository::new();
+        let mock_user = User {
+            id: Some("user_123".into()),
+            username: "test".into(),
+            email: "test@test.com".into(),
+            password_hash: "old_hash".into(),
+            role: "user".into(),
+            email_verified: false,
+            mfa: None,
+            identities: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+        };
+
+        mock_repo.expect_find_by_id()
+            .with(eq("user_123"))
+            .times(1)
+            .returning(move |_| Ok(Some(mock_user.clone())));
+        mock_repo.expect_update()
+            .withf(|id, update| {
+                let new_hash = update.get_str("passwordHash").unwrap();
+                id == "user_123" && PasswordHasher::default().verify("new_password", new_hash)
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let service = UserService::new(Arc::new(mock_repo));
+        assert!(service.set_password("user_123", "new_password").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_set_password_unknown_user() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_by_id().returning(|_| Ok(None));
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let result = service.set_password("missing", "new_password").await;
+        assert!(matches!(result, Err(fldp_rust_backend_template::error::AppError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_requires_verified_email() {
+        let mut mock_repo = MockUserRepository::new();
+        let password_hash = hash("pass", DEFAULT_COST).unwrap();
+        let mock_user = User {
+            id: Some("id".into()),
+            username: "test".into(),
+            email: "test@test.com".into(),
+            password_hash,
+            role: "user".into(),
+            email_verified: false,
+            mfa: None,
+            identities: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+        };
+
+        mock_repo.expect_find_by_email()
+            .returning(move |_| Ok(Some(mock_user.clone())));
+
+        let service = UserService::new(Arc::new(mock_repo)).with_email_verification_required(true);
+        let result = service.authenticate("test@test.com", "pass").await;
+
+        assert!(matches!(result, Err(fldp_rust_backend_template::error::AppError::EmailNotVerified)));
+    }
+
+    #[tokio::test]
+    async fn test_update_user_email_resets_verification() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_email_exists().returning(|_| Ok(false));
+        mock_repo.expect_update()
+            .withf(|_, update| update.get_bool("emailVerified") == Ok(false))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let input = UpdateUser {
+            username: None,
+            email: Some("new@test.com".into()),
+        };
+
+        assert!(service.update_user("user_123", input).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_mark_email_verified() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_update()
+            .withf(|id, update| id == "user_123" && update.get_bool("emailVerified") == Ok(true))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let service = UserService::new(Arc::new(mock_repo));
+        assert!(service.mark_email_verified("user_123").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_replace_mfa_only_when_unchanged() {
+        use fldp_rust_backend_template::error::AppError;
+        use fldp_rust_backend_template::models::user::UserMfa;
+
+        let current = UserMfa {
+            enabled: true,
+            secret: "encrypted".into(),
+            recovery_codes: vec!["hash_1".into()],
+            last_used_step: Some(10),
+        };
+        let next = UserMfa { last_used_step: Some(11), ..current.clone() };
+
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_update_if()
+            .withf(|id, condition, update| {
+                id == "user_123"
+                    && condition.get_i64("mfa.lastUsedStep") == Ok(10)
+                    && condition.get_array("mfa.recoveryCodes").is_ok_and(|codes| codes.len() == 1)
+                    && update.get_document("mfa").is_ok_and(|mfa| mfa.get_i64("lastUsedStep") == Ok(11))
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(true));
+        mock_repo.expect_update_if()
+            .times(1)
+            .returning(|_, _, _| Ok(false));
+
+        let service = UserService::new(Arc::new(mock_repo));
+        assert!(service.replace_mfa("user_123", &current, Some(next.clone())).await.is_ok());
+        assert!(matches!(
+            service.replace_mfa("user_123", &current, Some(next)).await,
+            Err(AppError::AuthError)
+        ));
+    }
+
+    fn external_profile(email_verified: bool) -> ExternalProfile {
+        ExternalProfile {
+            provider: "google".into(),
+            subject: "sub_1".into(),
+            email: Some("test@test.com".into()),
+            email_verified,
+            name: Some("Test User".into()),
+        }
+    }
+
+    fn local_user() -> User {
+        User {
+            id: Some("user_123".into()),
+            username: "test".into(),
+            email: "test@test.com".into(),
+            password_hash: "hash".into(),
+            role: "user".into(),
+            email_verified: false,
+            mfa: None,
+            identities: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_or_create_external_linked_identity() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_by_identity()
+            .with(eq("google"), eq("sub_1"))
+            .returning(|_, _| Ok(Some(local_user())));
+        mock_repo.expect_find_by_email().never();
+        mock_repo.expect_update().never();
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let user = service.find_or_create_external(external_profile(false)).await.unwrap();
+        assert_eq!(user.id.as_deref(), Some("user_123"));
+    }
+
+    #[tokio::test]
+    async fn test_find_or_create_external_links_by_email() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_by_identity().returning(|_, _| Ok(None));
+        mock_repo.expect_find_by_email()
+            .with(eq("test@test.com"))
+            .returning(|_| Ok(Some(User { email_verified: true, ..local_user() })));
+        mock_repo.expect_update()
+            .withf(|id, doc| id == "user_123" && doc.get_array("identities").unwrap().len() == 1)
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let user = service.find_or_create_external(external_profile(true)).await.unwrap();
+        assert!(user.email_verified);
+        assert_eq!(user.identities, vec![ExternalIdentity { provider: "google".into(), subject: "sub_1".into() }]);
+    }
+
+    #[tokio::test]
+    async fn test_find_or_create_external_refuses_unverified_local_account() {
+        // Someone registered the address first and never verified it.
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_by_identity().returning(|_, _| Ok(None));
+        mock_repo.expect_find_by_email()
+            .with(eq("test@test.com"))
+            .returning(|_| Ok(Some(local_user())));
+        mock_repo.expect_update().never();
+        mock_repo.expect_create().never();
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let result = service.find_or_create_external(external_profile(true)).await;
+        assert!(matches!(result, Err(fldp_rust_backend_template::error::AppError::ValidationError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_find_or_create_external_creates_user() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_by_identity().returning(|_, _| Ok(None));
+        mock_repo.expect_find_by_email().returning(|_| Ok(None));
+        mock_repo.expect_email_exists().returning(|_| Ok(false));
+        mock_repo.expect_create()
+            .withf(|user| user.email_verified && user.username == "Test User" && user.identities.len() == 1)
+            .times(1)
+            .returning(|_| Ok("new_id".to_string()));
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let user = service.find_or_create_external(external_profile(true)).await.unwrap();
+        assert_eq!(user.role, "user");
+    }
+
+    #[tokio::test]
+    async fn test_find_or_create_external_requires_verified_email() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_by_identity().returning(|_, _| Ok(None));
+        mock_repo.expect_find_by_email().never();
+        mock_repo.expect_create().never();
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let result = service.find_or_create_external(external_profile(false)).await;
+        assert!(matches!(result, Err(fldp_rust_backend_template::error::AppError::ValidationError(_))));
+    }
+
+    fn user_with_password(password: &str) -> User {
+        User {
+            password_hash: hash(password, 4).unwrap(),
+            ..local_user()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_change_password_success() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_by_id()
+            .with(eq("user_123"))
+            .returning(|_| Ok(Some(user_with_password("old_password"))));
+        mock_repo.expect_update()
+            .withf(|id, doc| {
+                id == "user_123"
+                    && PasswordHasher::default().verify("new_password", doc.get_str("passwordHash").unwrap())
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let input = ChangePassword {
+            current_password: "old_password".into(),
+            new_password: "new_password".into(),
+        };
+        let user = service.change_password("user_123", input).await.unwrap();
+        assert_eq!(user.id, "user_123");
+    }
+
+    #[tokio::test]
+    async fn test_change_password_wrong_current() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_by_id()
+            .returning(|_| Ok(Some(user_with_password("old_password"))));
+        mock_repo.expect_update().never();
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let input = ChangePassword {
+            current_password: "guess".into(),
+            new_password: "new_password".into(),
+        };
+        let result = service.change_password("user_123", input).await;
+        assert!(matches!(result, Err(fldp_rust_backend_template::error::AppError::InvalidCredentials)));
+    }
+
+    #[tokio::test]
+    async fn test_change_password_same_as_current() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_by_id()
+            .returning(|_| Ok(Some(user_with_password("old_password"))));
+        mock_repo.expect_update().never();
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let input = ChangePassword {
+            current_password: "old_password".into(),
+            new_password: "old_password".into(),
+        };
+        assert!(service.change_password("user_123", input).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_user_email_held_by_deleted_user() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_email_exists().returning(|_| Ok(true));
+        mock_repo.expect_create().never();
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let input = CreateUser {
+            username: "testuser".to_string(),
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+        };
+        assert!(matches!(
+            service.create_user(input).await,
+            Err(fldp_rust_backend_template::error::AppError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_find_or_create_external_deleted_account() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_by_identity().returning(|_, _| Ok(None));
+        mock_repo.expect_find_by_email().returning(|_| Ok(None));
+        mock_repo.expect_email_exists().returning(|_| Ok(true));
+        mock_repo.expect_create().never();
+
+        let service = UserService::new(Arc::new(mock_repo));
+        assert!(matches!(
+            service.find_or_create_external(external_profile(true)).await,
+            Err(fldp_rust_backend_template::error::AppError::PermissionDenied)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_delete_user() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_soft_delete()
+            .withf(|id, _| id == "user_123")
+            .times(1)
+            .returning(|_, _| Ok(true));
+
+        let service = UserService::new(Arc::new(mock_repo));
+        assert!(service.delete_user("user_123").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_delete_user_already_deleted() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_soft_delete().returning(|_, _| Ok(false));
+
+        let service = UserService::new(Arc::new(mock_repo));
+        assert!(matches!(
+            service.delete_user("user_123").await,
+            Err(fldp_rust_backend_template::error::AppError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_restore_user() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_restore().times(1).returning(|_, _| Ok(true));
+        mock_repo.expect_find_by_id()
+            .with(eq("user_123"))
+            .returning(|_| Ok(Some(local_user())));
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let user = service.restore_user("user_123").await.unwrap();
+        assert_eq!(user.id, "user_123");
+    }
+
+    #[tokio::test]
+    async fn test_purge_user_after_retention() {
+        let cutoff = Utc::now() - chrono::Duration::days(30);
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_purge().with(eq("user_123"), eq(cutoff)).times(1).returning(|_, _| Ok(true));
+        mock_repo.expect_find_deleted_by_id().never();
+
+        let service = UserService::new(Arc::new(mock_repo));
+        assert!(service.purge_user("user_123", cutoff).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_purge_user_within_retention() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_deleted_by_id().returning(|_| {
+            let mut user = local_user();
+            user.deleted_at = Some(Utc::now() - chrono::Duration::days(1));
+            Ok(Some(user))
+        });
+        mock_repo.expect_purge().times(1).returning(|_, _| Ok(false));
+
+        let service = UserService::new(Arc::new(mock_repo));
+        let cutoff = Utc::now() - chrono::Duration::days(30);
+        assert!(matches!(
+            service.purge_user("user_123", cutoff).await,
+            Err(fldp_rust_backend_template::error::AppError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_purge_user_not_deleted() {
+        let mut mock_repo = MockUserRepository::new();
+        mock_repo.expect_find_deleted_by_id().returning(|_| Ok(None));
+        mock_repo.expect_purge().times(1).returning(|_, _| Ok(false));
+
+        let service = UserService::new(Arc::new(mock_repo));
+        assert!(matches!(
+            service.purge_user("user_123", Utc::now()).await,
+            Err(fldp_rust_backend_template::error::AppError::NotFound)
+        ));
+    }
 }